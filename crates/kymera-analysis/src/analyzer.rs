@@ -1,11 +1,12 @@
 use anyhow::{Context, Result as AnalyzerResult};
 use kymera_parser::ast::{
-    AstNode, Expression, Statement, Function, Struct, Enum, Declaration, Assignment,
+    AstNode, Attribute, Expression, Statement, Function, Struct, Enum, Declaration, Assignment,
+    Implementation, Match, Pattern, Mutability, TypeExpr, TryCatch, VariantPayload, StructLiteral,
 };
 
 use crate::err::AnalysisError;
 use crate::types::{Type, TypeChecker, FunctionType, StructType, EnumType};
-use crate::symbols::{AnalysisSymbol, AnalysisTable, SymbolKind, Visibility};
+use crate::symbols::{AnalysisSymbol, AnalysisTable, SymbolKind, SymbolMetadata, Visibility};
 
 /// Main analyzer for Kymera code
 #[derive(Debug)]
@@ -14,6 +15,10 @@ pub struct Analyzer {
     symbols: AnalysisTable,
     /// Type checker for type inference and validation
     type_checker: TypeChecker,
+    /// Whether the function being analyzed is `xnc`
+    in_async: bool,
+    /// Generic parameters of the function being analyzed
+    generics: Vec<String>,
 }
 
 impl Analyzer {
@@ -22,6 +27,8 @@ impl Analyzer {
         Self {
             symbols: AnalysisTable::new(),
             type_checker: TypeChecker::new(),
+            in_async: false,
+            generics: Vec::new(),
         }
     }
 
@@ -38,26 +45,13 @@ impl Analyzer {
         Ok(())
     }
 
-    /// First pass: collect all declarations to build symbol table
+    /// First pass: collect all declarations to build symbol table. Structs
+    /// and enums are collected before functions so that signatures can name
+    /// types declared later in the file.
     fn collect_declarations(&mut self, nodes: &[AstNode]) -> AnalyzerResult<()> {
         for node in nodes {
             if let AstNode::Statement(stmt) = node {
                 match stmt {
-                    Statement::Function(func) => {
-                        let symbol = AnalysisSymbol {
-                            name: func.name.clone(),
-                            kind: SymbolKind::Function,
-                            ty: self.function_type(func)
-                                .context("Failed to determine function type")?,
-                            scope_level: self.symbols.current_level(),
-                            documentation: None,
-                            metadata: Default::default(),
-                            visibility: Visibility::Public,
-                            is_mutable: false,
-                        };
-                        self.symbols.define(symbol)
-                            .with_context(|| format!("Failed to define function symbol: {}", func.name))?;
-                    },
                     Statement::Struct(struct_def) => {
                         let symbol = AnalysisSymbol {
                             name: struct_def.name.clone(),
@@ -66,7 +60,7 @@ impl Analyzer {
                                 .context("Failed to determine struct type")?,
                             scope_level: self.symbols.current_level(),
                             documentation: None,
-                            metadata: Default::default(),
+                            metadata: Self::item_metadata(&struct_def.attributes),
                             visibility: Visibility::Public,
                             is_mutable: false,
                         };
@@ -81,7 +75,7 @@ impl Analyzer {
                                 .context("Failed to determine enum type")?,
                             scope_level: self.symbols.current_level(),
                             documentation: None,
-                            metadata: Default::default(),
+                            metadata: Self::item_metadata(&enum_def.attributes),
                             visibility: Visibility::Public,
                             is_mutable: false,
                         };
@@ -92,9 +86,36 @@ impl Analyzer {
                 }
             }
         }
+        for node in nodes {
+            if let AstNode::Statement(Statement::Function(func)) = node {
+                let symbol = AnalysisSymbol {
+                    name: func.name.clone(),
+                    kind: SymbolKind::Function,
+                    ty: self.function_type(func)
+                        .context("Failed to determine function type")?,
+                    scope_level: self.symbols.current_level(),
+                    documentation: None,
+                    metadata: Self::item_metadata(&func.attributes),
+                    visibility: Visibility::Public,
+                    is_mutable: false,
+                };
+                self.symbols.define(symbol)
+                    .with_context(|| format!("Failed to define function symbol: {}", func.name))?;
+            }
+        }
         Ok(())
     }
 
+    /// Builds the metadata of an item from its attributes
+    fn item_metadata(attributes: &[Attribute]) -> SymbolMetadata {
+        let deprecated = attributes.iter().find(|attribute| attribute.name == "obsoleto");
+        SymbolMetadata {
+            is_deprecated: deprecated.is_some(),
+            deprecation_message: deprecated.and_then(Attribute::message).map(str::to_string),
+            ..Default::default()
+        }
+    }
+
     /// Second pass: analyze all nodes
     fn analyze_nodes(&mut self, nodes: &[AstNode]) -> AnalyzerResult<()> {
         for node in nodes {
@@ -114,18 +135,25 @@ impl Analyzer {
                     .with_context(|| format!("Failed to analyze struct: {}", struct_def.name)),
                 Statement::Enum(enum_def) => self.analyze_enum(enum_def)
                     .with_context(|| format!("Failed to analyze enum: {}", enum_def.name)),
+                Statement::Implementation(imp) => self.analyze_implementation(imp)
+                    .with_context(|| format!("Failed to analyze implementation for: {}", imp.target)),
                 Statement::Declaration(decl) => self.analyze_declaration(decl)
-                    .with_context(|| format!("Failed to analyze declaration: {}", decl.name)),
+                    .context("Failed to analyze declaration"),
                 Statement::Assignment(assign) => self.analyze_assignment(assign)
-                    .with_context(|| format!("Failed to analyze assignment to: {}", assign.name)),
+                    .context("Failed to analyze assignment"),
                 Statement::Block(statements, _) => self.analyze_block(statements)
                     .context("Failed to analyze block"),
+                Statement::SyncBlock(block) => self.analyze_block(&block.body)
+                    .context("Failed to analyze snc block"),
+                Statement::TryCatch(try_catch) => self.analyze_try_catch(try_catch)
+                    .context("Failed to analyze ate/rev block"),
                 Statement::Expression(expr) => self.analyze_expression(expr)
                     .context("Failed to analyze expression"),
                 _ => Ok(Type::Unit), // Other statement types return unit
             },
             AstNode::Expression(expr) => self.analyze_expression(expr)
                 .context("Failed to analyze expression"),
+            AstNode::Error(_) => Ok(Type::Unknown), // Already reported by the parser
         }
     }
 
@@ -133,28 +161,33 @@ impl Analyzer {
     fn analyze_function(&mut self, func: &Function) -> AnalyzerResult<Type> {
         // Push new scope for function body
         self.symbols.push_scope();
+        let outer_generics = std::mem::replace(&mut self.generics, Self::generic_names(func));
 
         // Add parameters to scope
         for param in &func.params {
             let symbol = AnalysisSymbol {
-                name: param.clone(),
+                name: param.name.clone(),
                 kind: SymbolKind::Parameter,
-                ty: Type::Unknown, // Parameters have unknown type until type inference
+                ty: self.declared_type(param.ty.as_ref())
+                    .with_context(|| format!("Failed to parse type of parameter: {}", param.name))?,
                 scope_level: self.symbols.current_level(),
                 documentation: None,
                 metadata: Default::default(),
                 visibility: Visibility::Private,
-                is_mutable: false,
+                is_mutable: param.mutability == Mutability::Mutable,
             };
             self.symbols.define(symbol)
-                .with_context(|| format!("Failed to define parameter symbol: {}", param))?;
+                .with_context(|| format!("Failed to define parameter symbol: {}", param.name))?;
         }
 
         // Analyze function body
+        let outer_async = std::mem::replace(&mut self.in_async, func.is_async);
         let mut body_type = Type::Unit;
         for stmt in &func.body {
             body_type = self.analyze_node(stmt)?;
         }
+        self.in_async = outer_async;
+        self.generics = outer_generics;
 
         // Pop function scope
         self.symbols.pop_scope()
@@ -163,6 +196,67 @@ impl Analyzer {
         Ok(body_type)
     }
 
+    /// Analyzes an implementation block
+    fn analyze_implementation(&mut self, imp: &Implementation) -> AnalyzerResult<Type> {
+        // Methods see `soy` as a value of the implementing type
+        let target_type = match &imp.target {
+            TypeExpr::Named(named) => named.path.segments.last()
+                .and_then(|name| self.symbols.lookup(name).ok())
+                .map(|symbol| symbol.ty.clone())
+                .unwrap_or(Type::Unknown),
+            other => self.resolve_type(other).unwrap_or(Type::Unknown),
+        };
+
+        self.symbols.push_scope();
+        let symbol = AnalysisSymbol {
+            name: "soy".to_string(),
+            kind: SymbolKind::Parameter,
+            ty: target_type,
+            scope_level: self.symbols.current_level(),
+            documentation: None,
+            metadata: Default::default(),
+            visibility: Visibility::Private,
+            is_mutable: false,
+        };
+        self.symbols.define(symbol)
+            .context("Failed to define receiver symbol")?;
+
+        for method in &imp.methods {
+            self.analyze_function(method)
+                .with_context(|| format!("Failed to analyze method: {}", method.name))?;
+        }
+
+        self.symbols.pop_scope()
+            .context("Failed to pop implementation scope")?;
+
+        Ok(Type::Unit)
+    }
+
+    /// Analyzes an `ate`/`rev` error handling block
+    fn analyze_try_catch(&mut self, try_catch: &TryCatch) -> AnalyzerResult<Type> {
+        self.analyze_block(&try_catch.body)?;
+
+        // The handler sees the error bound to its variable
+        self.symbols.push_scope();
+        let symbol = AnalysisSymbol {
+            name: try_catch.binding.clone(),
+            kind: SymbolKind::Variable,
+            ty: Type::Unknown,
+            scope_level: self.symbols.current_level(),
+            documentation: None,
+            metadata: Default::default(),
+            visibility: Visibility::Private,
+            is_mutable: false,
+        };
+        self.symbols.define(symbol)
+            .with_context(|| format!("Failed to define error binding: {}", try_catch.binding))?;
+        self.analyze_nodes(&try_catch.handler)?;
+        self.symbols.pop_scope()
+            .context("Failed to pop handler scope")?;
+
+        Ok(Type::Unit)
+    }
+
     /// Analyzes a block of statements
     fn analyze_block(&mut self, statements: &[AstNode]) -> AnalyzerResult<Type> {
         self.symbols.push_scope();
@@ -180,26 +274,87 @@ impl Analyzer {
 
     /// Derives the type of a function declaration
     fn function_type(&mut self, func: &Function) -> AnalyzerResult<Type> {
-        // For now, all functions return Unit and take Unknown type parameters
-        let param_types = vec![Type::Unknown; func.params.len()];
-        let return_type = Type::Unit;
+        let outer_generics = std::mem::replace(&mut self.generics, Self::generic_names(func));
+        let signature = self.signature_types(func);
+        self.generics = outer_generics;
+        let (param_types, return_type) = signature?;
 
         Ok(Type::Function(FunctionType {
             params: param_types,
             return_type: Box::new(return_type),
-            type_params: vec![],
+            type_params: Self::generic_names(func),
         }))
     }
 
+    /// Resolves the parameter and return types of a function
+    fn signature_types(&mut self, func: &Function) -> AnalyzerResult<(Vec<Type>, Type)> {
+        let param_types = func.params.iter()
+            .map(|param| self.declared_type(param.ty.as_ref())
+                .with_context(|| format!("Failed to parse type of parameter: {}", param.name)))
+            .collect::<AnalyzerResult<Vec<_>>>()?;
+        let return_type = match &func.return_type {
+            Some(type_expr) => self.resolve_type(type_expr)
+                .with_context(|| format!("Failed to parse return type: {}", type_expr))?,
+            None => Type::Unit,
+        };
+        Ok((param_types, return_type))
+    }
+
+    /// Returns the names of a function's generic parameters
+    fn generic_names(func: &Function) -> Vec<String> {
+        func.generics.iter().map(|generic| generic.name.clone()).collect()
+    }
+
+    /// Resolves an optional type annotation; unannotated bindings are left
+    /// for type inference
+    fn declared_type(&mut self, type_expr: Option<&TypeExpr>) -> AnalyzerResult<Type> {
+        match type_expr {
+            Some(type_expr) => self.resolve_type(type_expr),
+            None => Ok(Type::Unknown),
+        }
+    }
+
+    /// Converts a type annotation into a Type, looking named types up in the
+    /// symbol table. Names of the current function's generic parameters stay
+    /// generic; other names that are not a declared struct or enum, such as
+    /// imported types, are left unknown.
+    fn resolve_type(&mut self, type_expr: &TypeExpr) -> AnalyzerResult<Type> {
+        let ty = Type::from_type_expr(type_expr)?;
+        Ok(self.resolve_names(ty))
+    }
+
+    /// Replaces the named types within a type by what they refer to
+    fn resolve_names(&mut self, ty: Type) -> Type {
+        match ty {
+            Type::Generic(name) if self.generics.contains(&name) => Type::Generic(name),
+            Type::Generic(name) => match self.symbols.lookup(&name) {
+                Ok(symbol) if symbol.kind == SymbolKind::Type => symbol.ty.clone(),
+                _ => Type::Unknown,
+            },
+            Type::Array(element) => Type::Array(Box::new(self.resolve_names(*element))),
+            Type::Option(inner) => Type::Option(Box::new(self.resolve_names(*inner))),
+            Type::Result(ok, err) => Type::Result(
+                Box::new(self.resolve_names(*ok)),
+                Box::new(self.resolve_names(*err)),
+            ),
+            Type::Function(function) => Type::Function(FunctionType {
+                params: function.params.into_iter().map(|param| self.resolve_names(param)).collect(),
+                return_type: Box::new(self.resolve_names(*function.return_type)),
+                type_params: function.type_params,
+            }),
+            other => other,
+        }
+    }
+
     /// Derives the type of a struct declaration
     fn struct_type(&mut self, struct_def: &Struct) -> AnalyzerResult<Type> {
         let mut fields = Vec::new();
         
-        // Parse field types from the type strings
-        for (name, type_str) in &struct_def.fields {
-            let field_type = Type::parse(type_str)
-                .with_context(|| format!("Failed to parse field type for {}: {}", name, type_str))?;
-            fields.push((name.clone(), field_type));
+        // Convert the declared field types
+        for field in &struct_def.fields {
+            let field_type = self.resolve_type(&field.ty)
+                .with_context(|| format!("Failed to parse field type for {}: {}", field.name, field.ty))?;
+            fields.push((field.name.clone(), field_type));
         }
         
         Ok(Type::Struct(StructType {
@@ -211,9 +366,30 @@ impl Analyzer {
 
     /// Derives the type of an enum declaration
     fn enum_type(&mut self, enum_def: &Enum) -> AnalyzerResult<Type> {
-        let variants = enum_def.variants.iter()
-            .map(|name| (name.clone(), None))
-            .collect();
+        let mut variants = Vec::new();
+        for variant in &enum_def.variants {
+            let payload = match &variant.payload {
+                VariantPayload::Unit => None,
+                VariantPayload::Tuple(types) if types.len() == 1 => Some(self.resolve_type(&types[0])
+                    .with_context(|| format!("Failed to parse payload of variant: {}", variant.name))?),
+                // Multi-field payloads are modeled as structs with positional field names
+                VariantPayload::Tuple(types) => {
+                    let fields = types.iter().enumerate()
+                        .map(|(index, ty)| self.resolve_type(ty).map(|ty| (index.to_string(), ty)))
+                        .collect::<AnalyzerResult<Vec<_>>>()
+                        .with_context(|| format!("Failed to parse payload of variant: {}", variant.name))?;
+                    Some(Type::Struct(StructType { name: variant.name.clone(), fields, type_params: vec![] }))
+                },
+                VariantPayload::Struct(fields) => {
+                    let fields = fields.iter()
+                        .map(|field| self.resolve_type(&field.ty).map(|ty| (field.name.clone(), ty)))
+                        .collect::<AnalyzerResult<Vec<_>>>()
+                        .with_context(|| format!("Failed to parse payload of variant: {}", variant.name))?;
+                    Some(Type::Struct(StructType { name: variant.name.clone(), fields, type_params: vec![] }))
+                },
+            };
+            variants.push((variant.name.clone(), payload));
+        }
         
         Ok(Type::Enum(EnumType {
             name: enum_def.name.clone(),
@@ -234,46 +410,94 @@ impl Analyzer {
 
     /// Analyzes a variable declaration
     fn analyze_declaration(&mut self, decl: &Declaration) -> AnalyzerResult<Type> {
-        let var_type = self.type_checker.infer_literal(&decl.value);
-        
-        let symbol = AnalysisSymbol {
-            name: decl.name.clone(),
-            kind: SymbolKind::Variable,
-            ty: var_type.clone(),
-            scope_level: self.symbols.current_level(),
-            documentation: None,
-            metadata: Default::default(),
-            visibility: Visibility::Private,
-            is_mutable: false,
+        let value_type = self.analyze_node(&decl.value)?;
+        let var_type = match &decl.ty {
+            Some(type_expr) => {
+                let declared = self.resolve_type(type_expr)
+                    .with_context(|| format!("Failed to parse declared type: {}", type_expr))?;
+                if !self.type_checker.can_coerce(&value_type, &declared) {
+                    return Err(AnalysisError::type_error(format!(
+                        "Cannot initialize variable of type {} with value of type {}",
+                        declared, value_type
+                    ))
+                    .at(decl.value.span(), format!("this has type {}", value_type))
+                    .with_note_at(type_expr.span(), format!("expected {} because of this annotation", declared)))
+                    .context("Type mismatch in declaration");
+                }
+                declared
+            },
+            None => value_type,
         };
-        self.symbols.define(symbol)
-            .with_context(|| format!("Failed to define variable symbol: {}", decl.name))?;
+        let is_mutable = decl.mutability == Mutability::Mutable;
+
+        match &decl.pattern {
+            Pattern::Identifier(name, _) => {
+                let symbol = AnalysisSymbol {
+                    name: name.clone(),
+                    kind: SymbolKind::Variable,
+                    ty: var_type.clone(),
+                    scope_level: self.symbols.current_level(),
+                    documentation: None,
+                    metadata: Default::default(),
+                    visibility: Visibility::Private,
+                    is_mutable,
+                };
+                self.symbols.define(symbol)
+                    .with_context(|| format!("Failed to define variable symbol: {}", name))?;
+            },
+            pattern => self.define_pattern_bindings(pattern, &var_type, is_mutable)?,
+        }
         
         Ok(var_type)
     }
 
     /// Analyzes an assignment
     fn analyze_assignment(&mut self, assign: &Assignment) -> AnalyzerResult<Type> {
-        let symbol = self.symbols.lookup(&assign.name)?;
-        let value_type = self.analyze_node(&assign.value)?;
-        
-        if !symbol.is_mutable {
-            return Err(AnalysisError::semantic_error(format!(
-                "Cannot assign to immutable variable {}",
-                assign.name
-            ))).context("Assignment to immutable variable");
+        // Assigning through a field or index still requires a mutable root
+        if let Some(name) = Self::assignment_root(&assign.target) {
+            let symbol = self.symbols.lookup(name)?;
+            if !symbol.is_mutable {
+                return Err(AnalysisError::semantic_error(format!(
+                    "Cannot assign to immutable variable {}",
+                    name
+                ))
+                .at(assign.target.span(), "cannot assign to this")
+                .with_help(format!("declare it as `djq muta {}` to allow assignment", name)))
+                .context("Assignment to immutable variable");
+            }
+        }
+
+        let target_type = self.analyze_node(&assign.target)?;
+        let mut value_type = self.analyze_node(&assign.value)?;
+
+        // `x op= v` is checked as `x = x op v`
+        if let Some(op) = assign.op.strip_suffix('=').filter(|op| !op.is_empty()) {
+            value_type = self.type_checker.check_binary_op(&target_type, op, &value_type)?;
         }
         
-        if !self.type_checker.can_coerce(&value_type, &symbol.ty) {
+        if !self.type_checker.can_coerce(&value_type, &target_type) {
             return Err(AnalysisError::type_error(format!(
-                "Cannot assign value of type {} to variable {} of type {}",
-                value_type, assign.name, symbol.ty
-            ))).context("Type mismatch in assignment");
+                "Cannot assign value of type {} to a place of type {}",
+                value_type, target_type
+            ))
+            .at(assign.value.span(), format!("this has type {}", value_type))
+            .with_note_at(assign.target.span(), format!("this has type {}", target_type)))
+            .context("Type mismatch in assignment");
         }
         
         Ok(Type::Unit)
     }
 
+    /// Returns the variable an assignment target ultimately writes to
+    fn assignment_root(target: &AstNode) -> Option<&str> {
+        match target {
+            AstNode::Expression(Expression::Identifier(name, _)) => Some(name),
+            AstNode::Expression(Expression::FieldAccess(access)) => Self::assignment_root(&access.object),
+            AstNode::Expression(Expression::ArrayAccess(access)) => Self::assignment_root(&access.array),
+            _ => None,
+        }
+    }
+
     /// Analyzes an expression
     fn analyze_expression(&mut self, expr: &Expression) -> AnalyzerResult<Type> {
         match expr {
@@ -282,6 +506,43 @@ impl Analyzer {
                 let symbol = self.symbols.lookup(name)?;
                 Ok(symbol.ty.clone())
             },
+            Expression::Path(path) => {
+                let symbol = self.symbols.lookup(&path.to_string())?;
+                Ok(symbol.ty.clone())
+            },
+            Expression::Array(elements, _) => {
+                let mut element_type = Type::Unknown;
+                let mut typed_element = None;
+                for element in elements {
+                    let ty = self.analyze_node(element)?;
+                    if element_type == Type::Unknown {
+                        element_type = ty;
+                        typed_element = Some(element);
+                    } else if !self.type_checker.can_coerce(&ty, &element_type) {
+                        let mut error = AnalysisError::type_error(format!(
+                            "Array elements must share a type: expected {}, got {}",
+                            element_type, ty
+                        ))
+                        .at(element.span(), format!("this has type {}", ty));
+                        if let Some(first) = typed_element {
+                            error = error.with_note_at(first.span(), format!("expected {} because of this element", element_type));
+                        }
+                        return Err(error).context("Mixed array element types");
+                    }
+                }
+                Ok(Type::Array(Box::new(element_type)))
+            },
+            Expression::Tuple(elements, _) => {
+                // Tuples have no type of their own yet; the unit tuple is `()`
+                self.analyze_nodes(elements)?;
+                Ok(if elements.is_empty() { Type::Unit } else { Type::Unknown })
+            },
+            Expression::StructLiteral(literal) => self.analyze_struct_literal(literal)
+                .with_context(|| format!("Failed to analyze construction of: {}", literal.path)),
+            Expression::SelfRef(_) => {
+                let symbol = self.symbols.lookup("soy")?;
+                Ok(symbol.ty.clone())
+            },
             Expression::BinaryOp(op) => {
                 let left_type = self.analyze_node(&op.left)?;
                 let right_type = self.analyze_node(&op.right)?;
@@ -292,14 +553,17 @@ impl Analyzer {
                 self.type_checker.check_unary_op(&op.op, &expr_type)
             },
             Expression::FunctionCall(call) => {
-                let callee_symbol = self.symbols.lookup(&call.name)?;
-                match &callee_symbol.ty {
+                let callee_type = self.analyze_node(&call.callee)?;
+                match &callee_type {
                     Type::Function(ft) => {
                         if call.args.len() != ft.params.len() {
                             return Err(AnalysisError::type_error(format!(
-                                "Function {} expects {} arguments but got {}",
-                                call.name, ft.params.len(), call.args.len()
-                            ))).context("Argument count mismatch");
+                                "Function of type {} expects {} arguments but got {}",
+                                callee_type, ft.params.len(), call.args.len()
+                            ))
+                            .at(call.span, format!("{} arguments given here", call.args.len()))
+                            .with_note_at(call.callee.span(), format!("this has type {}", callee_type)))
+                            .context("Argument count mismatch");
                         }
                         for (arg, expected_type) in call.args.iter().zip(ft.params.iter()) {
                             let arg_type = self.analyze_node(arg)?;
@@ -307,50 +571,322 @@ impl Analyzer {
                                 return Err(AnalysisError::type_error(format!(
                                     "Argument type mismatch: expected {}, got {}",
                                     expected_type, arg_type
-                                ))).context("Argument type mismatch");
+                                ))
+                                .at(arg.span(), format!("expected {}, got {}", expected_type, arg_type))
+                                .with_note_at(call.callee.span(), format!("this has type {}", callee_type)))
+                                .context("Argument type mismatch");
                             }
                         }
                         Ok(*ft.return_type.clone())
                     },
+                    Type::Unknown => {
+                        self.analyze_nodes(&call.args)?;
+                        Ok(Type::Unknown)
+                    },
                     _ => Err(AnalysisError::type_error(format!(
-                        "{} is not a function", call.name
-                    ))).context("Not a function"),
+                        "Value of type {} is not a function", callee_type
+                    ))
+                    .at(call.callee.span(), format!("this has type {}", callee_type)))
+                    .context("Not a function"),
+                }
+            },
+            Expression::BuiltinCall(call) => {
+                let arg_types = call.args.iter()
+                    .map(|arg| self.analyze_node(arg))
+                    .collect::<AnalyzerResult<Vec<_>>>()?;
+                if call.takes_format() {
+                    // The parser has already matched placeholders to arguments
+                    if let Some(format_type) = arg_types.first() {
+                        if !matches!(format_type, Type::String | Type::Unknown) {
+                            return Err(AnalysisError::type_error(format!(
+                                "Format string of {} must be a string, got {}", call.name, format_type
+                            ))).context("Invalid format string");
+                        }
+                    }
+                    if let Some(arg_type) = arg_types.iter().skip(1).find(|ty| matches!(ty, Type::Function(_))) {
+                        return Err(AnalysisError::type_error(format!(
+                            "Value of type {} cannot be formatted by {}", arg_type, call.name
+                        ))).context("Unformattable argument");
+                    }
+                    Ok(Type::Unit)
+                } else {
+                    // Other built-ins are not resolved yet
+                    Ok(Type::Unknown)
+                }
+            },
+            Expression::Propagate(propagate) => {
+                match self.analyze_node(&propagate.value)? {
+                    Type::Result(ok_type, _) => Ok(*ok_type),
+                    Type::Unknown => Ok(Type::Unknown),
+                    other => Err(AnalysisError::type_error(format!(
+                        "The ? operator can only be applied to Res values, got {}", other
+                    ))
+                    .at(propagate.value.span(), format!("this has type {}", other)))
+                    .context("Invalid error propagation"),
+                }
+            },
+            Expression::Await(await_expr) => {
+                if !self.in_async {
+                    return Err(AnalysisError::type_error(
+                        "spro can only be used inside an xnc function".to_string()
+                    )
+                    .at(await_expr.span, "awaited outside an xnc function")
+                    .with_help("declare the enclosing function with `xnc fnc`"))
+                    .context("Await outside async function");
                 }
+                self.analyze_node(&await_expr.value)
             },
-            Expression::FieldAccess(struct_name, field_name, _) => {
-                let struct_symbol = self.symbols.lookup(struct_name)?;
-                match &struct_symbol.ty {
+            Expression::MethodCall(call) => {
+                // Methods are not resolved yet, so only the operands are checked
+                self.analyze_node(&call.receiver)?;
+                self.analyze_nodes(&call.args)?;
+                Ok(Type::Unknown)
+            },
+            Expression::Match(match_expr) => self.analyze_match(match_expr)
+                .context("Failed to analyze match expression"),
+            Expression::FieldAccess(access) => {
+                let object_type = self.analyze_node(&access.object)?;
+                match &object_type {
                     Type::Struct(s) => {
-                        if let Some((_, field_type)) = s.fields.iter().find(|(name, _)| name == field_name) {
+                        if let Some((_, field_type)) = s.fields.iter().find(|(name, _)| *name == access.field) {
                             Ok(field_type.clone())
                         } else {
                             Err(AnalysisError::type_error(format!(
-                                "Field {} not found in struct {}", field_name, struct_name
-                            ))).context("Field not found")
+                                "Field {} not found in struct {}", access.field, s.name
+                            ))
+                            .at(access.span, format!("unknown field {}", access.field)))
+                            .context("Field not found")
                         }
                     },
+                    Type::Unknown => Ok(Type::Unknown),
                     _ => Err(AnalysisError::type_error(format!(
-                        "{} is not a struct", struct_name
-                    ))).context("Not a struct"),
+                        "Value of type {} is not a struct", object_type
+                    ))
+                    .at(access.object.span(), format!("this has type {}", object_type)))
+                    .context("Not a struct"),
                 }
             },
-            Expression::ArrayAccess(array_name, index_expr, _) => {
-                let array_symbol = self.symbols.lookup(array_name)?;
-                match &array_symbol.ty {
-                    Type::Array(element_type) => {
-                        let index_type = self.analyze_node(index_expr)?;
-                        if index_type != Type::Int {
-                            return Err(AnalysisError::type_error(format!(
-                                "Array index must be an integer, got {}", index_type
-                            ))).context("Invalid array index");
-                        }
-                        Ok(*element_type.clone())
-                    },
+            Expression::ArrayAccess(access) => {
+                let array_type = self.analyze_node(&access.array)?;
+                let index_type = self.analyze_node(&access.index)?;
+                if index_type != Type::Int && index_type != Type::Unknown {
+                    return Err(AnalysisError::type_error(format!(
+                        "Array index must be an integer, got {}", index_type
+                    ))
+                    .at(access.index.span(), format!("this has type {}", index_type)))
+                    .context("Invalid array index");
+                }
+                match array_type {
+                    Type::Array(element_type) => Ok(*element_type),
+                    Type::Unknown => Ok(Type::Unknown),
                     _ => Err(AnalysisError::type_error(format!(
-                        "{} is not an array", array_name
-                    ))).context("Not an array"),
+                        "Value of type {} is not an array", array_type
+                    ))
+                    .at(access.array.span(), format!("this has type {}", array_type)))
+                    .context("Not an array"),
                 }
             },
         }
     }
+
+    /// Analyzes a struct literal against the struct's declared fields
+    fn analyze_struct_literal(&mut self, literal: &StructLiteral) -> AnalyzerResult<Type> {
+        let struct_type = match self.symbols.lookup(&literal.path.to_string()).map(|symbol| symbol.ty.clone()) {
+            Ok(Type::Struct(struct_type)) => struct_type,
+            // Struct-style enum variants and unresolved paths are not checked yet
+            _ => {
+                for field in &literal.fields {
+                    self.analyze_node(&field.value)?;
+                }
+                return Ok(Type::Unknown);
+            },
+        };
+
+        for field in &literal.fields {
+            let value_type = self.analyze_node(&field.value)?;
+            let Some((_, field_type)) = struct_type.fields.iter().find(|(name, _)| *name == field.name) else {
+                return Err(AnalysisError::type_error(format!(
+                    "Field {} not found in struct {}", field.name, struct_type.name
+                ))
+                .at(field.span, format!("unknown field {}", field.name)))
+                .context("Field not found");
+            };
+            if !self.type_checker.can_coerce(&value_type, field_type) {
+                return Err(AnalysisError::type_error(format!(
+                    "Field {} of struct {} has type {}, got {}",
+                    field.name, struct_type.name, field_type, value_type
+                ))
+                .at(field.value.span(), format!("this has type {}", value_type)))
+                .context("Type mismatch in struct literal");
+            }
+        }
+        for (name, _) in &struct_type.fields {
+            if !literal.fields.iter().any(|field| &field.name == name) {
+                return Err(AnalysisError::type_error(format!(
+                    "Missing field {} in construction of struct {}", name, struct_type.name
+                ))
+                .at(literal.span, format!("missing field {}", name)))
+                .context("Missing struct field");
+            }
+        }
+
+        Ok(Type::Struct(struct_type))
+    }
+
+    /// Analyzes a match expression
+    fn analyze_match(&mut self, match_expr: &Match) -> AnalyzerResult<Type> {
+        let scrutinee_type = self.analyze_node(&match_expr.scrutinee)?;
+
+        // The first arm with a known type decides the type of the match
+        let mut match_type: Option<(Type, &AstNode)> = None;
+        for arm in &match_expr.arms {
+            // Each arm gets its own scope for the names its pattern binds
+            self.symbols.push_scope();
+            self.define_pattern_bindings(&arm.pattern, &scrutinee_type, false)?;
+            if let Some(guard) = &arm.guard {
+                self.analyze_node(guard)?;
+            }
+            let arm_type = self.analyze_node(&arm.body)?;
+            self.symbols.pop_scope()
+                .context("Failed to pop match arm scope")?;
+
+            match &match_type {
+                Some((expected, first)) if !self.type_checker.can_coerce(&arm_type, expected) => {
+                    return Err(AnalysisError::type_error(format!(
+                        "Match arms have incompatible types: expected {}, got {}",
+                        expected, arm_type
+                    ))
+                    .at(arm.body.span(), format!("this has type {}", arm_type))
+                    .with_note_at(first.span(), format!("expected {} because of this arm", expected)))
+                    .context("Mismatched match arm types");
+                },
+                Some((Type::Unknown, _)) | None => match_type = Some((arm_type, &arm.body)),
+                Some(_) => {},
+            }
+        }
+
+        Ok(match_type.map_or(Type::Unit, |(ty, _)| ty))
+    }
+
+    /// Defines the names bound by a pattern matched against a value of type
+    /// `ty` in the current scope
+    fn define_pattern_bindings(&mut self, pattern: &Pattern, ty: &Type, is_mutable: bool) -> AnalyzerResult<()> {
+        match pattern {
+            // A bare name of a unit variant matches that variant rather than
+            // binding a new variable
+            Pattern::Identifier(name, _) if matches!(ty, Type::Enum(enum_type)
+                if enum_type.variants.iter().any(|(variant, payload)| variant == name && payload.is_none())) => {},
+            Pattern::Identifier(name, _) => {
+                let symbol = AnalysisSymbol {
+                    name: name.clone(),
+                    kind: SymbolKind::Variable,
+                    ty: ty.clone(),
+                    scope_level: self.symbols.current_level(),
+                    documentation: None,
+                    metadata: Default::default(),
+                    visibility: Visibility::Private,
+                    is_mutable,
+                };
+                self.symbols.define(symbol)
+                    .with_context(|| format!("Failed to define pattern binding: {}", name))?;
+            },
+            Pattern::Variant(variant) => {
+                let payload = match ty {
+                    Type::Enum(enum_type) => variant.path.segments.last()
+                        .and_then(|name| enum_type.variants.iter().find(|(variant, _)| variant == name))
+                        .and_then(|(_, payload)| payload.clone()),
+                    _ => None,
+                };
+                let elements = variant.payload.as_deref().unwrap_or_default();
+                for (index, element) in elements.iter().enumerate() {
+                    // Multi-field payloads are structs with positional field names
+                    let element_type = match &payload {
+                        Some(Type::Struct(fields)) if elements.len() > 1 => Self::field_type(fields, &index.to_string()),
+                        Some(payload) if elements.len() == 1 => payload.clone(),
+                        _ => Type::Unknown,
+                    };
+                    self.define_pattern_bindings(element, &element_type, is_mutable)?;
+                }
+            },
+            Pattern::Struct(struct_pattern) => {
+                for field in &struct_pattern.fields {
+                    let field_type = match ty {
+                        Type::Struct(struct_type) => Self::field_type(struct_type, &field.name),
+                        _ => Type::Unknown,
+                    };
+                    self.define_pattern_bindings(&field.pattern, &field_type, is_mutable)?;
+                }
+            },
+            Pattern::Tuple(elements, _) => {
+                // Tuples have no type of their own yet
+                for element in elements {
+                    self.define_pattern_bindings(element, &Type::Unknown, is_mutable)?;
+                }
+            },
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {},
+        }
+        Ok(())
+    }
+
+    /// Returns the type of a struct field, or unknown if there is no such field
+    fn field_type(struct_type: &StructType, name: &str) -> Type {
+        struct_type.fields.iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, ty)| ty.clone())
+            .unwrap_or(Type::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kymera_parser::lexer::Lexer;
+    use kymera_parser::parser::Parser;
+
+    fn analyze(source: &str) -> AnalyzerResult<()> {
+        let nodes = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        Analyzer::new().analyze(&nodes)
+    }
+
+    #[test]
+    fn test_user_types_in_annotations() {
+        analyze("forma P { x: i32 }\ndjq p: P = P { x: 1 };").unwrap();
+        analyze("fnc f(p: P) -> P { ret p; }\nforma P { x: i32 }\ndjq q: P = f(P { x: 1 });").unwrap();
+        analyze("enum Color { Rojo, Verde }\nfnc f(c: Color) -> Color { ret c; }").unwrap();
+        analyze("fnc id<T>(x: T) -> T { ret x; }").unwrap();
+
+        let error = analyze("forma P { x: i32 }\nforma Q { x: i32 }\nfnc f(p: P) {}\nf(Q { x: 1 });").unwrap_err();
+        assert!(format!("{:#}", error).contains("Argument type mismatch: expected P, got Q"), "{:#}", error);
+    }
+
+    #[test]
+    fn test_unknown_values_in_annotated_declarations() {
+        analyze("djq v = [1, 2];\ndjq x: i32 = v.len();").unwrap();
+        analyze("djq t: (i32, f64) = (1, 2.0);").unwrap();
+        assert!(analyze("djq x: i32 = \"uno\";").is_err());
+    }
+
+    #[test]
+    fn test_destructured_bindings() {
+        analyze("djq (a, b) = (1, 2);\ndjq c = a + b;").unwrap();
+        analyze("forma P { x: i32, y: f64 }\ndjq P { x, y } = P { x: 1, y: 2.0 };\ndjq z: f64 = x + y;").unwrap();
+
+        let error = analyze("forma P { x: i32 }\ndjq P { x } = P { x: 1 };\ndjq s: Strng = x;").unwrap_err();
+        assert!(format!("{:#}", error).contains("Cannot initialize variable of type string with value of type int"), "{:#}", error);
+    }
+
+    #[test]
+    fn test_match_arms() {
+        analyze("fnc f(n: i32) -> f64 { ret mth n { 0 => 1.0, k => 2.0 }; }").unwrap();
+        analyze("fnc f(v: [i32]) -> i32 { ret mth 1 { 0 => v.len(), _ => 2 }; }").unwrap();
+
+        let error = analyze("fnc f(n: i32) { mth n { 0 => 1, _ => \"dos\" }; }").unwrap_err();
+        let message = format!("{:#}", error);
+        assert!(message.contains("Match arms have incompatible types: expected int, got string"), "{}", message);
+
+        // `Rojo` names a variant and binds nothing; `otro` is a new binding
+        analyze("enum Color { Rojo, Verde }\nfnc f(c: Color) -> Color { ret mth c { Rojo => c, otro => otro }; }").unwrap();
+        let error = analyze("enum Color { Rojo, Verde }\nfnc f(c: Color) { mth c { Rojo => Rojo, _ => c }; }").unwrap_err();
+        assert!(format!("{:#}", error).contains("Symbol Rojo not found"), "{:#}", error);
+    }
 }
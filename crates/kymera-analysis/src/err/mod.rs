@@ -1,7 +1,11 @@
 //! Error types for the Kymera analyzer.
 
+use std::fmt;
+
 use thiserror::Error as AnalyzerError;
 use anyhow::Result as AnalyzerResult;
+use miette::{Diagnostic, LabeledSpan};
+use kymera_parser::position::Span;
 
 /// Custom error type for the analysis phase
 #[derive(Debug, AnalyzerError)]
@@ -76,6 +80,17 @@ pub enum AnalysisError {
     /// I/O errors
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
+
+    /// An error pointing at the code that caused it
+    #[error("{error}")]
+    Located {
+        error: Box<AnalysisError>,
+        span: Span,
+        label: String,
+        /// Related code, each with its own label
+        notes: Vec<(Span, String)>,
+        help: Option<String>,
+    },
 }
 
 impl AnalysisError {
@@ -143,7 +158,12 @@ impl AnalysisError {
     where
         E: std::error::Error + Send + Sync + 'static,
     {
-        match &mut self {
+        self.set_source(Box::new(err));
+        self
+    }
+
+    fn set_source(&mut self, err: Box<dyn std::error::Error + Send + Sync>) {
+        match self {
             Self::TypeError { source, .. } |
             Self::TypeParseError { source, .. } |
             Self::TypeValidationError { source, .. } |
@@ -151,13 +171,181 @@ impl AnalysisError {
             Self::SymbolError { source, .. } |
             Self::ScopeError { source, .. } |
             Self::SemanticError { source, .. } => {
-                *source = Some(Box::new(err));
+                *source = Some(err);
             }
+            Self::Located { error, .. } => error.set_source(err),
             _ => {}
         }
+    }
+
+    /// Points the error at the code that caused it; an error that already
+    /// has a location keeps it
+    pub fn at<S: Into<String>>(self, span: Span, label: S) -> Self {
+        match self {
+            Self::Located { .. } => self,
+            error => Self::Located {
+                error: Box::new(error),
+                span,
+                label: label.into(),
+                notes: Vec::new(),
+                help: None,
+            },
+        }
+    }
+
+    /// Adds a label pointing at related code; has no effect until the
+    /// error is located with [`AnalysisError::at`]
+    pub fn with_note_at<S: Into<String>>(mut self, span: Span, label: S) -> Self {
+        if let Self::Located { notes, .. } = &mut self {
+            notes.push((span, label.into()));
+        }
+        self
+    }
+
+    /// Adds a suggestion for fixing the error; has no effect until the
+    /// error is located with [`AnalysisError::at`]
+    pub fn with_help<S: Into<String>>(mut self, message: S) -> Self {
+        if let Self::Located { help, .. } = &mut self {
+            *help = Some(message.into());
+        }
         self
     }
 }
 
+impl Diagnostic for AnalysisError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match self {
+            Self::TypeError { .. } => "kymera::type",
+            Self::TypeParseError { .. } => "kymera::type_parse",
+            Self::TypeValidationError { .. } => "kymera::type_validation",
+            Self::TypeParameterError { .. } => "kymera::type_parameter",
+            Self::SymbolError { .. } => "kymera::symbol",
+            Self::ScopeError { .. } => "kymera::scope",
+            Self::SemanticError { .. } => "kymera::semantic",
+            Self::Parser(err) => return err.code(),
+            Self::Core(_) => "kymera::core",
+            Self::IoError(_) => "kymera::io",
+            Self::Located { error, .. } => return error.code(),
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self {
+            Self::Parser(err) => err.help(),
+            Self::Located { help: Some(help), .. } => Some(Box::new(help)),
+            Self::Located { error, .. } => error.help(),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        match self {
+            Self::Parser(err) => err.labels(),
+            Self::Located { span, label, notes, .. } => {
+                let primary = LabeledSpan::new_primary_with_span(Some(label.clone()), *span);
+                let notes = notes
+                    .iter()
+                    .map(|(span, label)| LabeledSpan::new_with_span(Some(label.clone()), *span));
+                Some(Box::new(std::iter::once(primary).chain(notes)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the diagnostic carried by an error from the analysis phase, if it
+/// is an [`AnalysisError`] or a parser error.
+pub fn as_diagnostic(error: &anyhow::Error) -> Option<&dyn Diagnostic> {
+    if let Some(error) = error.downcast_ref::<AnalysisError>() {
+        return Some(error);
+    }
+    error
+        .downcast_ref::<kymera_parser::err::ParserError>()
+        .map(|error| error as &dyn Diagnostic)
+}
+
 /// Result type alias for the analysis phase
-pub type Result<T> = AnalyzerResult<T>; 
\ No newline at end of file
+pub type Result<T> = AnalyzerResult<T>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::Analyzer;
+    use kymera_parser::lexer::Lexer;
+    use kymera_parser::parser::Parser;
+    use kymera_parser::Renderer;
+
+    fn analyze(source: &str) -> anyhow::Error {
+        let nodes = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        Analyzer::new().analyze(&nodes).unwrap_err()
+    }
+
+    #[test]
+    fn test_type_mismatch_diagnostic() {
+        let source = "djq x: i32 = \"one\";\n";
+        let error = analyze(source);
+        let analysis = error.downcast_ref::<AnalysisError>().unwrap();
+        assert!(matches!(analysis, AnalysisError::Located { .. }));
+        assert_eq!(analysis.code().unwrap().to_string(), "kymera::type");
+
+        let labels: Vec<_> = analysis.labels().unwrap().collect();
+        assert_eq!(labels.len(), 2);
+        assert!(labels[0].primary());
+        assert_eq!(labels[0].offset(), source.find('"').unwrap());
+        assert!(labels[0].label().unwrap().starts_with("this has type"));
+        assert!(!labels[1].primary());
+        assert_eq!(labels[1].offset(), source.find("i32").unwrap());
+        assert!(labels[1].label().unwrap().contains("because of this annotation"));
+
+        let report = Renderer::new().with_width(100).render("main.ky", source, analysis);
+        assert!(report.contains("kymera::type"), "{}", report);
+        assert!(report.contains("[main.ky:1:14]"), "{}", report);
+        assert!(report.contains("because of this annotation"), "{}", report);
+    }
+
+    #[test]
+    fn test_help_and_helper() {
+        let source = "djq x: i32 = 1;\nx = 2;\n";
+        let error = analyze(source);
+        let diagnostic = as_diagnostic(&error).unwrap();
+        assert_eq!(diagnostic.code().unwrap().to_string(), "kymera::semantic");
+        assert_eq!(diagnostic.help().unwrap().to_string(), "declare it as `djq muta x` to allow assignment");
+        let labels: Vec<_> = diagnostic.labels().unwrap().collect();
+        assert_eq!(labels.len(), 1);
+        assert_eq!(labels[0].label(), Some("cannot assign to this"));
+
+        let report = Renderer::new().render("main.ky", source, diagnostic);
+        assert!(report.contains("help: declare it as `djq muta x`"), "{}", report);
+
+        let parse_error = anyhow::Error::new(Parser::new(Lexer::new("djq = 1;").tokenize().unwrap()).parse().unwrap_err());
+        assert!(as_diagnostic(&parse_error).unwrap().code().is_some());
+        assert!(as_diagnostic(&anyhow::anyhow!("plain")).is_none());
+    }
+
+    #[test]
+    fn test_location_builders() {
+        let span = |start: usize, end: usize| {
+            let mut span = Span::default();
+            span.start.offset = start;
+            span.end.offset = end;
+            span
+        };
+
+        let unlocated = AnalysisError::semantic_error("bad").with_help("ignored");
+        assert!(unlocated.help().is_none());
+        assert!(unlocated.labels().is_none());
+
+        let error = AnalysisError::semantic_error("bad")
+            .at(span(4, 6), "first")
+            .at(span(0, 1), "second")
+            .with_note_at(span(8, 9), "note")
+            .with_help("help");
+        assert_eq!(error.to_string(), "Semantic error: bad");
+        assert_eq!(error.help().unwrap().to_string(), "help");
+        let labels: Vec<_> = error.labels().unwrap().collect();
+        assert_eq!(labels.len(), 2);
+        assert_eq!((labels[0].offset(), labels[0].len(), labels[0].label()), (4, 2, Some("first")));
+        assert_eq!((labels[1].offset(), labels[1].label()), (8, Some("note")));
+    }
+}
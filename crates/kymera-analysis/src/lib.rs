@@ -6,7 +6,7 @@ pub mod symbols;
 pub mod types;
 
 pub use analyzer::Analyzer;
-pub use err::{AnalysisError, Result};
+pub use err::{as_diagnostic, AnalysisError, Result};
 pub use symbols::{AnalysisSymbol, AnalysisTable, SymbolKind, Visibility};
 pub use types::{Type, TypeChecker, FunctionType, StructType, EnumType};
 
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::sync::Arc;
 use anyhow::{Context, Result as AnalyzerResult};
-use kymera_parser::ast::Literal;
+use kymera_parser::ast::{Literal, TypeExpr};
 
 use crate::err::AnalysisError;
 
@@ -117,6 +117,16 @@ impl TypeChecker {
 
     /// Checks if a binary operation is valid and returns its result type
     pub fn check_binary_op(&self, left: &Type, op: &str, right: &Type) -> AnalyzerResult<Type> {
+        // Operands whose type is not inferred yet are not rejected; the result
+        // is only known where the operator alone decides it
+        if left == &Type::Unknown || right == &Type::Unknown {
+            match op {
+                "==" | "!=" | "<" | "<=" | ">" | ">=" | "&&" | "||" => return Ok(Type::Bool),
+                ".." | "..=" => return Ok(Type::Array(Box::new(Type::Int))),
+                "+" | "-" | "*" | "/" | "%" | "&" | "|" | "^" | "<<" | ">>" => return Ok(Type::Unknown),
+                _ => {},
+            }
+        }
         match op {
             "+" | "-" | "*" | "/" | "%" => {
                 match (left, right) {
@@ -149,6 +159,25 @@ impl TypeChecker {
                     ))).context("Invalid comparison operands"),
                 }
             },
+            "&" | "|" | "^" | "<<" | ">>" => {
+                match (left, right) {
+                    (Type::Int, Type::Int) => Ok(Type::Int),
+                    _ => Err(AnalysisError::type_error(format!(
+                        "Bitwise operations require integer operands: {} {} {}",
+                        left, op, right
+                    ))).context("Invalid bitwise operands"),
+                }
+            },
+            ".." | "..=" => {
+                // Ranges are iterated like arrays of their bounds
+                match (left, right) {
+                    (Type::Int, Type::Int) => Ok(Type::Array(Box::new(Type::Int))),
+                    _ => Err(AnalysisError::type_error(format!(
+                        "Range bounds must be integers, got {} and {}",
+                        left, right
+                    ))).context("Invalid range bounds"),
+                }
+            },
             "&&" | "||" => {
                 if left == &Type::Bool && right == &Type::Bool {
                     Ok(Type::Bool)
@@ -168,6 +197,13 @@ impl TypeChecker {
 
     /// Checks if a unary operation is valid and returns its result type
     pub fn check_unary_op(&self, op: &str, expr: &Type) -> AnalyzerResult<Type> {
+        if expr == &Type::Unknown {
+            match op {
+                "!" => return Ok(Type::Bool),
+                "-" | "~" => return Ok(Type::Unknown),
+                _ => {},
+            }
+        }
         match op {
             "-" => {
                 match expr {
@@ -178,6 +214,16 @@ impl TypeChecker {
                     ))).context("Invalid negation operand"),
                 }
             },
+            "~" => {
+                if expr == &Type::Int {
+                    Ok(Type::Int)
+                } else {
+                    Err(AnalysisError::type_error(format!(
+                        "Bitwise not requires integer operand, got {}",
+                        expr
+                    ))).context("Invalid bitwise not operand")
+                }
+            },
             "!" => {
                 if expr == &Type::Bool {
                     Ok(Type::Bool)
@@ -203,6 +249,7 @@ impl TypeChecker {
             Literal::Bool(..) => Type::Bool,
             Literal::Strng(..) => Type::String,
             Literal::Stilo(..) => Type::String,
+            Literal::Bytes(..) => Type::Array(Box::new(Type::Int)),
             Literal::Nil(..) => Type::Unit,
         }
     }
@@ -233,6 +280,9 @@ impl TypeChecker {
         match (from, to) {
             // Same types can always be coerced
             (t1, t2) if t1 == t2 => true,
+
+            // Values whose type is not inferred yet are not rejected
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
             
             // Int can be coerced to Float
             (Type::Int, Type::Float) => true,
@@ -314,20 +364,24 @@ impl Type {
             "int" => Ok(Type::Int),
             "float" => Ok(Type::Float),
             "string" => Ok(Type::String),
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isz"
+            | "u8" | "u16" | "u32" | "u64" | "u128" | "usz" => Ok(Type::Int),
+            "f32" | "f64" => Ok(Type::Float),
+            "Strng" | "Stilo" | "strng" | "stilo" => Ok(Type::String),
             s if s.starts_with('[') && s.ends_with(']') => {
                 let inner = &s[1..s.len()-1];
                 let element_type = Type::parse(inner)
                     .with_context(|| format!("Failed to parse array element type: {}", inner))?;
                 Ok(Type::Array(Box::new(element_type)))
             },
-            s if s.starts_with("Option<") && s.ends_with('>') => {
-                let inner = &s[7..s.len()-1];
+            s if (s.starts_with("Option<") || s.starts_with("Optn<")) && s.ends_with('>') => {
+                let inner = &s[s.find('<').unwrap_or(0) + 1..s.len()-1];
                 let inner_type = Type::parse(inner)
                     .with_context(|| format!("Failed to parse Option type parameter: {}", inner))?;
                 Ok(Type::Option(Box::new(inner_type)))
             },
-            s if s.starts_with("Result<") && s.ends_with('>') => {
-                let inner = &s[7..s.len()-1];
+            s if (s.starts_with("Result<") || s.starts_with("Res<")) && s.ends_with('>') => {
+                let inner = &s[s.find('<').unwrap_or(0) + 1..s.len()-1];
                 let parts: Vec<&str> = inner.split(',').map(str::trim).collect();
                 if parts.len() != 2 {
                     return Err(AnalysisError::type_parse_error(
@@ -398,6 +452,59 @@ impl Type {
         }
     }
 
+    /// Converts a parsed type annotation into a Type
+    pub fn from_type_expr(type_expr: &TypeExpr) -> AnalyzerResult<Self> {
+        match type_expr {
+            TypeExpr::Primitive(name, _) => Type::parse(name),
+            TypeExpr::Named(named) => {
+                let name = named.path.segments.last().map(String::as_str).unwrap_or_default();
+                match (name, named.args.as_slice()) {
+                    ("Optn" | "Option", [inner]) => {
+                        Ok(Type::Option(Box::new(Type::from_type_expr(inner)?)))
+                    },
+                    ("Res" | "Result", [ok, err]) => Ok(Type::Result(
+                        Box::new(Type::from_type_expr(ok)?),
+                        Box::new(Type::from_type_expr(err)?),
+                    )),
+                    ("Res" | "Result", _) => Err(AnalysisError::type_parse_error(
+                        "Result type requires exactly two type parameters",
+                        &type_expr.to_string()
+                    )).context("Invalid Result type"),
+                    _ => {
+                        for arg in &named.args {
+                            Type::from_type_expr(arg)
+                                .with_context(|| format!("Failed to parse type parameter: {}", arg))?;
+                        }
+                        Ok(Type::Generic(name.to_string()))
+                    }
+                }
+            },
+            TypeExpr::Slice(element, _) | TypeExpr::Array(element, _, _) => {
+                let element_type = Type::from_type_expr(element)
+                    .with_context(|| format!("Failed to parse array element type: {}", element))?;
+                Ok(Type::Array(Box::new(element_type)))
+            },
+            TypeExpr::Tuple(elements, _) if elements.is_empty() => Ok(Type::Unit),
+            TypeExpr::Tuple(_, _) => Ok(Type::Unknown), // No tuple types yet
+            TypeExpr::Function(function) => {
+                let params = function.params.iter()
+                    .map(Type::from_type_expr)
+                    .collect::<AnalyzerResult<Vec<_>>>()
+                    .context("Failed to parse function parameters")?;
+                let return_type = match &function.return_type {
+                    Some(return_type) => Type::from_type_expr(return_type)
+                        .context("Failed to parse function return type")?,
+                    None => Type::Unit,
+                };
+                Ok(Type::Function(FunctionType {
+                    params,
+                    return_type: Box::new(return_type),
+                    type_params: Vec::new(),
+                }))
+            },
+        }
+    }
+
     /// Validates type parameters against their constraints
     pub fn validate_type_params(&self, type_params: &[TypeParameter]) -> AnalyzerResult<()> {
         match self {
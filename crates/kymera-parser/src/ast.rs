@@ -1,25 +1,143 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
 use crate::position::Span;
-use crate::lexer::TokenType;
+use crate::lexer::{NumericSuffix, TokenType};
 
 /// Represents a literal value in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Literal {
-    /// An integer literal.
-    Int(i64, Span),
-    /// A float literal.
-    Float(f64, Span),
+    /// An integer literal with its type suffix, if any.
+    Int(i128, Option<NumericSuffix>, Span),
+    /// A float literal with its type suffix, if any.
+    Float(f64, Option<NumericSuffix>, Span),
     /// A boolean literal.
     Bool(bool, Span),
     /// A string literal.
     Strng(String, Span),
     /// A Stilo (string slice) literal.
     Stilo(String, Span),
+    /// A byte string literal.
+    Bytes(Vec<u8>, Span),
     /// A nil (null) literal.
     Nil(Span),
 }
 
+impl Literal {
+    /// Returns the location of the literal in the source code.
+    pub fn span(&self) -> Span {
+        match self {
+            Literal::Int(_, _, span)
+            | Literal::Float(_, _, span)
+            | Literal::Bool(_, span)
+            | Literal::Strng(_, span)
+            | Literal::Stilo(_, span)
+            | Literal::Bytes(_, span)
+            | Literal::Nil(span) => *span,
+        }
+    }
+}
+
+/// Represents a type annotation in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TypeExpr {
+    /// A primitive type such as `i32`, `f64`, `bool` or `Strng`.
+    Primitive(String, Span),
+    /// A named type, optionally with a path and generic arguments, such as
+    /// `Persona`, `Res<i32, Strng>` or `core:>io:>Handle`.
+    Named(Box<NamedType>),
+    /// A slice type such as `[u8]`.
+    Slice(Box<TypeExpr>, Span),
+    /// A fixed-size array type such as `[u8; 4]`.
+    Array(Box<TypeExpr>, usize, Span),
+    /// A tuple type such as `(i32, Strng)`. The empty tuple is the unit type.
+    Tuple(Vec<TypeExpr>, Span),
+    /// A function type such as `fnc(i32, i32) -> i32`.
+    Function(FunctionTypeExpr),
+}
+
+impl TypeExpr {
+    /// Returns the location of the type in the source code.
+    pub fn span(&self) -> Span {
+        match self {
+            TypeExpr::Primitive(_, span)
+            | TypeExpr::Slice(_, span)
+            | TypeExpr::Array(_, _, span)
+            | TypeExpr::Tuple(_, span) => *span,
+            TypeExpr::Named(named) => named.span,
+            TypeExpr::Function(function) => function.span,
+        }
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeExpr::Primitive(name, _) => write!(f, "{}", name),
+            TypeExpr::Named(named) => {
+                write!(f, "{}", named.path)?;
+                if !named.args.is_empty() {
+                    write!(f, "<{}>", join_types(&named.args))?;
+                }
+                Ok(())
+            }
+            TypeExpr::Slice(element, _) => write!(f, "[{}]", element),
+            TypeExpr::Array(element, len, _) => write!(f, "[{}; {}]", element, len),
+            TypeExpr::Tuple(elements, _) => write!(f, "({})", join_types(elements)),
+            TypeExpr::Function(function) => {
+                write!(f, "fnc({})", join_types(&function.params))?;
+                if let Some(return_type) = &function.return_type {
+                    write!(f, " -> {}", return_type)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn join_types(types: &[TypeExpr]) -> String {
+    types.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// Represents a `:>`-separated path such as `core:>math:>add`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Path {
+    /// The segments of the path, in source order.
+    pub segments: Vec<String>,
+    /// The location of the path in the source code.
+    pub span: Span,
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.segments.join(":>"))
+    }
+}
+
+/// Represents a named type such as `Res<i32, Strng>`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedType {
+    /// The path of the type.
+    pub path: Path,
+    /// The generic arguments of the type.
+    pub args: Vec<TypeExpr>,
+    /// The location of the type in the source code.
+    pub span: Span,
+}
+
+/// Represents a function type such as `fnc(i32) -> Strng`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionTypeExpr {
+    /// The parameter types.
+    pub params: Vec<TypeExpr>,
+    /// The return type, if any.
+    pub return_type: Option<Box<TypeExpr>>,
+    /// The location of the type in the source code.
+    pub span: Span,
+}
+
 /// Represents a binary operation in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BinaryOp {
     /// The left-hand side of the operation.
     pub left: Box<AstNode>,
@@ -32,7 +150,7 @@ pub struct BinaryOp {
 }
 
 /// Represents a unary operation in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UnaryOp {
     /// The operator.
     pub op: String,
@@ -43,29 +161,35 @@ pub struct UnaryOp {
 }
 
 /// Represents a variable declaration in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Declaration {
-    /// The name of the variable.
-    pub name: String,
-    /// The value assigned to the variable.
-    pub value: Literal,
+    /// The `muta`/`nmut` designator of the bindings.
+    pub mutability: Mutability,
+    /// The pattern being bound, usually a single name.
+    pub pattern: Pattern,
+    /// The declared type of the variable, if any.
+    pub ty: Option<TypeExpr>,
+    /// The initializer expression.
+    pub value: Box<AstNode>,
     /// The location of the declaration in the source code.
     pub span: Span,
 }
 
 /// Represents a variable assignment in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Assignment {
-    /// The name of the variable being assigned to.
-    pub name: String,
-    /// The new value of the variable.
+    /// The place being assigned to: a name, field access or index.
+    pub target: Box<AstNode>,
+    /// The assignment operator, `=` or a compound operator such as `+=`.
+    pub op: String,
+    /// The value being assigned.
     pub value: Box<AstNode>,
     /// The location of the assignment in the source code.
     pub span: Span,
 }
 
 /// Represents an if statement in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct IfStatement {
     /// The condition of the if statement.
     pub condition: Box<AstNode>,
@@ -78,8 +202,10 @@ pub struct IfStatement {
 }
 
 /// Represents a loop statement in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LoopStatement {
+    /// The label of the loop, if any (`label: wyo ...`).
+    pub label: Option<String>,
     /// The condition of the loop.
     pub condition: Box<AstNode>,
     /// The body of the loop.
@@ -88,8 +214,32 @@ pub struct LoopStatement {
     pub span: Span,
 }
 
+/// Represents a for loop (`spa pattern in iterable { }`) in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForStatement {
+    /// The label of the loop, if any (`label: spa ...`).
+    pub label: Option<String>,
+    /// The pattern each element is bound to.
+    pub pattern: Pattern,
+    /// The expression being iterated over.
+    pub iterable: Box<AstNode>,
+    /// The body of the loop.
+    pub body: Vec<AstNode>,
+    /// The location of the for statement in the source code.
+    pub span: Span,
+}
+
+/// Represents a `break` or `continue` statement in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoopControl {
+    /// The label of the targeted loop, or `None` for the innermost loop.
+    pub label: Option<String>,
+    /// The location of the statement in the source code.
+    pub span: Span,
+}
+
 /// Represents a return statement in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ReturnStatement {
     /// The value being returned.
     pub value: Box<AstNode>,
@@ -98,23 +248,222 @@ pub struct ReturnStatement {
 }
 
 /// Represents a function call in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FunctionCall {
-    /// The name of the function being called.
-    pub name: String,
+    /// The expression being called, such as a path or another call.
+    pub callee: Box<AstNode>,
     /// The arguments passed to the function.
     pub args: Vec<AstNode>,
     /// The location of the function call in the source code.
     pub span: Span,
 }
 
+/// Represents a built-in call such as `prnt("hola {}", x)` or `afirma!(x > 0)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuiltinCall {
+    /// The name of the built-in, without the `!`.
+    pub name: String,
+    /// The arguments passed to the built-in.
+    pub args: Vec<AstNode>,
+    /// The location of the call in the source code.
+    pub span: Span,
+}
+
+impl BuiltinCall {
+    /// Returns true if the built-in takes a format string as its first
+    /// argument.
+    pub fn takes_format(&self) -> bool {
+        matches!(self.name.as_str(), "prnt" | "verx")
+    }
+}
+
+/// Represents a method call such as `persona.saludo(1)` in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodCall {
+    /// The expression the method is called on.
+    pub receiver: Box<AstNode>,
+    /// The name of the method.
+    pub method: String,
+    /// The arguments passed to the method.
+    pub args: Vec<AstNode>,
+    /// The location of the method call in the source code.
+    pub span: Span,
+}
+
+/// Represents a struct construction such as `Persona { nombre: "x", edad: 3 }`.
+///
+/// Struct-style enum variants are built the same way, with a path such as
+/// `Forma:>Rect { ancho: 1.0, alto: 2.0 }`. Tuple-style variants are built
+/// with an ordinary call, `Forma:>Circulo(1.0)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructLiteral {
+    /// The path of the struct or variant being constructed.
+    pub path: Path,
+    /// The field initializers, in source order.
+    pub fields: Vec<FieldInit>,
+    /// The location of the struct literal in the source code.
+    pub span: Span,
+}
+
+/// Represents a single `name: value` field initializer. The shorthand
+/// `name` is stored as `name: name`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldInit {
+    /// The name of the field.
+    pub name: String,
+    /// The value of the field.
+    pub value: AstNode,
+    /// The location of the initializer in the source code.
+    pub span: Span,
+}
+
+/// Represents an `spro` await expression in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Await {
+    /// The expression being awaited.
+    pub value: Box<AstNode>,
+    /// The location of the await expression in the source code.
+    pub span: Span,
+}
+
+/// Represents a postfix `?` that propagates the error of a `Res` value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Propagate {
+    /// The expression whose error is propagated.
+    pub value: Box<AstNode>,
+    /// The location of the expression, including the `?`.
+    pub span: Span,
+}
+
+/// Represents an `ate { } rev err { }` error handling block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TryCatch {
+    /// The statements that may fail.
+    pub body: Vec<AstNode>,
+    /// The name the error is bound to in the handler.
+    pub binding: String,
+    /// The location of the error binding in the source code.
+    pub binding_span: Span,
+    /// The statements run when the body fails.
+    pub handler: Vec<AstNode>,
+    /// The location of the block in the source code.
+    pub span: Span,
+}
+
+/// Represents a `snc { }` block, whose statements run synchronously.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncBlock {
+    /// The statements in the block.
+    pub body: Vec<AstNode>,
+    /// The location of the block in the source code.
+    pub span: Span,
+}
+
+/// Represents a field access such as `persona.nombre` in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldAccess {
+    /// The expression whose field is accessed.
+    pub object: Box<AstNode>,
+    /// The name of the field.
+    pub field: String,
+    /// The location of the field access in the source code.
+    pub span: Span,
+}
+
+/// Represents an index expression such as `tabla[i]` in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArrayAccess {
+    /// The expression being indexed.
+    pub array: Box<AstNode>,
+    /// The index expression.
+    pub index: Box<AstNode>,
+    /// The location of the index expression in the source code.
+    pub span: Span,
+}
+
+/// Represents the mutability marker on a binding in the Kymera language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Mutability {
+    /// No marker was written.
+    #[default]
+    Unspecified,
+    /// Marked `muta`.
+    Mutable,
+    /// Marked `nmut`.
+    Immutable,
+}
+
+/// Represents the `soy` receiver of a method in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Receiver {
+    /// The mutability marker on the receiver (`muta soy`).
+    pub mutability: Mutability,
+    /// The location of the receiver in the source code.
+    pub span: Span,
+}
+
+/// Represents a function parameter in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Param {
+    /// The mutability marker on the parameter.
+    pub mutability: Mutability,
+    /// The name of the parameter.
+    pub name: String,
+    /// The declared type of the parameter, if any.
+    pub ty: Option<TypeExpr>,
+    /// The location of the parameter in the source code.
+    pub span: Span,
+}
+
+/// Represents an attribute such as `#[obsoleto("usa otra")]` on an item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attribute {
+    /// The name of the attribute.
+    pub name: String,
+    /// The arguments of the attribute, empty for `#[prueba]`.
+    pub args: Vec<AttributeArg>,
+    /// The location of the attribute in the source code.
+    pub span: Span,
+}
+
+impl Attribute {
+    /// Returns the first string argument, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.args.iter().find_map(|arg| match arg {
+            AttributeArg::Literal(Literal::Strng(message, _)) => Some(message.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Represents an argument of an attribute.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttributeArg {
+    /// A literal, as in `#[obsoleto("usa otra")]`.
+    Literal(Literal),
+    /// A bare name, as in `#[repr(C)]`.
+    Identifier(String, Span),
+    /// A named value, as in `#[ffi(nombre = "strlen")]`.
+    NameValue(String, Literal, Span),
+}
+
 /// Represents a function definition in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Function {
+    /// The attributes attached to the function.
+    pub attributes: Vec<Attribute>,
     /// The name of the function.
     pub name: String,
+    /// Whether the function was declared `xnc`.
+    pub is_async: bool,
+    /// The generic parameters of the function.
+    pub generics: Vec<GenericParam>,
+    /// The `soy` receiver, if the function is a method.
+    pub receiver: Option<Receiver>,
     /// The parameters of the function.
-    pub params: Vec<String>,
+    pub params: Vec<Param>,
+    /// The declared return type of the function, if any.
+    pub return_type: Option<TypeExpr>,
     /// The body of the function.
     pub body: Vec<AstNode>,
     /// The location of the function definition in the source code.
@@ -122,42 +471,254 @@ pub struct Function {
 }
 
 /// Represents a struct definition in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Struct {
+    /// The attributes attached to the struct.
+    pub attributes: Vec<Attribute>,
     /// The name of the struct.
     pub name: String,
     /// The fields of the struct.
-    pub fields: Vec<(String, String)>, // (field_name, field_type)
+    pub fields: Vec<StructField>,
     /// The location of the struct definition in the source code.
     pub span: Span,
 }
 
+/// Represents a field of a struct definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructField {
+    /// The attributes attached to the field.
+    pub attributes: Vec<Attribute>,
+    /// The name of the field.
+    pub name: String,
+    /// The type of the field.
+    pub ty: TypeExpr,
+    /// The location of the field in the source code.
+    pub span: Span,
+}
+
 /// Represents an enum definition in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Enum {
+    /// The attributes attached to the enum.
+    pub attributes: Vec<Attribute>,
     /// The name of the enum.
     pub name: String,
     /// The variants of the enum.
-    pub variants: Vec<String>,
+    pub variants: Vec<EnumVariant>,
     /// The location of the enum definition in the source code.
     pub span: Span,
 }
 
+/// Represents a single variant of an enum definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumVariant {
+    /// The name of the variant.
+    pub name: String,
+    /// The data carried by the variant.
+    pub payload: VariantPayload,
+    /// The location of the variant in the source code.
+    pub span: Span,
+}
+
+/// Represents the data carried by an enum variant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum VariantPayload {
+    /// No data, as in `Rojo`.
+    Unit,
+    /// Positional fields, as in `Circulo(f64)`.
+    Tuple(Vec<TypeExpr>),
+    /// Named fields, as in `Rect { ancho: f64, alto: f64 }`.
+    Struct(Vec<StructField>),
+}
+
+/// Represents an implementation block in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Implementation {
+    /// The attributes attached to the implementation block.
+    pub attributes: Vec<Attribute>,
+    /// The type the methods belong to.
+    pub target: TypeExpr,
+    /// The interface being implemented, if any (`imp Interface spa Type`).
+    pub interface: Option<TypeExpr>,
+    /// The methods defined in the block.
+    pub methods: Vec<Function>,
+    /// The location of the implementation block in the source code.
+    pub span: Span,
+}
+
+/// Represents a generic parameter such as `T: Display + Clone`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericParam {
+    /// The name of the parameter.
+    pub name: String,
+    /// The interfaces the parameter is bounded by.
+    pub bounds: Vec<TypeExpr>,
+    /// The location of the parameter in the source code.
+    pub span: Span,
+}
+
+/// Represents a method declared by an interface.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceMethod {
+    /// The name of the method.
+    pub name: String,
+    /// Whether the method was declared `xnc`.
+    pub is_async: bool,
+    /// The generic parameters of the method.
+    pub generics: Vec<GenericParam>,
+    /// The `soy` receiver, if any.
+    pub receiver: Option<Receiver>,
+    /// The parameters of the method.
+    pub params: Vec<Param>,
+    /// The return type of the method, if any.
+    pub return_type: Option<TypeExpr>,
+    /// The default body of the method, if one is provided.
+    pub body: Option<Vec<AstNode>>,
+    /// The location of the method in the source code.
+    pub span: Span,
+}
+
+/// Represents an interface definition in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interface {
+    /// The name of the interface.
+    pub name: String,
+    /// The generic parameters of the interface.
+    pub generics: Vec<GenericParam>,
+    /// The interfaces this interface extends.
+    pub super_interfaces: Vec<TypeExpr>,
+    /// The methods declared by the interface.
+    pub methods: Vec<InterfaceMethod>,
+    /// The location of the interface definition in the source code.
+    pub span: Span,
+}
+
+/// Represents a pattern in a match arm in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    /// The `_` wildcard, which matches anything.
+    Wildcard(Span),
+    /// A literal value.
+    Literal(Literal),
+    /// A name binding the matched value.
+    Identifier(String, Span),
+    /// An enum variant, optionally with a payload.
+    Variant(VariantPattern),
+    /// A struct destructuring.
+    Struct(StructPattern),
+    /// A tuple of patterns.
+    Tuple(Vec<Pattern>, Span),
+}
+
+impl Pattern {
+    /// Returns the location of the pattern in the source code.
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(span) | Pattern::Identifier(_, span) | Pattern::Tuple(_, span) => *span,
+            Pattern::Literal(literal) => literal.span(),
+            Pattern::Variant(variant) => variant.span,
+            Pattern::Struct(struct_pattern) => struct_pattern.span,
+        }
+    }
+}
+
+/// Represents an enum variant pattern such as `Res:>Ok(valor)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariantPattern {
+    /// The path of the variant.
+    pub path: Path,
+    /// The payload patterns, if the variant carries data.
+    pub payload: Option<Vec<Pattern>>,
+    /// The location of the pattern in the source code.
+    pub span: Span,
+}
+
+/// Represents a struct destructuring pattern such as `Persona { nombre, .. }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructPattern {
+    /// The path of the struct.
+    pub path: Path,
+    /// The destructured fields.
+    pub fields: Vec<FieldPattern>,
+    /// Whether the remaining fields are ignored with `..`.
+    pub rest: bool,
+    /// The location of the pattern in the source code.
+    pub span: Span,
+}
+
+/// Represents a single field in a struct pattern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldPattern {
+    /// The name of the field.
+    pub name: String,
+    /// The pattern the field is matched against. For shorthand fields this
+    /// binds the field's own name.
+    pub pattern: Pattern,
+    /// The location of the field pattern in the source code.
+    pub span: Span,
+}
+
+/// Represents a single arm of a match expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchArm {
+    /// The pattern the value is matched against.
+    pub pattern: Pattern,
+    /// The guard condition, if any.
+    pub guard: Option<Box<AstNode>>,
+    /// The body evaluated when the arm matches.
+    pub body: Box<AstNode>,
+    /// The location of the arm in the source code.
+    pub span: Span,
+}
+
+/// Represents a match expression in the Kymera language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    /// The value being matched.
+    pub scrutinee: Box<AstNode>,
+    /// The arms of the match, in source order.
+    pub arms: Vec<MatchArm>,
+    /// The location of the match expression in the source code.
+    pub span: Span,
+}
+
 /// Represents an import statement in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Import {
-    /// The type of import (Pydes or Rudes)
+    /// The type of import (Des, Pydes or Rudes)
     pub import_type: TokenType,
-    /// The path being imported
-    pub path: String,
-    /// Optional alias for the import
-    pub alias: Option<String>,
+    /// The tree of paths being imported
+    pub tree: UseTree,
     /// The span of the import statement
     pub span: Span,
 }
 
+/// Represents the paths imported by a `des` statement, such as
+/// `core:>{math, io:>File as F}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UseTree {
+    /// The path shared by everything in the tree. Empty for a top-level
+    /// group such as `{a, b}`.
+    pub prefix: Path,
+    /// What is imported from the prefix.
+    pub kind: UseTreeKind,
+    /// The location of the tree in the source code.
+    pub span: Span,
+}
+
+/// Represents what a use tree imports from its prefix.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UseTreeKind {
+    /// The prefix itself, with an optional `as` alias.
+    Simple(Option<String>),
+    /// Everything in the prefix (`prefix:>*`).
+    Glob,
+    /// A group of nested trees (`prefix:>{a, b:>c}`).
+    Nested(Vec<UseTree>),
+}
+
 /// Represents an expression in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Expression {
     /// A literal value.
     Literal(Literal),
@@ -167,16 +728,59 @@ pub enum Expression {
     UnaryOp(UnaryOp),
     /// A variable identifier.
     Identifier(String, Span),
+    /// The `soy` self-reference.
+    SelfRef(Span),
+    /// A multi-segment path such as `Color:>Rojo`.
+    Path(Path),
+    /// An array literal such as `[1, 2, 3]`.
+    Array(Vec<AstNode>, Span),
+    /// A tuple literal such as `(a, b)`. The empty tuple is the unit value.
+    Tuple(Vec<AstNode>, Span),
+    /// A struct or struct-style variant construction.
+    StructLiteral(StructLiteral),
     /// A function call.
     FunctionCall(FunctionCall),
+    /// A built-in call such as `prnt(...)` or `name!(...)`.
+    BuiltinCall(BuiltinCall),
+    /// A method call.
+    MethodCall(MethodCall),
+    /// An `spro` await expression.
+    Await(Await),
+    /// A postfix `?` error propagation.
+    Propagate(Propagate),
+    /// A match expression.
+    Match(Match),
     /// A struct field access.
-    FieldAccess(String, String, Span), // (struct_name, field_name, span)
+    FieldAccess(FieldAccess),
     /// An array access.
-    ArrayAccess(String, Box<AstNode>, Span), // (array_name, index_expr, span)
+    ArrayAccess(ArrayAccess),
+}
+
+impl Expression {
+    /// Returns the location of the expression in the source code.
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Literal(literal) => literal.span(),
+            Expression::BinaryOp(op) => op.span,
+            Expression::UnaryOp(op) => op.span,
+            Expression::Identifier(_, span) | Expression::SelfRef(span) => *span,
+            Expression::Path(path) => path.span,
+            Expression::Array(_, span) | Expression::Tuple(_, span) => *span,
+            Expression::StructLiteral(literal) => literal.span,
+            Expression::FunctionCall(call) => call.span,
+            Expression::BuiltinCall(call) => call.span,
+            Expression::MethodCall(call) => call.span,
+            Expression::Await(await_expr) => await_expr.span,
+            Expression::Propagate(propagate) => propagate.span,
+            Expression::Match(match_expr) => match_expr.span,
+            Expression::FieldAccess(access) => access.span,
+            Expression::ArrayAccess(access) => access.span,
+        }
+    }
 }
 
 /// Represents a statement in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Statement {
     /// A variable declaration.
     Declaration(Declaration),
@@ -186,6 +790,12 @@ pub enum Statement {
     IfStatement(IfStatement),
     /// A loop statement.
     LoopStatement(LoopStatement),
+    /// A for loop.
+    ForStatement(ForStatement),
+    /// A `break` statement.
+    Break(LoopControl),
+    /// A `continue` statement.
+    Continue(LoopControl),
     /// A return statement.
     ReturnStatement(ReturnStatement),
     /// A function definition.
@@ -194,19 +804,65 @@ pub enum Statement {
     Struct(Struct),
     /// An enum definition.
     Enum(Enum),
+    /// An interface definition.
+    Interface(Interface),
+    /// An implementation block.
+    Implementation(Implementation),
     /// An import statement.
     Import(Import),
     /// A block of statements.
     Block(Vec<AstNode>, Span),
+    /// A `snc` block.
+    SyncBlock(SyncBlock),
+    /// An `ate`/`rev` error handling block.
+    TryCatch(TryCatch),
     /// An expression statement.
     Expression(Expression),
 }
 
+impl Statement {
+    /// Returns the location of the statement in the source code.
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Declaration(decl) => decl.span,
+            Statement::Assignment(assign) => assign.span,
+            Statement::IfStatement(stmt) => stmt.span,
+            Statement::LoopStatement(stmt) => stmt.span,
+            Statement::ForStatement(stmt) => stmt.span,
+            Statement::Break(control) | Statement::Continue(control) => control.span,
+            Statement::ReturnStatement(ret) => ret.span,
+            Statement::Function(func) => func.span,
+            Statement::Struct(struct_def) => struct_def.span,
+            Statement::Enum(enum_def) => enum_def.span,
+            Statement::Interface(interface) => interface.span,
+            Statement::Implementation(imp) => imp.span,
+            Statement::Import(import) => import.span,
+            Statement::Block(_, span) => *span,
+            Statement::SyncBlock(block) => block.span,
+            Statement::TryCatch(try_catch) => try_catch.span,
+            Statement::Expression(expr) => expr.span(),
+        }
+    }
+}
+
 /// Represents a node in the Abstract Syntax Tree (AST).
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AstNode {
     /// An expression node.
     Expression(Expression),
     /// A statement node.
     Statement(Statement),
-}
\ No newline at end of file
+    /// A region that failed to parse and was skipped during error recovery.
+    Error(Span),
+}
+
+impl AstNode {
+    /// Returns the location of the node in the source code.
+    pub fn span(&self) -> Span {
+        match self {
+            AstNode::Expression(expr) => expr.span(),
+            AstNode::Statement(stmt) => stmt.span(),
+            AstNode::Error(span) => *span,
+        }
+    }
+}
@@ -0,0 +1,395 @@
+//! Lossless concrete syntax tree for the Kymera language.
+//!
+//! The CST keeps every token of the source, including whitespace and
+//! comments, so that [`SyntaxTree::text`] reproduces the input byte for byte.
+//! Top-level items become [`SyntaxKind::Item`] nodes and every bracketed
+//! region becomes a nested [`SyntaxKind::Group`]. Comments directly above an
+//! item belong to that item, while a comment on the same line after an item
+//! belongs to the item it follows.
+//!
+//! The tree can be lowered to the regular [`AstNode`] representation at any
+//! time by dropping the trivia and running the parser over what remains.
+
+use std::ops::Range;
+
+use crate::ast::AstNode;
+use crate::err::{ParserError, Result};
+use crate::lexer::{Lexer, Token, TokenType};
+use crate::parser::{Edition, Parser};
+use crate::position::Span;
+
+/// Delimiter of a bracketed group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    /// `( ... )`
+    Paren,
+    /// `{ ... }`
+    Brace,
+    /// `[ ... ]`
+    Bracket,
+}
+
+impl Delimiter {
+    /// Returns the delimiter opened by the given token type, if any.
+    fn opened_by(token_type: &TokenType) -> Option<Self> {
+        match token_type {
+            TokenType::LParen => Some(Self::Paren),
+            TokenType::LBrace => Some(Self::Brace),
+            TokenType::LBracket => Some(Self::Bracket),
+            _ => None,
+        }
+    }
+
+    /// Returns the delimiter closed by the given token type, if any.
+    fn closed_by(token_type: &TokenType) -> Option<Self> {
+        match token_type {
+            TokenType::RParen => Some(Self::Paren),
+            TokenType::RBrace => Some(Self::Brace),
+            TokenType::RBracket => Some(Self::Bracket),
+            _ => None,
+        }
+    }
+}
+
+/// The kind of a syntax node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// The whole source file.
+    Root,
+    /// A top-level statement or declaration, with its attached trivia.
+    Item,
+    /// A bracketed group, including its opening and closing tokens.
+    Group(Delimiter),
+}
+
+/// A child of a syntax node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    /// A nested node.
+    Node(SyntaxNode),
+    /// A token, which may be trivia.
+    Token(Token),
+}
+
+/// A node in the concrete syntax tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    /// The kind of node.
+    pub kind: SyntaxKind,
+    /// The children of the node, in source order.
+    pub children: Vec<SyntaxElement>,
+    /// The location of the node in the source code, including trivia.
+    pub span: Span,
+}
+
+impl SyntaxNode {
+    fn new(kind: SyntaxKind) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+            span: Span::default(),
+        }
+    }
+
+    /// Appends a child and widens the span to cover it.
+    fn push(&mut self, element: SyntaxElement) {
+        let span = match &element {
+            SyntaxElement::Node(node) => node.span,
+            SyntaxElement::Token(token) => token.span,
+        };
+        if self.children.is_empty() {
+            self.span = span;
+        } else {
+            self.span.end = span.end;
+        }
+        self.children.push(element);
+    }
+
+    /// Returns every token below this node in source order, trivia included.
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a Token>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// Returns the tokens below this node that the parser cares about.
+    pub fn significant_tokens(&self) -> Vec<Token> {
+        self.tokens()
+            .into_iter()
+            .filter(|token| !token.token_type.is_trivia())
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the exact source text covered by this node.
+    pub fn text(&self) -> String {
+        self.tokens().into_iter().map(|token| token.lexeme.as_str()).collect()
+    }
+
+    /// Returns the documentation comments leading this node.
+    pub fn doc_comments(&self) -> Vec<&Token> {
+        self.children
+            .iter()
+            .map_while(|child| match child {
+                SyntaxElement::Token(token) if token.token_type.is_trivia() => Some(token),
+                _ => None,
+            })
+            .filter(|token| token.token_type == TokenType::Dmt)
+            .collect()
+    }
+
+    /// Lowers this node to AST nodes by parsing its significant tokens
+    /// under the legacy edition.
+    pub fn lower(&self) -> Result<Vec<AstNode>> {
+        self.lower_with_edition(Edition::Legacy)
+    }
+
+    /// Lowers this node to AST nodes by parsing its significant tokens
+    /// under the given edition.
+    pub fn lower_with_edition(&self, edition: Edition) -> Result<Vec<AstNode>> {
+        Parser::with_edition(self.significant_tokens(), edition).parse()
+    }
+}
+
+/// A lossless syntax tree for a Kymera source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTree {
+    root: SyntaxNode,
+    /// The grammar edition the source was parsed under.
+    edition: Edition,
+}
+
+impl SyntaxTree {
+    /// Builds the syntax tree for the given source code using the legacy
+    /// edition.
+    ///
+    /// Only lexical errors are fatal; syntax errors leave the affected tokens
+    /// in an item of their own, exactly like
+    /// [`Parser::parse_with_recovery`] does for the AST.
+    pub fn parse(source: &str) -> Result<Self> {
+        Self::parse_with_edition(source, Edition::Legacy)
+    }
+
+    /// Builds the syntax tree for the given source code under the given
+    /// edition. The edition decides where items end, since `ate` and `si`
+    /// start different statements in each.
+    pub fn parse_with_edition(source: &str, edition: Edition) -> Result<Self> {
+        let tokens = Lexer::new(source).with_edition(edition).tokenize_lossless()?;
+
+        // Indices of the significant tokens within the lossless stream.
+        let significant: Vec<usize> = tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| !token.token_type.is_trivia())
+            .map(|(index, _)| index)
+            .collect();
+        let parser_tokens = significant.iter().map(|&index| tokens[index].clone()).collect();
+        let (items, _) = Parser::with_edition(parser_tokens, edition).parse_items_with_recovery();
+
+        // Map each item's significant token range onto the lossless stream.
+        let item_ranges: Vec<Range<usize>> = items
+            .iter()
+            .filter(|(_, range)| !range.is_empty())
+            .map(|(_, range)| significant[range.start]..significant[range.end - 1] + 1)
+            .collect();
+
+        Ok(Self {
+            root: Self::build(tokens, &item_ranges),
+            edition,
+        })
+    }
+
+    /// Assembles the tree from lossless tokens and the item token ranges.
+    fn build(tokens: Vec<Token>, item_ranges: &[Range<usize>]) -> SyntaxNode {
+        let mut root = SyntaxNode::new(SyntaxKind::Root);
+        let mut tokens = tokens.into_iter().enumerate().peekable();
+        let mut leading = Vec::new();
+
+        for (item_index, range) in item_ranges.iter().enumerate() {
+            let mut item = SyntaxNode::new(SyntaxKind::Item);
+
+            // Trivia before the item that was not claimed by the previous one.
+            for token in leading.drain(..) {
+                item.push(SyntaxElement::Token(token));
+            }
+            while let Some((_, token)) = tokens.next_if(|(index, _)| *index < range.start) {
+                item.push(SyntaxElement::Token(token));
+            }
+
+            let mut groups: Vec<SyntaxNode> = Vec::new();
+            while let Some((_, token)) = tokens.next_if(|(index, _)| *index < range.end) {
+                Self::push_grouped(&mut item, &mut groups, token);
+            }
+            while let Some(group) = groups.pop() {
+                Self::push_to(&mut item, &mut groups, SyntaxElement::Node(group));
+            }
+
+            // Trailing trivia on the same line as the end of the item.
+            while let Some((_, token)) = tokens.next_if(|(_, token)| {
+                token.token_type.is_trivia()
+                    && !(token.token_type == TokenType::Whitespace && token.lexeme.contains('\n'))
+            }) {
+                item.push(SyntaxElement::Token(token));
+            }
+
+            // Remaining trivia up to the next item leads that item.
+            if let Some(next) = item_ranges.get(item_index + 1) {
+                while let Some((_, token)) = tokens.next_if(|(index, _)| *index < next.start) {
+                    leading.push(token);
+                }
+            }
+
+            root.push(SyntaxElement::Node(item));
+        }
+
+        for (_, token) in tokens {
+            root.push(SyntaxElement::Token(token));
+        }
+        root
+    }
+
+    /// Adds a token to the innermost open group, opening or closing groups
+    /// as brackets are encountered.
+    fn push_grouped(item: &mut SyntaxNode, groups: &mut Vec<SyntaxNode>, token: Token) {
+        if let Some(delimiter) = Delimiter::opened_by(&token.token_type) {
+            let mut group = SyntaxNode::new(SyntaxKind::Group(delimiter));
+            group.push(SyntaxElement::Token(token));
+            groups.push(group);
+            return;
+        }
+
+        let closes = Delimiter::closed_by(&token.token_type);
+        Self::push_to(item, groups, SyntaxElement::Token(token));
+        if closes.is_some()
+            && groups.last().map(|group| group.kind) == closes.map(SyntaxKind::Group)
+        {
+            let group = groups.pop().expect("group checked above");
+            Self::push_to(item, groups, SyntaxElement::Node(group));
+        }
+    }
+
+    fn push_to(item: &mut SyntaxNode, groups: &mut [SyntaxNode], element: SyntaxElement) {
+        match groups.last_mut() {
+            Some(group) => group.push(element),
+            None => item.push(element),
+        }
+    }
+
+    /// Returns the root node.
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// Returns the top-level items.
+    pub fn items(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.root.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) if node.kind == SyntaxKind::Item => Some(node),
+            _ => None,
+        })
+    }
+
+    /// Returns the exact source text the tree was built from.
+    pub fn text(&self) -> String {
+        self.root.text()
+    }
+
+    /// Returns the grammar edition the tree was parsed under.
+    pub fn edition(&self) -> Edition {
+        self.edition
+    }
+
+    /// Lowers the tree to AST nodes under its edition, recovering from
+    /// syntax errors.
+    pub fn lower(&self) -> (Vec<AstNode>, Vec<ParserError>) {
+        Parser::with_edition(self.root.significant_tokens(), self.edition).parse_with_recovery()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Statement;
+
+    const SOURCE: &str = "// header\n\n/// Adds one.\nfnc inc(x) {\n    ret x + 1; /* done */\n}\ndjq y = \"a\\tb\"; // trailing\n";
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        let tree = SyntaxTree::parse(SOURCE).unwrap();
+        assert_eq!(tree.text(), SOURCE);
+    }
+
+    #[test]
+    fn test_comments_attach_to_items() {
+        let tree = SyntaxTree::parse(SOURCE).unwrap();
+        let items: Vec<_> = tree.items().collect();
+        assert_eq!(items.len(), 2);
+
+        let docs = items[0].doc_comments();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].lexeme, "/// Adds one.");
+        assert!(items[0].text().starts_with("// header"));
+        assert!(items[1].text().ends_with("// trailing"));
+    }
+
+    #[test]
+    fn test_groups_nest() {
+        let tree = SyntaxTree::parse("fnc f(a) { ret (a); }").unwrap();
+        let item = tree.items().next().unwrap();
+        let body = item
+            .children
+            .iter()
+            .find_map(|child| match child {
+                SyntaxElement::Node(node) if node.kind == SyntaxKind::Group(Delimiter::Brace) => Some(node),
+                _ => None,
+            })
+            .unwrap();
+        assert!(body
+            .children
+            .iter()
+            .any(|child| matches!(child, SyntaxElement::Node(node) if node.kind == SyntaxKind::Group(Delimiter::Paren))));
+    }
+
+    #[test]
+    fn test_lower_matches_parser() {
+        let tree = SyntaxTree::parse(SOURCE).unwrap();
+        let (nodes, errors) = tree.lower();
+        assert!(errors.is_empty());
+        assert!(matches!(nodes[0], AstNode::Statement(Statement::Function(_))));
+        assert!(matches!(nodes[1], AstNode::Statement(Statement::Declaration(_))));
+
+        let item = tree.items().next().unwrap();
+        assert_eq!(item.lower().unwrap(), vec![nodes[0].clone()]);
+    }
+
+    #[test]
+    fn test_items_follow_edition() {
+        let source = "ate {\n    usar(x);\n} rev e {\n    usar(e);\n}\nsi x > 1 { usar(x); } sino { usar(0); }\n";
+        let tree = SyntaxTree::parse_with_edition(source, Edition::V2).unwrap();
+        assert_eq!(tree.edition(), Edition::V2);
+        assert_eq!(tree.text(), source);
+        let items: Vec<_> = tree.items().map(SyntaxNode::text).collect();
+        assert_eq!(items.len(), 2);
+        assert!(items[0].ends_with("usar(e);\n}"), "{:?}", items);
+        assert!(items[1].contains("sino"), "{:?}", items);
+
+        let (nodes, errors) = tree.lower();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(matches!(nodes[0], AstNode::Statement(Statement::TryCatch(_))));
+        assert!(matches!(nodes[1], AstNode::Statement(Statement::IfStatement(_))));
+        let item = tree.items().nth(1).unwrap();
+        assert_eq!(item.lower_with_edition(Edition::V2).unwrap(), vec![nodes[1].clone()]);
+
+        // Under the legacy edition `si` is a name, so the same text differs.
+        let (_, errors) = SyntaxTree::parse(source).unwrap().lower();
+        assert!(!errors.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+//! Rich diagnostics for parser errors.
+//!
+//! [`ParserError`] implements [`miette::Diagnostic`], giving each error a
+//! stable code, a label on the offending code and, where there is something
+//! useful to say, a help message. When the input ends inside an unclosed
+//! `(`, `[` or `{`, a second label points at the opening delimiter. [`Renderer`] prints any diagnostic as a
+//! rustc-style report with labeled snippets of the file it came from.
+
+use std::fmt;
+
+use miette::{
+    Diagnostic, GraphicalReportHandler, GraphicalTheme, LabeledSpan, NamedSource, Severity, SourceCode, SourceSpan,
+};
+
+use crate::err::ParserError;
+use crate::position::Span;
+
+impl From<Span> for SourceSpan {
+    fn from(span: Span) -> Self {
+        SourceSpan::new(span.start.offset.into(), span.end.offset.saturating_sub(span.start.offset))
+    }
+}
+
+impl ParserError {
+    /// Returns true if the error was caused by the input ending early. The
+    /// parser reports running out of tokens as finding an empty one.
+    pub(crate) fn at_end(&self) -> bool {
+        match self {
+            Self::UnexpectedEof { .. } => true,
+            Self::UnexpectedToken { found, .. } => found.is_empty(),
+            Self::Unclosed { error, .. } => error.at_end(),
+            _ => false,
+        }
+    }
+}
+
+impl Diagnostic for ParserError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let code = match self {
+            Self::Unclosed { error, .. } => return error.code(),
+            Self::Lexer { .. } => "kymera::lex",
+            Self::Parser { .. } => "kymera::syntax",
+            Self::UnexpectedToken { .. } => "kymera::unexpected_token",
+            Self::UnexpectedEof { .. } => "kymera::unexpected_eof",
+            Self::Io(_) => "kymera::io",
+            Self::Serialization(_) => "kymera::serialization",
+            Self::Internal(_) => "kymera::internal",
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        match self {
+            Self::Unclosed { .. } => Some(Box::new("add the missing closing delimiter")),
+            _ if self.at_end() => Some(Box::new(
+                "the file ends inside an unfinished construct; check for a missing `}`, `)` or `;`",
+            )),
+            Self::Internal(_) => Some(Box::new("this is a bug in the Kymera parser")),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (span, label) = match self {
+            Self::Unclosed { error, delimiter } => {
+                let opened = LabeledSpan::new_with_span(Some("unclosed delimiter opened here".to_string()), *delimiter);
+                return Some(Box::new(error.labels().into_iter().flatten().chain(std::iter::once(opened))));
+            }
+            Self::Lexer { span, .. } => (*span, "invalid token".to_string()),
+            Self::Parser { span, .. } => (*span, "here".to_string()),
+            _ if self.at_end() => (self.span()?, "input ends here".to_string()),
+            Self::UnexpectedToken { span, expected, .. } => (*span, format!("expected {}", expected)),
+            _ => return None,
+        };
+        Some(Box::new(std::iter::once(LabeledSpan::new_primary_with_span(Some(label), span))))
+    }
+}
+
+/// Prints diagnostics as rustc-style reports with labeled source snippets.
+pub struct Renderer {
+    handler: GraphicalReportHandler,
+}
+
+impl Renderer {
+    /// Creates a renderer that prints plain Unicode text.
+    pub fn new() -> Self {
+        Self {
+            handler: GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor()),
+        }
+    }
+
+    /// Creates a renderer that colors its reports with ANSI escapes, for
+    /// printing to a terminal.
+    pub fn colored() -> Self {
+        Self {
+            handler: GraphicalReportHandler::new_themed(GraphicalTheme::unicode()),
+        }
+    }
+
+    /// Sets the width reports are wrapped at.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.handler = self.handler.with_width(width);
+        self
+    }
+
+    /// Renders a diagnostic whose spans point into `source`, the contents of
+    /// the file `name`.
+    pub fn render(&self, name: &str, source: &str, diagnostic: &dyn Diagnostic) -> String {
+        let located = WithSource {
+            diagnostic,
+            source: NamedSource::new(name, source.to_string()),
+        };
+        let mut out = String::new();
+        if self.handler.render_report(&mut out, &located).is_err() {
+            out = located.to_string();
+        }
+        out
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A diagnostic together with the source code its spans point into.
+struct WithSource<'a> {
+    diagnostic: &'a dyn Diagnostic,
+    source: NamedSource<String>,
+}
+
+impl fmt::Debug for WithSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.diagnostic, f)
+    }
+}
+
+impl fmt::Display for WithSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.diagnostic, f)
+    }
+}
+
+impl std::error::Error for WithSource<'_> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.diagnostic.source()
+    }
+}
+
+impl Diagnostic for WithSource<'_> {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.diagnostic.code()
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.diagnostic.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.diagnostic.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.diagnostic.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        self.diagnostic.labels()
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        self.diagnostic.related()
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn Diagnostic> {
+        self.diagnostic.diagnostic_source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_render_parser_error() {
+        let source = "fnc f() {\n    djq x = ;\n}\n";
+        let error = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap_err();
+        let report = Renderer::new().with_width(80).render("main.ky", source, &error);
+
+        assert!(report.contains("kymera::syntax"), "{}", report);
+        assert!(report.contains("[main.ky:2:13]"), "{}", report);
+        assert!(report.contains("    djq x = ;"), "{}", report);
+        assert!(report.contains(&error.message()), "{}", report);
+        assert!(!report.contains("Span {"), "{}", report);
+
+        assert!(report.contains(" here"), "{}", report);
+
+        let error = Parser::new(Lexer::new("fnc f() {").tokenize().unwrap()).parse().unwrap_err();
+        let report = Renderer::new().render("main.ky", "fnc f() {", &error);
+        assert!(report.contains("help:"), "{}", report);
+        assert_eq!(error.code().unwrap().to_string(), "kymera::unexpected_token");
+        let labels: Vec<_> = error.labels().unwrap().collect();
+        assert_eq!(labels.len(), 2);
+        assert!(labels[0].primary());
+        assert_eq!(labels[1].label(), Some("unclosed delimiter opened here"));
+        assert_eq!(labels[1].offset(), 8);
+
+        let source = "djq s = \"abc;";
+        let error = Lexer::new(source).tokenize().unwrap_err();
+        let report = Renderer::new().render("main.ky", source, &error);
+        assert!(report.contains("kymera::lex"), "{}", report);
+        assert!(report.contains("invalid token"), "{}", report);
+    }
+}
@@ -11,33 +11,44 @@ use crate::position::Span;
 /// Parser-specific error type
 #[derive(Debug, Error)]
 pub enum ParserError {
-    #[error("Lexer error at {span:?}: {message}")]
+    #[error("Lexer error at {span}: {message}")]
     Lexer {
         span: Span,
         message: String,
     },
 
-    #[error("Parser error at {span:?}: {message}")]
+    #[error("Parser error at {span}: {message}")]
     Parser {
         span: Span,
         message: String,
     },
 
-    #[error("Unexpected token at {span:?}: expected {expected}, found {found}")]
+    #[error("Unexpected token at {span}: expected {expected}, found {found}")]
     UnexpectedToken {
         span: Span,
         expected: String,
         found: String,
     },
 
-    #[error("Unexpected end of input at {span:?}")]
+    #[error("Unexpected end of input at {span}")]
     UnexpectedEof {
         span: Span,
     },
 
+    /// An error at the end of the input, together with the innermost
+    /// delimiter that was opened but never closed.
+    #[error("{error}")]
+    Unclosed {
+        error: Box<ParserError>,
+        delimiter: Span,
+    },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -79,6 +90,11 @@ impl ParserError {
         Self::UnexpectedEof { span }
     }
 
+    /// Creates a new serialization error
+    pub fn serialization(msg: impl Into<String>) -> Self {
+        Self::Serialization(msg.into())
+    }
+
     /// Creates a new internal error
     pub fn internal(msg: impl Into<String>) -> Self {
         Self::Internal(msg.into())
@@ -91,6 +107,7 @@ impl ParserError {
             Self::Parser { span, .. } => Some(*span),
             Self::UnexpectedToken { span, .. } => Some(*span),
             Self::UnexpectedEof { span } => Some(*span),
+            Self::Unclosed { error, .. } => error.span(),
             _ => None,
         }
     }
@@ -102,7 +119,9 @@ impl ParserError {
             Self::Parser { message, .. } => message.clone(),
             Self::UnexpectedToken { expected, found, .. } => format!("expected {}, found {}", expected, found),
             Self::UnexpectedEof { .. } => "unexpected end of input".to_string(),
+            Self::Unclosed { error, .. } => error.message(),
             Self::Io(e) => e.to_string(),
+            Self::Serialization(msg) => msg.clone(),
             Self::Internal(msg) => msg.clone(),
         }
     }
@@ -0,0 +1,562 @@
+//! Owned rewriting of the Kymera AST.
+//!
+//! [`Fold`] takes each node by value and returns its replacement. Every
+//! method defaults to the free function of the same name, which folds the
+//! node's children and rebuilds it, so an implementation only overrides the
+//! methods for the nodes it rewrites.
+
+use crate::ast::{
+    ArrayAccess, Attribute, AttributeArg, AstNode, Await, BinaryOp, BuiltinCall, Declaration, Assignment, Enum,
+    EnumVariant, Expression, FieldAccess, FieldInit, FieldPattern, ForStatement, Function, FunctionCall,
+    FunctionTypeExpr, GenericParam, IfStatement, Implementation, Import, Interface, InterfaceMethod, Literal,
+    LoopControl, LoopStatement, Match, MatchArm, MethodCall, NamedType, Param, Path, Pattern, Propagate, Receiver,
+    ReturnStatement, Statement, Struct, StructField, StructLiteral, StructPattern, SyncBlock, TryCatch, TypeExpr,
+    UnaryOp, UseTree, UseTreeKind, VariantPattern, VariantPayload,
+};
+use crate::position::Span;
+
+/// Rewrites the nodes of an AST by value.
+pub trait Fold {
+    /// Folds any AST node.
+    fn fold_node(&mut self, node: AstNode) -> AstNode {
+        fold_node(self, node)
+    }
+
+    /// Folds a statement.
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        fold_statement(self, statement)
+    }
+
+    /// Folds an expression.
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        fold_expression(self, expression)
+    }
+
+    /// Folds the statements of a body, such as a function or loop body.
+    fn fold_block(&mut self, body: Vec<AstNode>) -> Vec<AstNode> {
+        fold_block(self, body)
+    }
+
+    /// Folds a function or method definition.
+    fn fold_function(&mut self, function: Function) -> Function {
+        fold_function(self, function)
+    }
+
+    /// Folds a method declared by an interface.
+    fn fold_interface_method(&mut self, method: InterfaceMethod) -> InterfaceMethod {
+        fold_interface_method(self, method)
+    }
+
+    /// Folds a function parameter.
+    fn fold_param(&mut self, param: Param) -> Param {
+        fold_param(self, param)
+    }
+
+    /// Folds a generic parameter.
+    fn fold_generic_param(&mut self, param: GenericParam) -> GenericParam {
+        fold_generic_param(self, param)
+    }
+
+    /// Folds an item attribute.
+    fn fold_attribute(&mut self, attribute: Attribute) -> Attribute {
+        fold_attribute(self, attribute)
+    }
+
+    /// Folds a field of a struct or struct-style variant.
+    fn fold_struct_field(&mut self, field: StructField) -> StructField {
+        fold_struct_field(self, field)
+    }
+
+    /// Folds an enum variant.
+    fn fold_enum_variant(&mut self, variant: EnumVariant) -> EnumVariant {
+        fold_enum_variant(self, variant)
+    }
+
+    /// Folds a match arm.
+    fn fold_match_arm(&mut self, arm: MatchArm) -> MatchArm {
+        fold_match_arm(self, arm)
+    }
+
+    /// Folds a pattern.
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        fold_pattern(self, pattern)
+    }
+
+    /// Folds a type annotation.
+    fn fold_type(&mut self, ty: TypeExpr) -> TypeExpr {
+        fold_type(self, ty)
+    }
+
+    /// Folds a `:>`-separated path.
+    fn fold_path(&mut self, path: Path) -> Path {
+        fold_path(self, path)
+    }
+
+    /// Folds the use tree of an import.
+    fn fold_use_tree(&mut self, tree: UseTree) -> UseTree {
+        fold_use_tree(self, tree)
+    }
+
+    /// Folds a literal.
+    fn fold_literal(&mut self, literal: Literal) -> Literal {
+        fold_literal(self, literal)
+    }
+
+    /// Folds a span. Returns it unchanged by default.
+    fn fold_span(&mut self, span: Span) -> Span {
+        span
+    }
+}
+
+/// Folds a boxed node in place.
+fn fold_boxed<F: Fold + ?Sized>(folder: &mut F, node: Box<AstNode>) -> Box<AstNode> {
+    Box::new(folder.fold_node(*node))
+}
+
+/// Folds a list of nodes that is not a body, such as call arguments.
+fn fold_nodes<F: Fold + ?Sized>(folder: &mut F, nodes: Vec<AstNode>) -> Vec<AstNode> {
+    nodes.into_iter().map(|node| folder.fold_node(node)).collect()
+}
+
+fn fold_types<F: Fold + ?Sized>(folder: &mut F, types: Vec<TypeExpr>) -> Vec<TypeExpr> {
+    types.into_iter().map(|ty| folder.fold_type(ty)).collect()
+}
+
+fn fold_attributes<F: Fold + ?Sized>(folder: &mut F, attributes: Vec<Attribute>) -> Vec<Attribute> {
+    attributes.into_iter().map(|attribute| folder.fold_attribute(attribute)).collect()
+}
+
+fn fold_generic_params<F: Fold + ?Sized>(folder: &mut F, generics: Vec<GenericParam>) -> Vec<GenericParam> {
+    generics.into_iter().map(|generic| folder.fold_generic_param(generic)).collect()
+}
+
+fn fold_struct_fields<F: Fold + ?Sized>(folder: &mut F, fields: Vec<StructField>) -> Vec<StructField> {
+    fields.into_iter().map(|field| folder.fold_struct_field(field)).collect()
+}
+
+fn fold_receiver<F: Fold + ?Sized>(folder: &mut F, receiver: Option<Receiver>) -> Option<Receiver> {
+    receiver.map(|receiver| Receiver {
+        span: folder.fold_span(receiver.span),
+        ..receiver
+    })
+}
+
+/// Folds the children of an AST node.
+pub fn fold_node<F: Fold + ?Sized>(folder: &mut F, node: AstNode) -> AstNode {
+    match node {
+        AstNode::Expression(expression) => AstNode::Expression(folder.fold_expression(expression)),
+        AstNode::Statement(statement) => AstNode::Statement(folder.fold_statement(statement)),
+        AstNode::Error(span) => AstNode::Error(folder.fold_span(span)),
+    }
+}
+
+/// Folds the children of a statement.
+pub fn fold_statement<F: Fold + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Declaration(decl) => Statement::Declaration(Declaration {
+            pattern: folder.fold_pattern(decl.pattern),
+            ty: decl.ty.map(|ty| folder.fold_type(ty)),
+            value: fold_boxed(folder, decl.value),
+            span: folder.fold_span(decl.span),
+            ..decl
+        }),
+        Statement::Assignment(assign) => Statement::Assignment(Assignment {
+            target: fold_boxed(folder, assign.target),
+            value: fold_boxed(folder, assign.value),
+            span: folder.fold_span(assign.span),
+            ..assign
+        }),
+        Statement::IfStatement(stmt) => Statement::IfStatement(IfStatement {
+            condition: fold_boxed(folder, stmt.condition),
+            body: folder.fold_block(stmt.body),
+            else_body: stmt.else_body.map(|else_body| folder.fold_block(else_body)),
+            span: folder.fold_span(stmt.span),
+        }),
+        Statement::LoopStatement(stmt) => Statement::LoopStatement(LoopStatement {
+            condition: fold_boxed(folder, stmt.condition),
+            body: folder.fold_block(stmt.body),
+            span: folder.fold_span(stmt.span),
+            ..stmt
+        }),
+        Statement::ForStatement(stmt) => Statement::ForStatement(ForStatement {
+            pattern: folder.fold_pattern(stmt.pattern),
+            iterable: fold_boxed(folder, stmt.iterable),
+            body: folder.fold_block(stmt.body),
+            span: folder.fold_span(stmt.span),
+            ..stmt
+        }),
+        Statement::Break(control) => Statement::Break(LoopControl {
+            span: folder.fold_span(control.span),
+            ..control
+        }),
+        Statement::Continue(control) => Statement::Continue(LoopControl {
+            span: folder.fold_span(control.span),
+            ..control
+        }),
+        Statement::ReturnStatement(stmt) => Statement::ReturnStatement(ReturnStatement {
+            value: fold_boxed(folder, stmt.value),
+            span: folder.fold_span(stmt.span),
+        }),
+        Statement::Function(function) => Statement::Function(folder.fold_function(function)),
+        Statement::Struct(struct_def) => Statement::Struct(Struct {
+            attributes: fold_attributes(folder, struct_def.attributes),
+            fields: fold_struct_fields(folder, struct_def.fields),
+            span: folder.fold_span(struct_def.span),
+            ..struct_def
+        }),
+        Statement::Enum(enum_def) => Statement::Enum(Enum {
+            attributes: fold_attributes(folder, enum_def.attributes),
+            variants: enum_def
+                .variants
+                .into_iter()
+                .map(|variant| folder.fold_enum_variant(variant))
+                .collect(),
+            span: folder.fold_span(enum_def.span),
+            ..enum_def
+        }),
+        Statement::Interface(interface) => Statement::Interface(Interface {
+            generics: fold_generic_params(folder, interface.generics),
+            super_interfaces: fold_types(folder, interface.super_interfaces),
+            methods: interface
+                .methods
+                .into_iter()
+                .map(|method| folder.fold_interface_method(method))
+                .collect(),
+            span: folder.fold_span(interface.span),
+            ..interface
+        }),
+        Statement::Implementation(imp) => Statement::Implementation(Implementation {
+            attributes: fold_attributes(folder, imp.attributes),
+            interface: imp.interface.map(|interface| folder.fold_type(interface)),
+            target: folder.fold_type(imp.target),
+            methods: imp.methods.into_iter().map(|method| folder.fold_function(method)).collect(),
+            span: folder.fold_span(imp.span),
+        }),
+        Statement::Import(import) => Statement::Import(Import {
+            tree: folder.fold_use_tree(import.tree),
+            span: folder.fold_span(import.span),
+            ..import
+        }),
+        Statement::Block(body, span) => Statement::Block(folder.fold_block(body), folder.fold_span(span)),
+        Statement::SyncBlock(block) => Statement::SyncBlock(SyncBlock {
+            body: folder.fold_block(block.body),
+            span: folder.fold_span(block.span),
+        }),
+        Statement::TryCatch(try_catch) => Statement::TryCatch(TryCatch {
+            body: folder.fold_block(try_catch.body),
+            binding_span: folder.fold_span(try_catch.binding_span),
+            handler: folder.fold_block(try_catch.handler),
+            span: folder.fold_span(try_catch.span),
+            ..try_catch
+        }),
+        Statement::Expression(expression) => Statement::Expression(folder.fold_expression(expression)),
+    }
+}
+
+/// Folds the children of an expression.
+pub fn fold_expression<F: Fold + ?Sized>(folder: &mut F, expression: Expression) -> Expression {
+    match expression {
+        Expression::Literal(literal) => Expression::Literal(folder.fold_literal(literal)),
+        Expression::BinaryOp(op) => Expression::BinaryOp(BinaryOp {
+            left: fold_boxed(folder, op.left),
+            right: fold_boxed(folder, op.right),
+            span: folder.fold_span(op.span),
+            ..op
+        }),
+        Expression::UnaryOp(op) => Expression::UnaryOp(UnaryOp {
+            operand: fold_boxed(folder, op.operand),
+            span: folder.fold_span(op.span),
+            ..op
+        }),
+        Expression::Identifier(name, span) => Expression::Identifier(name, folder.fold_span(span)),
+        Expression::SelfRef(span) => Expression::SelfRef(folder.fold_span(span)),
+        Expression::Path(path) => Expression::Path(folder.fold_path(path)),
+        Expression::Array(elements, span) => {
+            Expression::Array(fold_nodes(folder, elements), folder.fold_span(span))
+        }
+        Expression::Tuple(elements, span) => {
+            Expression::Tuple(fold_nodes(folder, elements), folder.fold_span(span))
+        }
+        Expression::StructLiteral(literal) => Expression::StructLiteral(StructLiteral {
+            path: folder.fold_path(literal.path),
+            fields: literal
+                .fields
+                .into_iter()
+                .map(|field| FieldInit {
+                    value: folder.fold_node(field.value),
+                    span: folder.fold_span(field.span),
+                    ..field
+                })
+                .collect(),
+            span: folder.fold_span(literal.span),
+        }),
+        Expression::FunctionCall(call) => Expression::FunctionCall(FunctionCall {
+            callee: fold_boxed(folder, call.callee),
+            args: fold_nodes(folder, call.args),
+            span: folder.fold_span(call.span),
+        }),
+        Expression::BuiltinCall(call) => Expression::BuiltinCall(BuiltinCall {
+            args: fold_nodes(folder, call.args),
+            span: folder.fold_span(call.span),
+            ..call
+        }),
+        Expression::MethodCall(call) => Expression::MethodCall(MethodCall {
+            receiver: fold_boxed(folder, call.receiver),
+            args: fold_nodes(folder, call.args),
+            span: folder.fold_span(call.span),
+            ..call
+        }),
+        Expression::Await(await_expr) => Expression::Await(Await {
+            value: fold_boxed(folder, await_expr.value),
+            span: folder.fold_span(await_expr.span),
+        }),
+        Expression::Propagate(propagate) => Expression::Propagate(Propagate {
+            value: fold_boxed(folder, propagate.value),
+            span: folder.fold_span(propagate.span),
+        }),
+        Expression::Match(match_expr) => Expression::Match(Match {
+            scrutinee: fold_boxed(folder, match_expr.scrutinee),
+            arms: match_expr.arms.into_iter().map(|arm| folder.fold_match_arm(arm)).collect(),
+            span: folder.fold_span(match_expr.span),
+        }),
+        Expression::FieldAccess(access) => Expression::FieldAccess(FieldAccess {
+            object: fold_boxed(folder, access.object),
+            span: folder.fold_span(access.span),
+            ..access
+        }),
+        Expression::ArrayAccess(access) => Expression::ArrayAccess(ArrayAccess {
+            array: fold_boxed(folder, access.array),
+            index: fold_boxed(folder, access.index),
+            span: folder.fold_span(access.span),
+        }),
+    }
+}
+
+/// Folds the statements of a body.
+pub fn fold_block<F: Fold + ?Sized>(folder: &mut F, body: Vec<AstNode>) -> Vec<AstNode> {
+    fold_nodes(folder, body)
+}
+
+/// Folds the children of a function definition.
+pub fn fold_function<F: Fold + ?Sized>(folder: &mut F, function: Function) -> Function {
+    Function {
+        attributes: fold_attributes(folder, function.attributes),
+        generics: fold_generic_params(folder, function.generics),
+        receiver: fold_receiver(folder, function.receiver),
+        params: function.params.into_iter().map(|param| folder.fold_param(param)).collect(),
+        return_type: function.return_type.map(|ty| folder.fold_type(ty)),
+        body: folder.fold_block(function.body),
+        span: folder.fold_span(function.span),
+        ..function
+    }
+}
+
+/// Folds the children of an interface method.
+pub fn fold_interface_method<F: Fold + ?Sized>(folder: &mut F, method: InterfaceMethod) -> InterfaceMethod {
+    InterfaceMethod {
+        generics: fold_generic_params(folder, method.generics),
+        receiver: fold_receiver(folder, method.receiver),
+        params: method.params.into_iter().map(|param| folder.fold_param(param)).collect(),
+        return_type: method.return_type.map(|ty| folder.fold_type(ty)),
+        body: method.body.map(|body| folder.fold_block(body)),
+        span: folder.fold_span(method.span),
+        ..method
+    }
+}
+
+/// Folds the children of a function parameter.
+pub fn fold_param<F: Fold + ?Sized>(folder: &mut F, param: Param) -> Param {
+    Param {
+        ty: param.ty.map(|ty| folder.fold_type(ty)),
+        span: folder.fold_span(param.span),
+        ..param
+    }
+}
+
+/// Folds the children of a generic parameter.
+pub fn fold_generic_param<F: Fold + ?Sized>(folder: &mut F, param: GenericParam) -> GenericParam {
+    GenericParam {
+        bounds: fold_types(folder, param.bounds),
+        span: folder.fold_span(param.span),
+        ..param
+    }
+}
+
+/// Folds the children of an attribute.
+pub fn fold_attribute<F: Fold + ?Sized>(folder: &mut F, attribute: Attribute) -> Attribute {
+    Attribute {
+        args: attribute
+            .args
+            .into_iter()
+            .map(|arg| match arg {
+                AttributeArg::Literal(literal) => AttributeArg::Literal(folder.fold_literal(literal)),
+                AttributeArg::Identifier(name, span) => AttributeArg::Identifier(name, folder.fold_span(span)),
+                AttributeArg::NameValue(name, literal, span) => {
+                    AttributeArg::NameValue(name, folder.fold_literal(literal), folder.fold_span(span))
+                }
+            })
+            .collect(),
+        span: folder.fold_span(attribute.span),
+        ..attribute
+    }
+}
+
+/// Folds the children of a struct field.
+pub fn fold_struct_field<F: Fold + ?Sized>(folder: &mut F, field: StructField) -> StructField {
+    StructField {
+        attributes: fold_attributes(folder, field.attributes),
+        ty: folder.fold_type(field.ty),
+        span: folder.fold_span(field.span),
+        ..field
+    }
+}
+
+/// Folds the children of an enum variant.
+pub fn fold_enum_variant<F: Fold + ?Sized>(folder: &mut F, variant: EnumVariant) -> EnumVariant {
+    EnumVariant {
+        payload: match variant.payload {
+            VariantPayload::Unit => VariantPayload::Unit,
+            VariantPayload::Tuple(types) => VariantPayload::Tuple(fold_types(folder, types)),
+            VariantPayload::Struct(fields) => VariantPayload::Struct(fold_struct_fields(folder, fields)),
+        },
+        span: folder.fold_span(variant.span),
+        ..variant
+    }
+}
+
+/// Folds the children of a match arm.
+pub fn fold_match_arm<F: Fold + ?Sized>(folder: &mut F, arm: MatchArm) -> MatchArm {
+    MatchArm {
+        pattern: folder.fold_pattern(arm.pattern),
+        guard: arm.guard.map(|guard| fold_boxed(folder, guard)),
+        body: fold_boxed(folder, arm.body),
+        span: folder.fold_span(arm.span),
+    }
+}
+
+/// Folds the children of a pattern.
+pub fn fold_pattern<F: Fold + ?Sized>(folder: &mut F, pattern: Pattern) -> Pattern {
+    match pattern {
+        Pattern::Wildcard(span) => Pattern::Wildcard(folder.fold_span(span)),
+        Pattern::Literal(literal) => Pattern::Literal(folder.fold_literal(literal)),
+        Pattern::Identifier(name, span) => Pattern::Identifier(name, folder.fold_span(span)),
+        Pattern::Variant(variant) => Pattern::Variant(VariantPattern {
+            path: folder.fold_path(variant.path),
+            payload: variant
+                .payload
+                .map(|patterns| patterns.into_iter().map(|pattern| folder.fold_pattern(pattern)).collect()),
+            span: folder.fold_span(variant.span),
+        }),
+        Pattern::Struct(struct_pattern) => Pattern::Struct(StructPattern {
+            path: folder.fold_path(struct_pattern.path),
+            fields: struct_pattern
+                .fields
+                .into_iter()
+                .map(|field| FieldPattern {
+                    pattern: folder.fold_pattern(field.pattern),
+                    span: folder.fold_span(field.span),
+                    ..field
+                })
+                .collect(),
+            span: folder.fold_span(struct_pattern.span),
+            ..struct_pattern
+        }),
+        Pattern::Tuple(patterns, span) => Pattern::Tuple(
+            patterns.into_iter().map(|pattern| folder.fold_pattern(pattern)).collect(),
+            folder.fold_span(span),
+        ),
+    }
+}
+
+/// Folds the children of a type annotation.
+pub fn fold_type<F: Fold + ?Sized>(folder: &mut F, ty: TypeExpr) -> TypeExpr {
+    match ty {
+        TypeExpr::Primitive(name, span) => TypeExpr::Primitive(name, folder.fold_span(span)),
+        TypeExpr::Named(named) => TypeExpr::Named(Box::new(NamedType {
+            path: folder.fold_path(named.path),
+            args: fold_types(folder, named.args),
+            span: folder.fold_span(named.span),
+        })),
+        TypeExpr::Slice(element, span) => {
+            TypeExpr::Slice(Box::new(folder.fold_type(*element)), folder.fold_span(span))
+        }
+        TypeExpr::Array(element, len, span) => {
+            TypeExpr::Array(Box::new(folder.fold_type(*element)), len, folder.fold_span(span))
+        }
+        TypeExpr::Tuple(elements, span) => TypeExpr::Tuple(fold_types(folder, elements), folder.fold_span(span)),
+        TypeExpr::Function(function) => TypeExpr::Function(FunctionTypeExpr {
+            params: fold_types(folder, function.params),
+            return_type: function.return_type.map(|ty| Box::new(folder.fold_type(*ty))),
+            span: folder.fold_span(function.span),
+        }),
+    }
+}
+
+/// Folds the children of a path.
+pub fn fold_path<F: Fold + ?Sized>(folder: &mut F, path: Path) -> Path {
+    Path {
+        span: folder.fold_span(path.span),
+        ..path
+    }
+}
+
+/// Folds the children of a use tree.
+pub fn fold_use_tree<F: Fold + ?Sized>(folder: &mut F, tree: UseTree) -> UseTree {
+    UseTree {
+        prefix: folder.fold_path(tree.prefix),
+        kind: match tree.kind {
+            UseTreeKind::Nested(trees) => {
+                UseTreeKind::Nested(trees.into_iter().map(|tree| folder.fold_use_tree(tree)).collect())
+            }
+            kind => kind,
+        },
+        span: folder.fold_span(tree.span),
+    }
+}
+
+/// Folds the children of a literal.
+pub fn fold_literal<F: Fold + ?Sized>(folder: &mut F, literal: Literal) -> Literal {
+    match literal {
+        Literal::Int(value, suffix, span) => Literal::Int(value, suffix, folder.fold_span(span)),
+        Literal::Float(value, suffix, span) => Literal::Float(value, suffix, folder.fold_span(span)),
+        Literal::Bool(value, span) => Literal::Bool(value, folder.fold_span(span)),
+        Literal::Strng(value, span) => Literal::Strng(value, folder.fold_span(span)),
+        Literal::Stilo(value, span) => Literal::Stilo(value, folder.fold_span(span)),
+        Literal::Bytes(value, span) => Literal::Bytes(value, folder.fold_span(span)),
+        Literal::Nil(span) => Literal::Nil(folder.fold_span(span)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Renames every use of one identifier.
+    struct Rename<'a> {
+        from: &'a str,
+        to: &'a str,
+    }
+
+    impl Fold for Rename<'_> {
+        fn fold_expression(&mut self, expression: Expression) -> Expression {
+            match expression {
+                Expression::Identifier(name, span) if name == self.from => {
+                    Expression::Identifier(self.to.to_string(), span)
+                }
+                other => fold_expression(self, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_rewrites_nested_expressions() {
+        let parse = |source: &str| Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let nodes = parse("fnc f() { djq y = [x, g(x.a)]; ret x + 1; }");
+
+        let mut rename = Rename { from: "x", to: "z" };
+        let folded: Vec<_> = nodes.into_iter().map(|node| rename.fold_node(node)).collect();
+        assert_eq!(folded, parse("fnc f() { djq y = [z, g(z.a)]; ret z + 1; }"));
+    }
+}
@@ -0,0 +1,145 @@
+//! Wadler-style documents and their layout.
+//!
+//! A [`Doc`] describes text together with the places where it may break.
+//! [`Doc::Group`] is laid out flat, with every [`Doc::Line`] printed as a
+//! space, when its contents fit in the remaining width; otherwise its lines
+//! become newlines at the current [`Doc::Nest`] indentation.
+
+/// A document to be laid out.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Doc {
+    /// Nothing.
+    Nil,
+    /// Literal text.
+    Text(String),
+    /// A space when flat, a newline when broken.
+    Line,
+    /// Nothing when flat, a newline when broken.
+    SoftLine,
+    /// Always a newline; forces every enclosing group to break.
+    HardLine,
+    /// Prints nothing but forces every enclosing group to break.
+    BreakParent,
+    /// The first text when broken, the second when flat.
+    IfBreak(&'static str, &'static str),
+    /// A sequence of documents.
+    Concat(Vec<Doc>),
+    /// Indents the lines of the document by one more level.
+    Nest(Box<Doc>),
+    /// Lays out the document flat if it fits.
+    Group(Box<Doc>),
+}
+
+impl Doc {
+    pub(crate) fn text(text: impl Into<String>) -> Self {
+        Doc::Text(text.into())
+    }
+
+    pub(crate) fn nest(doc: Doc) -> Self {
+        Doc::Nest(Box::new(doc))
+    }
+
+    pub(crate) fn group(doc: Doc) -> Self {
+        Doc::Group(Box::new(doc))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Lays out documents for a given width and indentation.
+pub(crate) struct Printer<'a> {
+    /// The text of one indentation level.
+    pub(crate) indent: &'a str,
+    /// The width of one indentation level, in columns.
+    pub(crate) indent_width: usize,
+    /// The preferred maximum line width, in columns.
+    pub(crate) max_width: usize,
+}
+
+impl Printer<'_> {
+    /// Lays out a document. Lines never end in whitespace.
+    pub(crate) fn print(&self, doc: &Doc) -> String {
+        let mut out = String::new();
+        let mut column = 0;
+        let mut stack = vec![(0usize, Mode::Break, doc)];
+
+        while let Some((level, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Nil | Doc::BreakParent => {}
+                Doc::Text(text) => {
+                    out.push_str(text);
+                    column = match text.rfind('\n') {
+                        Some(at) => text[at + 1..].chars().count(),
+                        None => column + text.chars().count(),
+                    };
+                }
+                Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                    if *doc == Doc::Line {
+                        out.push(' ');
+                        column += 1;
+                    }
+                }
+                Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                    let trimmed = out.trim_end_matches([' ', '\t']).len();
+                    out.truncate(trimmed);
+                    out.push('\n');
+                    for _ in 0..level {
+                        out.push_str(self.indent);
+                    }
+                    column = level * self.indent_width;
+                }
+                Doc::IfBreak(broken, flat) => {
+                    let text = if mode == Mode::Break { broken } else { flat };
+                    out.push_str(text);
+                    column += text.chars().count();
+                }
+                Doc::Concat(docs) => {
+                    for doc in docs.iter().rev() {
+                        stack.push((level, mode, doc));
+                    }
+                }
+                Doc::Nest(doc) => stack.push((level + 1, mode, doc)),
+                Doc::Group(doc) => {
+                    let fits = mode == Mode::Flat || Self::fits(self.max_width.saturating_sub(column), doc);
+                    stack.push((level, if fits { Mode::Flat } else { Mode::Break }, doc));
+                }
+            }
+        }
+
+        let trimmed = out.trim_end().len();
+        out.truncate(trimmed);
+        out
+    }
+
+    /// Returns true if the document fits in `width` columns when flat.
+    fn fits(mut width: usize, doc: &Doc) -> bool {
+        let mut stack = vec![doc];
+        while let Some(doc) = stack.pop() {
+            let used = match doc {
+                Doc::Nil | Doc::SoftLine => 0,
+                Doc::HardLine | Doc::BreakParent => return false,
+                Doc::Text(text) if text.contains('\n') => return false,
+                Doc::Text(text) => text.chars().count(),
+                Doc::Line => 1,
+                Doc::IfBreak(_, flat) => flat.chars().count(),
+                Doc::Concat(docs) => {
+                    stack.extend(docs.iter().rev());
+                    0
+                }
+                Doc::Nest(doc) | Doc::Group(doc) => {
+                    stack.push(doc);
+                    0
+                }
+            };
+            width = match width.checked_sub(used) {
+                Some(width) => width,
+                None => return false,
+            };
+        }
+        true
+    }
+}
@@ -0,0 +1,725 @@
+//! Canonical source formatting for the Kymera language.
+//!
+//! [`format`] rebuilds the whitespace of a file from its lossless
+//! [`SyntaxTree`], so every comment survives, and lays it out with a
+//! Wadler-style pretty-printer. Statements go one per line; bracketed lists
+//! stay on one line when they fit in [`FormatOptions::max_width`] and put one
+//! element per line otherwise. Formatting is idempotent: formatting the
+//! output again gives back the same text.
+//!
+//! Blank lines between statements are kept, collapsed to one. Comments that
+//! start their own line stay on their own line, and comments that follow
+//! code stay after it. Spacing around `<` and `>` is kept as written, since
+//! the tokens alone do not tell a comparison from a generic argument list.
+
+mod doc;
+
+use crate::cst::{Delimiter, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxTree};
+use crate::err::Result;
+use crate::lexer::{Token, TokenType};
+use crate::parser::Edition;
+use doc::{Doc, Printer};
+
+/// Where the opening brace of a block goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BraceStyle {
+    /// On the same line as the code it belongs to: `fnc f() {`.
+    #[default]
+    SameLine,
+    /// On a line of its own, aligned with the code it belongs to.
+    NextLine,
+}
+
+/// Options controlling the layout produced by [`format`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The number of columns per indentation level.
+    pub indent_width: usize,
+    /// Whether to indent with tabs instead of spaces.
+    pub use_tabs: bool,
+    /// The line width lists are wrapped at.
+    pub max_width: usize,
+    /// Where the opening brace of a block goes.
+    pub brace_style: BraceStyle,
+    /// The grammar edition the source is checked against.
+    pub edition: Edition,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            use_tabs: false,
+            max_width: 100,
+            brace_style: BraceStyle::default(),
+            edition: Edition::default(),
+        }
+    }
+}
+
+/// Formats Kymera source code.
+///
+/// Returns the first lexical or syntax error instead if the source does not
+/// parse, since the layout of broken code cannot be trusted.
+pub fn format(source: &str, options: &FormatOptions) -> Result<String> {
+    let tree = SyntaxTree::parse_with_edition(source, options.edition)?;
+    let (_, errors) = tree.lower();
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+
+    let mut atoms = Vec::new();
+    collect_atoms(&tree.root().children, &mut atoms, &mut Gap::default());
+    let doc = Formatter { options }.statements(&atoms);
+
+    let indent = if options.use_tabs {
+        "\t".to_string()
+    } else {
+        " ".repeat(options.indent_width)
+    };
+    let printer = Printer {
+        indent: &indent,
+        indent_width: options.indent_width,
+        max_width: options.max_width,
+    };
+    let mut formatted = printer.print(&doc);
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
+
+/// A token, comment or bracketed group, with the whitespace before it.
+#[derive(Debug, Clone, Copy)]
+struct Atom<'a> {
+    kind: AtomKind<'a>,
+    /// Newlines in the whitespace before the atom.
+    newlines_before: usize,
+    /// Whether any whitespace came before the atom.
+    space_before: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AtomKind<'a> {
+    Token(&'a Token),
+    Comment(&'a Token),
+    Group(&'a SyntaxNode),
+}
+
+impl<'a> Atom<'a> {
+    fn token(&self) -> Option<&'a Token> {
+        match self.kind {
+            AtomKind::Token(token) => Some(token),
+            _ => None,
+        }
+    }
+
+    fn is_comment(&self) -> bool {
+        matches!(self.kind, AtomKind::Comment(_))
+    }
+
+    fn is_brace_group(&self) -> bool {
+        matches!(self.kind, AtomKind::Group(node) if node.kind == SyntaxKind::Group(Delimiter::Brace))
+    }
+
+    fn is_token(&self, token_type: &TokenType) -> bool {
+        self.token().is_some_and(|token| token.token_type == *token_type)
+    }
+}
+
+/// Whitespace seen since the last atom.
+#[derive(Default)]
+struct Gap {
+    newlines: usize,
+    space: bool,
+}
+
+/// Flattens syntax elements into atoms, descending into items but not
+/// into groups.
+fn collect_atoms<'a>(elements: &'a [SyntaxElement], atoms: &mut Vec<Atom<'a>>, gap: &mut Gap) {
+    for element in elements {
+        let kind = match element {
+            SyntaxElement::Node(node) if node.kind == SyntaxKind::Group(delimiter_of(node)) => AtomKind::Group(node),
+            SyntaxElement::Node(node) => {
+                collect_atoms(&node.children, atoms, gap);
+                continue;
+            }
+            SyntaxElement::Token(token) if token.token_type == TokenType::Whitespace => {
+                gap.newlines += token.lexeme.matches('\n').count();
+                gap.space = true;
+                continue;
+            }
+            SyntaxElement::Token(token) if token.token_type == TokenType::Eof => continue,
+            SyntaxElement::Token(token) if token.token_type.is_trivia() => AtomKind::Comment(token),
+            SyntaxElement::Token(token) => AtomKind::Token(token),
+        };
+        atoms.push(Atom {
+            kind,
+            newlines_before: gap.newlines,
+            space_before: gap.space,
+        });
+        *gap = Gap::default();
+    }
+}
+
+fn delimiter_of(node: &SyntaxNode) -> Delimiter {
+    match node.kind {
+        SyntaxKind::Group(delimiter) => delimiter,
+        _ => Delimiter::Paren,
+    }
+}
+
+/// Returns true if a comment runs to the end of its line.
+fn is_line_comment(token: &Token) -> bool {
+    let lexeme = token.lexeme.as_str();
+    matches!(token.token_type, TokenType::Cmt | TokenType::Dmt)
+        && (lexeme.starts_with("//") || !(lexeme.ends_with("<|") || lexeme.ends_with("<D|")))
+}
+
+/// Returns true if the token can end an operand, so that a following `-`
+/// is binary and a following `(` or `[` is a call or an index.
+fn ends_operand(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Identifier(_)
+            | TokenType::IntLiteral(..)
+            | TokenType::FloatLiteral(..)
+            | TokenType::StringLiteral(_)
+            | TokenType::StiloLiteral(_)
+            | TokenType::ByteStringLiteral(_)
+            | TokenType::BoolLiteral(_)
+            | TokenType::Nil
+            | TokenType::Soy
+            | TokenType::Question
+            | TokenType::RParen
+            | TokenType::RBracket
+    )
+}
+
+/// Returns true if a token after a `{ }` group continues the same statement,
+/// as `rev` does after the body of an `ate`.
+fn continues_after_block(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Rev
+            | TokenType::Sino
+            | TokenType::Dot
+            | TokenType::Question
+            | TokenType::Semicolon
+            | TokenType::Comma
+            | TokenType::FatArrow
+            | TokenType::Arrow
+            | TokenType::Eq
+            | TokenType::PlusEq
+            | TokenType::MinusEq
+            | TokenType::StarEq
+            | TokenType::SlashEq
+            | TokenType::PercentEq
+            | TokenType::AmpEq
+            | TokenType::PipeEq
+            | TokenType::CaretEq
+            | TokenType::ShlEq
+            | TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Star
+            | TokenType::Slash
+            | TokenType::Percent
+            | TokenType::EqEq
+            | TokenType::Ne
+            | TokenType::Lt
+            | TokenType::Gt
+            | TokenType::Le
+            | TokenType::Ge
+            | TokenType::And
+            | TokenType::Or
+            | TokenType::Amp
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::Shl
+            | TokenType::DotDot
+            | TokenType::DotDotEq
+    )
+}
+
+/// Returns true if the token starts a statement that only appears in blocks.
+fn starts_block_statement(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Fnc
+            | TokenType::Xnc
+            | TokenType::Snc
+            | TokenType::Djq
+            | TokenType::Ret
+            | TokenType::Wyo
+            | TokenType::Spa
+            | TokenType::Ate
+            | TokenType::Si
+            | TokenType::Mth
+            | TokenType::Break
+            | TokenType::Continue
+            | TokenType::Imp
+            | TokenType::Forma
+            | TokenType::Enum
+            | TokenType::Ifz
+            | TokenType::Des
+            | TokenType::Pydes
+            | TokenType::Rudes
+            | TokenType::Prnt
+    )
+}
+
+/// A statement or list element.
+#[derive(Debug, Default)]
+struct Entry<'a> {
+    atoms: Vec<Atom<'a>>,
+    /// Whether a `,` separated this entry from the next one.
+    comma: bool,
+    /// Comments on the same line after the entry's separator.
+    trailing: Vec<Atom<'a>>,
+}
+
+impl Entry<'_> {
+    fn blank_before(&self) -> bool {
+        self.atoms.first().is_some_and(|atom| atom.newlines_before >= 2)
+    }
+
+    fn ends_with_block(&self) -> bool {
+        self.atoms.iter().rev().find(|atom| !atom.is_comment()).is_some_and(Atom::is_brace_group)
+    }
+}
+
+/// How a sequence of atoms is divided into entries.
+#[derive(Debug, Clone, Copy)]
+struct Split {
+    /// Split at `,`, dropping it.
+    commas: bool,
+    /// Split after `;`, keeping it.
+    semicolons: bool,
+    /// Split after a `{ }` group that ends a statement.
+    blocks: bool,
+}
+
+/// Returns true if the `{ }` group at `index` ends a statement that more
+/// code follows.
+fn ends_statement(atoms: &[Atom], index: usize) -> bool {
+    match atoms[index + 1..].iter().find(|atom| !atom.is_comment()) {
+        Some(next) => !next.token().is_some_and(|token| continues_after_block(&token.token_type)),
+        None => false,
+    }
+}
+
+fn split<'a>(atoms: &[Atom<'a>], how: Split) -> Vec<Entry<'a>> {
+    let mut entries: Vec<Entry<'a>> = Vec::new();
+    let mut current = Entry::default();
+    let mut just_closed = false;
+
+    for (index, atom) in atoms.iter().enumerate() {
+        if just_closed && atom.is_comment() && atom.newlines_before == 0 {
+            if let Some(previous) = entries.last_mut() {
+                previous.trailing.push(*atom);
+                continue;
+            }
+        }
+        just_closed = false;
+
+        if how.commas && atom.is_token(&TokenType::Comma) {
+            current.comma = true;
+            entries.push(std::mem::take(&mut current));
+            just_closed = true;
+            continue;
+        }
+        current.atoms.push(*atom);
+        if (how.semicolons && atom.is_token(&TokenType::Semicolon))
+            || (how.blocks
+                && atom.is_brace_group()
+                && (ends_statement(atoms, index) || atoms[index + 1..].iter().all(Atom::is_comment)))
+        {
+            entries.push(std::mem::take(&mut current));
+            just_closed = true;
+        }
+    }
+    if !current.atoms.is_empty() {
+        entries.push(current);
+    }
+    entries
+}
+
+/// The significant token laid out last, for spacing decisions.
+#[derive(Clone, Copy)]
+struct Previous<'a> {
+    token: &'a Token,
+    /// Whether the token is a prefix operator.
+    unary: bool,
+}
+
+/// Builds documents from atoms.
+struct Formatter<'o> {
+    options: &'o FormatOptions,
+}
+
+impl Formatter<'_> {
+    /// Lays out statements one per line, keeping single blank lines.
+    fn statements(&self, atoms: &[Atom]) -> Doc {
+        let how = Split {
+            commas: false,
+            semicolons: true,
+            blocks: true,
+        };
+        let mut docs = Vec::new();
+        for (index, entry) in split(atoms, how).iter().enumerate() {
+            if index > 0 {
+                docs.push(Doc::HardLine);
+                if entry.blank_before() {
+                    docs.push(Doc::HardLine);
+                }
+            }
+            docs.push(self.inline(&entry.atoms).0);
+            docs.push(Self::trailing(entry).0);
+        }
+        Doc::Concat(docs)
+    }
+
+    /// Lays out list elements separated by `,` and a line.
+    ///
+    /// With `trailing_comma` the last element gets a `,` only when the list
+    /// is broken; otherwise a trailing `,` is kept as written.
+    fn list(&self, entries: &[Entry], trailing_comma: bool) -> Doc {
+        let mut docs = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let last = index + 1 == entries.len();
+            let (doc, mut needs_line) = self.inline(&entry.atoms);
+            docs.push(doc);
+            if !last && entry.comma {
+                docs.push(Doc::text(","));
+            } else if last && trailing_comma && !entry.ends_with_block() {
+                docs.push(Doc::IfBreak(",", ""));
+            } else if last && !trailing_comma && entry.comma {
+                docs.push(Doc::text(","));
+            }
+            let (trailing, line_comment) = Self::trailing(entry);
+            docs.push(trailing);
+            needs_line |= line_comment;
+            if needs_line {
+                docs.push(Doc::BreakParent);
+            }
+            if !last {
+                docs.push(Doc::Line);
+            }
+        }
+        Doc::Concat(docs)
+    }
+
+    /// Lays out the comments after an entry, and reports whether the last
+    /// one runs to the end of the line.
+    fn trailing(entry: &Entry) -> (Doc, bool) {
+        let mut docs = Vec::new();
+        let mut line_comment = false;
+        for atom in &entry.trailing {
+            if let AtomKind::Comment(token) = atom.kind {
+                docs.push(Doc::text(" "));
+                docs.push(Doc::text(token.lexeme.clone()));
+                line_comment = is_line_comment(token);
+            }
+        }
+        (Doc::Concat(docs), line_comment)
+    }
+
+    /// Lays out the atoms of one entry, and reports whether it ends with a
+    /// comment that runs to the end of the line.
+    fn inline(&self, atoms: &[Atom]) -> (Doc, bool) {
+        let mut docs = Vec::new();
+        let mut previous: Option<Previous> = None;
+        let mut after_comment = false;
+        let mut needs_line = false;
+
+        for (index, atom) in atoms.iter().enumerate() {
+            let at_start = index == 0;
+            match atom.kind {
+                AtomKind::Comment(token) => {
+                    if !at_start {
+                        docs.push(if needs_line || atom.newlines_before > 0 {
+                            Doc::HardLine
+                        } else {
+                            Doc::text(" ")
+                        });
+                    }
+                    docs.push(Doc::text(token.lexeme.clone()));
+                    needs_line = is_line_comment(token) || atom.newlines_before > 0;
+                    after_comment = true;
+                }
+                AtomKind::Token(token) => {
+                    let unary = matches!(token.token_type, TokenType::Minus | TokenType::Not | TokenType::Tilde)
+                        && !previous.is_some_and(|previous| {
+                            ends_operand(&previous.token.token_type) || token.token_type == TokenType::Not
+                                && matches!(previous.token.token_type, TokenType::Identifier(_) | TokenType::Prnt)
+                        });
+                    if needs_line || (after_comment && atom.newlines_before > 0) {
+                        docs.push(Doc::HardLine);
+                    } else if !at_start && (after_comment || Self::space_between(previous, token, atom.space_before)) {
+                        docs.push(Doc::text(" "));
+                    }
+                    docs.push(Doc::text(token.lexeme.clone()));
+                    previous = Some(Previous { token, unary });
+                    needs_line = false;
+                    after_comment = false;
+                }
+                AtomKind::Group(node) => {
+                    let Some(SyntaxElement::Token(open)) = node.children.first() else {
+                        continue;
+                    };
+                    let block = atom.is_brace_group() && self.is_block(node);
+                    if needs_line || (after_comment && atom.newlines_before > 0) {
+                        docs.push(Doc::HardLine);
+                    } else if !at_start {
+                        if block && self.options.brace_style == BraceStyle::NextLine {
+                            docs.push(Doc::HardLine);
+                        } else if after_comment || Self::space_between(previous, open, atom.space_before) {
+                            docs.push(Doc::text(" "));
+                        }
+                    }
+                    docs.push(self.group(node, block));
+
+                    // An attribute goes on its own line above what it annotates.
+                    let attribute = previous.is_some_and(|previous| previous.token.token_type == TokenType::Hash);
+                    if let Some(SyntaxElement::Token(close)) = node.children.last() {
+                        previous = Some(Previous { token: close, unary: false });
+                    }
+                    needs_line = attribute;
+                    after_comment = false;
+                }
+            }
+        }
+        (Doc::Concat(docs), needs_line)
+    }
+
+    /// Returns true if a space separates `next` from the previous token on
+    /// the same line.
+    fn space_between(previous: Option<Previous>, next: &Token, space_before: bool) -> bool {
+        let Some(previous) = previous else {
+            return false;
+        };
+        let (prev, next) = (&previous.token.token_type, &next.token_type);
+        match (prev, next) {
+            (_, TokenType::Comma | TokenType::Semicolon | TokenType::Colon | TokenType::Dot | TokenType::Question)
+            | (_, TokenType::RParen | TokenType::RBracket)
+            | (TokenType::Dot | TokenType::Hash | TokenType::LParen | TokenType::LBracket, _)
+            | (TokenType::Spacs, _)
+            | (_, TokenType::Spacs)
+            | (TokenType::DotDot | TokenType::DotDotEq, _)
+            | (_, TokenType::DotDot | TokenType::DotDotEq) => false,
+            _ if previous.unary => false,
+            (_, TokenType::LBrace) => true,
+            // `<` and `>` are both comparisons and generic brackets, and `>>`
+            // must stay joined, so their spacing is kept as written.
+            (TokenType::Lt | TokenType::Gt, _) | (_, TokenType::Lt | TokenType::Gt) => space_before,
+            (TokenType::Identifier(_) | TokenType::Prnt, TokenType::Not) => false,
+            (_, TokenType::LParen) => {
+                !(ends_operand(prev) || matches!(prev, TokenType::Not | TokenType::Prnt | TokenType::Fnc))
+            }
+            (_, TokenType::LBracket) => !ends_operand(prev),
+            _ => true,
+        }
+    }
+
+    /// Returns true if a `{ }` group holds statements rather than a list.
+    fn is_block(&self, node: &SyntaxNode) -> bool {
+        let atoms = Self::contents(node);
+        let mut significant = atoms.iter().filter(|atom| !atom.is_comment()).peekable();
+        // Attributes on the first field or method say nothing either way.
+        while significant.peek().is_some_and(|atom| atom.is_token(&TokenType::Hash)) {
+            significant.next();
+            significant.next();
+        }
+        let Some(first) = significant.next() else {
+            // Only comments: lay them out as statements.
+            return !atoms.is_empty();
+        };
+        first.token().is_some_and(|token| starts_block_statement(&token.token_type))
+            || atoms.iter().any(|atom| atom.is_token(&TokenType::Semicolon))
+            || atoms.iter().enumerate().any(|(index, atom)| atom.is_brace_group() && ends_statement(&atoms, index))
+    }
+
+    /// Returns the atoms between the delimiters of a group.
+    fn contents(node: &SyntaxNode) -> Vec<Atom<'_>> {
+        let end = node.children.len().saturating_sub(1).max(1);
+        let mut atoms = Vec::new();
+        collect_atoms(&node.children[1..end], &mut atoms, &mut Gap::default());
+        atoms
+    }
+
+    /// Lays out a bracketed group.
+    fn group(&self, node: &SyntaxNode, block: bool) -> Doc {
+        let (Some(SyntaxElement::Token(open)), Some(SyntaxElement::Token(close))) =
+            (node.children.first(), node.children.last())
+        else {
+            return Doc::Nil;
+        };
+        let atoms = Self::contents(node);
+        let open = Doc::text(open.lexeme.clone());
+        let close = Doc::text(close.lexeme.clone());
+        if atoms.is_empty() {
+            return Doc::Concat(vec![open, close]);
+        }
+
+        if block {
+            return Doc::Concat(vec![
+                open,
+                Doc::nest(Doc::Concat(vec![Doc::HardLine, self.statements(&atoms)])),
+                Doc::HardLine,
+                close,
+            ]);
+        }
+
+        let brace = node.kind == SyntaxKind::Group(Delimiter::Brace);
+        let how = Split {
+            commas: true,
+            semicolons: false,
+            blocks: brace,
+        };
+        let entries = split(&atoms, how);
+        let line = if brace { Doc::Line } else { Doc::SoftLine };
+        Doc::group(Doc::Concat(vec![
+            open,
+            Doc::nest(Doc::Concat(vec![line.clone(), self.list(&entries, brace)])),
+            line,
+            close,
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::AstNode;
+    use crate::fold::Fold;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::position::{Position, Span};
+
+    /// Parses source with every span cleared, so layouts can be compared.
+    fn parse_without_spans(source: &str) -> Vec<AstNode> {
+        struct ClearSpans;
+        impl Fold for ClearSpans {
+            fn fold_span(&mut self, _span: Span) -> Span {
+                Span::new(Position::new(0, 0, 0), Position::new(0, 0, 0))
+            }
+        }
+        let nodes = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        nodes.into_iter().map(|node| ClearSpans.fold_node(node)).collect()
+    }
+
+    fn fmt(source: &str) -> String {
+        format(source, &FormatOptions::default()).unwrap()
+    }
+
+    const MESSY: &str = "// Cabecera\ndes core:>{io,math};\n\n\n#[inline] fnc suma(a:i32,b:i32)->i32{djq c=a+b*-2; // parcial\nret c;}\nforma Punto{x:f64,y:f64}\nfnc main(){\n    djq p=Punto{x:1.0,y:2.0};\n    ate p.x > 0.0{usar(p.x)?;}rev{usar(-p.y,[1,2][0]);}\n\n\n    mth p.x { 1 => a!(x), _ => { ret 0; } }\n    /* fin */\n}\n";
+
+    #[test]
+    fn test_format_layout() {
+        assert_eq!(
+            fmt(MESSY),
+            "// Cabecera\n\
+             des core:>{ io, math };\n\
+             \n\
+             #[inline]\n\
+             fnc suma(a: i32, b: i32) -> i32 {\n    \
+                 djq c = a + b * -2; // parcial\n    \
+                 ret c;\n\
+             }\n\
+             forma Punto { x: f64, y: f64 }\n\
+             fnc main() {\n    \
+                 djq p = Punto { x: 1.0, y: 2.0 };\n    \
+                 ate p.x > 0.0 {\n        \
+                     usar(p.x)?;\n    \
+                 } rev {\n        \
+                     usar(-p.y, [1, 2][0]);\n    \
+                 }\n\
+                 \n    \
+                 mth p.x {\n        \
+                     1 => a!(x),\n        \
+                     _ => {\n            \
+                         ret 0;\n        \
+                     }\n    \
+                 }\n    \
+                 /* fin */\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_format_is_idempotent_and_preserves_meaning() {
+        let sources = [
+            MESSY,
+            "enum Forma { Punto, Circulo(f64), Rect { ancho: f64, alto: f64 } }\nimp Forma { fnc area(soy) -> f64 { ret mth soy { Forma:>Circulo(r) => r * r, _ => 0.0 }; } }",
+            "fnc f() { djq x = usar(un_argumento_bastante_largo, otro_argumento_bastante_largo, y_un_tercero_largo); }",
+            "fnc g() { wyo x < 10 { x += 1; } djq v: Res<Vec<i32>, Strng> = h(1 >> 2); }",
+        ];
+        for source in sources {
+            let once = fmt(source);
+            assert_eq!(fmt(&once), once, "not idempotent for:\n{}", source);
+            assert_eq!(parse_without_spans(&once), parse_without_spans(source));
+        }
+    }
+
+    #[test]
+    fn test_format_keeps_comment_lines() {
+        for source in [
+            "|A> a <I|\nfnc f() {}\n",
+            "fnc f() {\n    /* nota */\n    usar(1);\n}\n",
+            "djq x = usar(\n    |> primero <|\n    1,\n    2\n);\n",
+        ] {
+            assert_eq!(fmt(source), source);
+        }
+        assert_eq!(fmt("/* a */ fnc f() {}"), "/* a */ fnc f() {}\n");
+    }
+
+    #[test]
+    fn test_format_builtin_calls_and_legacy_names() {
+        assert_eq!(
+            fmt("fnc f() { prnt!(\"hola {}\", x); prnt (\"{}\", !x); }"),
+            "fnc f() {\n    prnt!(\"hola {}\", x);\n    prnt(\"{}\", !x);\n}\n"
+        );
+        assert_eq!(fmt("djq p = P{si,sino:1};"), "djq p = P { si, sino: 1 };\n");
+    }
+
+    #[test]
+    fn test_format_wraps_long_lists() {
+        let source = "fnc f() { usar(un_argumento_bastante_largo, otro_argumento_bastante_largo); }";
+        let options = FormatOptions {
+            max_width: 40,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format(source, &options).unwrap(),
+            "fnc f() {\n    usar(\n        un_argumento_bastante_largo,\n        otro_argumento_bastante_largo\n    );\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_options() {
+        let options = FormatOptions {
+            use_tabs: true,
+            brace_style: BraceStyle::NextLine,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format("fnc f() { ret 1; }", &options).unwrap(),
+            "fnc f()\n{\n\tret 1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_rejects_invalid_source() {
+        assert!(format("fnc f( {", &FormatOptions::default()).is_err());
+        assert!(format("si x { }", &FormatOptions::default()).is_err());
+        let v2 = FormatOptions {
+            edition: Edition::V2,
+            ..FormatOptions::default()
+        };
+        assert_eq!(format("si x {usar(x);}", &v2).unwrap(), "si x {\n    usar(x);\n}\n");
+        assert_eq!(
+            format("ate {usar(x);} rev e {usar(e);}\nsi x {usar(x);} sino {usar(0);}", &v2).unwrap(),
+            "ate {\n    usar(x);\n} rev e {\n    usar(e);\n}\nsi x {\n    usar(x);\n} sino {\n    usar(0);\n}\n"
+        );
+    }
+}
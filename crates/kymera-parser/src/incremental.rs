@@ -0,0 +1,501 @@
+//! Incremental relexing and reparsing driven by text edits.
+//!
+//! [`IncrementalParser`] keeps the tokens and top-level nodes of a document
+//! between edits. When an edit arrives, the lexer restarts just before the
+//! edited region and stops as soon as its output lines up with the old token
+//! stream again. Only the top-level items touching the changed tokens are
+//! parsed again; the items after them are reused with their spans shifted.
+
+use std::ops::Range;
+
+use tracing::debug;
+
+use crate::ast::AstNode;
+use crate::err::{ParserError, Result};
+use crate::lexer::{Lexer, Token, TokenType};
+use crate::parser::{Edition, Parser};
+use crate::position::{Position, Span};
+use crate::visit_mut::VisitorMut;
+
+/// Number of tokens past the end of an item that the parser may inspect
+/// while deciding where the item ends.
+const LOOKAHEAD: usize = 2;
+
+/// A change to the source text: the bytes in `range` are replaced by `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the replaced text in the old source.
+    pub range: Range<usize>,
+    /// The replacement text.
+    pub text: String,
+}
+
+impl TextEdit {
+    /// Creates an edit replacing the bytes in `range` with `text`.
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Self {
+            range,
+            text: text.into(),
+        }
+    }
+
+    /// Creates an edit replacing the text covered by `span`.
+    pub fn replace(span: Span, text: impl Into<String>) -> Self {
+        Self::new(span.start.offset..span.end.offset, text)
+    }
+
+    /// Creates an edit inserting `text` at `offset`.
+    pub fn insert(offset: usize, text: impl Into<String>) -> Self {
+        Self::new(offset..offset, text)
+    }
+
+    /// Creates an edit deleting the bytes in `range`.
+    pub fn delete(range: Range<usize>) -> Self {
+        Self::new(range, String::new())
+    }
+}
+
+/// How much work applying an edit took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EditStats {
+    /// Number of tokens produced by the lexer.
+    pub relexed_tokens: usize,
+    /// Number of top-level items that were parsed again.
+    pub reparsed_items: usize,
+    /// Number of top-level items carried over from the previous tree.
+    pub reused_items: usize,
+}
+
+/// A top-level node together with the tokens it was parsed from.
+#[derive(Debug)]
+struct Item {
+    node: AstNode,
+    tokens: Range<usize>,
+    errors: Vec<ParserError>,
+}
+
+/// A parsed document that can be updated with text edits.
+#[derive(Debug)]
+pub struct IncrementalParser {
+    source: String,
+    tokens: Vec<Token>,
+    items: Vec<Item>,
+    /// The grammar edition the source is parsed under.
+    edition: Edition,
+    /// Set when the source failed to lex; the next edit starts from scratch.
+    stale: bool,
+}
+
+impl IncrementalParser {
+    /// Lexes and parses the given source code using the legacy edition.
+    pub fn new(source: impl Into<String>) -> Result<Self> {
+        Self::with_edition(source, Edition::Legacy)
+    }
+
+    /// Lexes and parses the given source code under the given edition.
+    pub fn with_edition(source: impl Into<String>, edition: Edition) -> Result<Self> {
+        let mut parser = Self {
+            source: source.into(),
+            tokens: Vec::new(),
+            items: Vec::new(),
+            edition,
+            stale: true,
+        };
+        parser.reparse_all()?;
+        Ok(parser)
+    }
+
+    /// Returns the current source code.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the current tokens, ending with `Eof`.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Returns the current top-level nodes.
+    pub fn nodes(&self) -> impl Iterator<Item = &AstNode> {
+        self.items.iter().map(|item| &item.node)
+    }
+
+    /// Returns the syntax errors of the current tree.
+    pub fn errors(&self) -> impl Iterator<Item = &ParserError> {
+        self.items.iter().flat_map(|item| item.errors.iter())
+    }
+
+    /// Applies an edit to the source and updates the tokens and tree.
+    ///
+    /// On a lexer error the source is still updated, the tree is cleared and
+    /// the error is returned; the next edit then relexes the whole document.
+    pub fn apply_edit(&mut self, edit: &TextEdit) -> Result<EditStats> {
+        let range = edit.range.clone();
+        if range.start > range.end
+            || range.end > self.source.len()
+            || !self.source.is_char_boundary(range.start)
+            || !self.source.is_char_boundary(range.end)
+        {
+            return Err(ParserError::internal(format!(
+                "Edit range {:?} is outside the document",
+                range
+            )));
+        }
+
+        let old_end = position_at(&self.source, range.end);
+        let new_end_offset = range.start + edit.text.len();
+        // The extent of a `|>` comment depends on markers arbitrarily far
+        // ahead, so edits near one can change tokens far from the edit.
+        let near_marker = near_comment_marker(&self.source, range.clone());
+        self.source.replace_range(range.clone(), &edit.text);
+        if self.stale || near_marker || near_comment_marker(&self.source, range.start..new_end_offset) {
+            return self.reparse_all();
+        }
+        let mut shift = Shift {
+            from: old_end,
+            to: position_at(&self.source, new_end_offset),
+        };
+
+        // The first token that the edit touches or could merge with.
+        let first_changed = self
+            .tokens
+            .partition_point(|token| token.span.end.offset < range.start);
+        // The first item whose tokens, or lookahead, reach the changed token.
+        let first_item = self
+            .items
+            .partition_point(|item| item.tokens.end + LOOKAHEAD <= first_changed);
+        let restart = self
+            .items
+            .get(first_item)
+            .map_or(first_changed, |item| item.tokens.start)
+            .min(first_changed);
+
+        // Relex from the end of the last untouched token until the new
+        // tokens line up with the old ones after the edit.
+        let lex_start = match restart {
+            0 => Position::start(),
+            index => self.tokens[index - 1].span.end,
+        };
+        let mut lexer = Lexer::new_at(&self.source, lex_start).with_edition(self.edition);
+        let mut fresh = Vec::new();
+        let mut old_index = first_changed;
+        let sync = loop {
+            let token = match lexer.next_token() {
+                Ok(Some(token)) => token,
+                Ok(None) => break self.tokens.len(),
+                Err(err) => {
+                    self.tokens.clear();
+                    self.items.clear();
+                    self.stale = true;
+                    return Err(err);
+                }
+            };
+
+            if token.span.start.offset >= new_end_offset {
+                let shifted = |old: &Token| old.span.start.offset - range.end + new_end_offset;
+                while let Some(old) = self.tokens.get(old_index) {
+                    if old.span.start.offset >= range.end && shifted(old) >= token.span.start.offset {
+                        break;
+                    }
+                    old_index += 1;
+                }
+                if let Some(old) = self.tokens.get(old_index) {
+                    if shifted(old) == token.span.start.offset
+                        && old.token_type == token.token_type
+                        && old.lexeme == token.lexeme
+                    {
+                        break old_index;
+                    }
+                }
+            }
+
+            let at_end = token.token_type == TokenType::Eof;
+            fresh.push(token);
+            if at_end {
+                break self.tokens.len();
+            }
+        };
+
+        let relexed_tokens = fresh.len();
+        let new_sync = restart + fresh.len();
+        let mut tail = self.tokens.split_off(sync);
+        for token in &mut tail {
+            shift.span(&mut token.span);
+        }
+        self.tokens.truncate(restart);
+        self.tokens.extend(fresh);
+        self.tokens.extend(tail);
+
+        // Items starting in the unchanged suffix parse identically if the
+        // new tree reaches a boundary at the same token.
+        let mut candidates: Vec<Item> = self
+            .items
+            .split_off(first_item)
+            .into_iter()
+            .filter(|item| item.tokens.start >= sync)
+            .collect();
+        for item in &mut candidates {
+            item.tokens = item.tokens.start - sync + new_sync..item.tokens.end - sync + new_sync;
+        }
+
+        let mut parser = Parser::with_edition(std::mem::take(&mut self.tokens), self.edition);
+        parser.seek(restart);
+        let mut reparsed = Vec::new();
+        let mut next = 0;
+        loop {
+            while next < candidates.len() && candidates[next].tokens.start < parser.token_index() {
+                next += 1;
+            }
+            if next < candidates.len() && candidates[next].tokens.start == parser.token_index() {
+                break;
+            }
+            if parser.is_at_end() {
+                next = candidates.len();
+                break;
+            }
+            let start = parser.token_index();
+            let (node, errors) = parser.parse_item_with_recovery();
+            reparsed.push(Item {
+                node,
+                tokens: start..parser.token_index(),
+                errors,
+            });
+        }
+        self.tokens = parser.into_tokens();
+
+        let stats = EditStats {
+            relexed_tokens,
+            reparsed_items: reparsed.len(),
+            reused_items: first_item + candidates.len() - next,
+        };
+        self.items.extend(reparsed);
+        for mut item in candidates.drain(next..) {
+            shift.visit_node_mut(&mut item.node);
+            for error in &mut item.errors {
+                shift.error(error);
+            }
+            self.items.push(item);
+        }
+
+        debug!("Applied edit {:?}: {:?}", edit.range, stats);
+        Ok(stats)
+    }
+
+    /// Lexes and parses the whole source from scratch.
+    fn reparse_all(&mut self) -> Result<EditStats> {
+        self.tokens.clear();
+        self.items.clear();
+        self.stale = true;
+
+        let tokens = Lexer::new(&self.source).with_edition(self.edition).tokenize()?;
+        let mut parser = Parser::with_edition(tokens, self.edition);
+        while !parser.is_at_end() {
+            let start = parser.token_index();
+            let (node, errors) = parser.parse_item_with_recovery();
+            self.items.push(Item {
+                node,
+                tokens: start..parser.token_index(),
+                errors,
+            });
+        }
+        self.tokens = parser.into_tokens();
+        self.stale = false;
+
+        Ok(EditStats {
+            relexed_tokens: self.tokens.len(),
+            reparsed_items: self.items.len(),
+            reused_items: 0,
+        })
+    }
+}
+
+/// Returns true if the text in `range`, or just around it, contains part of
+/// a comment opener or closer.
+fn near_comment_marker(source: &str, range: Range<usize>) -> bool {
+    const MARKERS: [&str; 6] = ["|>", "|D>", "|A>", "<|", "<D|", "<I|"];
+    let mut start = range.start.saturating_sub(2);
+    while !source.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (range.end + 2).min(source.len());
+    while !source.is_char_boundary(end) {
+        end += 1;
+    }
+    let window = &source[start..end];
+    MARKERS.iter().any(|marker| window.contains(marker))
+}
+
+/// Returns the line/column position of a byte offset in `source`.
+fn position_at(source: &str, offset: usize) -> Position {
+    let prefix = &source[..offset];
+    let line = prefix.matches('\n').count() + 1;
+    let line_start = prefix.rfind('\n').map_or(0, |index| index + 1);
+    let column = prefix[line_start..].chars().count() + 1;
+    Position::new(line, column, offset)
+}
+
+/// Moves positions after an edit to where they are in the new source.
+#[derive(Debug, Clone, Copy)]
+struct Shift {
+    /// End of the replaced text in the old source.
+    from: Position,
+    /// End of the replacement text in the new source.
+    to: Position,
+}
+
+impl Shift {
+    fn position(&self, position: &mut Position) {
+        if position.offset < self.from.offset {
+            return;
+        }
+        if position.line == self.from.line {
+            position.column = position.column + self.to.column - self.from.column;
+        }
+        position.line = position.line + self.to.line - self.from.line;
+        position.offset = position.offset + self.to.offset - self.from.offset;
+    }
+
+    fn span(&self, span: &mut Span) {
+        self.position(&mut span.start);
+        self.position(&mut span.end);
+    }
+
+    fn error(&self, error: &mut ParserError) {
+        match error {
+            ParserError::Lexer { span, .. }
+            | ParserError::Parser { span, .. }
+            | ParserError::UnexpectedToken { span, .. }
+            | ParserError::UnexpectedEof { span } => self.span(span),
+            ParserError::Unclosed { error, delimiter } => {
+                self.error(error);
+                self.span(delimiter);
+            }
+            ParserError::Io(_) | ParserError::Serialization(_) | ParserError::Internal(_) => {}
+        }
+    }
+}
+
+impl VisitorMut for Shift {
+    fn visit_span_mut(&mut self, span: &mut Span) {
+        self.span(span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fnc uno() {\n    ret 1;\n}\n\nfnc dos() {\n    ret 2;\n}\n\nfnc tres() {\n    ret 3;\n}\n";
+
+    /// Asserts that the incremental state matches a from-scratch parse.
+    fn assert_matches_full_parse(parser: &IncrementalParser) {
+        let tokens = Lexer::new(parser.source()).with_edition(parser.edition).tokenize().unwrap();
+        assert_eq!(parser.tokens(), tokens.as_slice());
+
+        let (nodes, errors) = Parser::with_edition(tokens, parser.edition).parse_with_recovery();
+        assert_eq!(parser.nodes().cloned().collect::<Vec<_>>(), nodes);
+        assert_eq!(
+            parser.errors().map(|err| err.to_string()).collect::<Vec<_>>(),
+            errors.iter().map(|err| err.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_edit_reparses_only_touched_item() {
+        let mut parser = IncrementalParser::new(SOURCE).unwrap();
+        let offset = SOURCE.find("ret 2").unwrap() + 4;
+        let stats = parser.apply_edit(&TextEdit::new(offset..offset + 1, "42")).unwrap();
+
+        assert_eq!(stats.reparsed_items, 1);
+        assert_eq!(stats.reused_items, 2);
+        assert!(stats.relexed_tokens < parser.tokens().len());
+        assert_matches_full_parse(&parser);
+    }
+
+    #[test]
+    fn test_multiline_insert_shifts_following_items() {
+        let mut parser = IncrementalParser::new(SOURCE).unwrap();
+        let offset = SOURCE.find("ret 1;").unwrap();
+        let stats = parser
+            .apply_edit(&TextEdit::insert(offset, "djq x = 5;\n    djq y = 6;\n    "))
+            .unwrap();
+
+        assert_eq!(stats.reparsed_items, 1);
+        assert_matches_full_parse(&parser);
+    }
+
+    #[test]
+    fn test_structural_edits_match_full_parse() {
+        let mut parser = IncrementalParser::new(SOURCE).unwrap();
+
+        // Remove the closing brace of the first function.
+        let offset = SOURCE.find("}\n").unwrap();
+        parser.apply_edit(&TextEdit::delete(offset..offset + 1)).unwrap();
+        assert_matches_full_parse(&parser);
+
+        // Put it back.
+        parser.apply_edit(&TextEdit::insert(offset, "}")).unwrap();
+        assert_matches_full_parse(&parser);
+
+        // Append a new item and join two tokens.
+        let end = parser.source().len();
+        parser.apply_edit(&TextEdit::insert(end, "djq z = 1")).unwrap();
+        assert_matches_full_parse(&parser);
+        let end = parser.source().len();
+        parser.apply_edit(&TextEdit::insert(end, "0;")).unwrap();
+        assert_matches_full_parse(&parser);
+    }
+
+    #[test]
+    fn test_lexer_error_recovers_on_next_edit() {
+        let mut parser = IncrementalParser::new(SOURCE).unwrap();
+        let offset = SOURCE.find("ret 3").unwrap();
+        assert!(parser.apply_edit(&TextEdit::insert(offset, "\"")).is_err());
+        assert_eq!(parser.nodes().count(), 0);
+
+        parser.apply_edit(&TextEdit::delete(offset..offset + 1)).unwrap();
+        assert_eq!(parser.source(), SOURCE);
+        assert_matches_full_parse(&parser);
+    }
+
+    #[test]
+    fn test_comment_marker_edits_match_full_parse() {
+        let source = "|> nota\nfnc uno() { ret 1; }\nfnc dos() { ret 2; }\n";
+        let mut parser = IncrementalParser::new(source).unwrap();
+
+        // Closing the comment on a later line turns it into a block comment
+        // that swallows the first function.
+        let offset = source.find("fnc dos").unwrap();
+        parser.apply_edit(&TextEdit::insert(offset, "<| ")).unwrap();
+        assert_matches_full_parse(&parser);
+        assert_eq!(parser.nodes().count(), 1);
+
+        parser.apply_edit(&TextEdit::delete(offset..offset + 3)).unwrap();
+        assert_matches_full_parse(&parser);
+        assert_eq!(parser.nodes().count(), 2);
+    }
+
+    #[test]
+    fn test_legacy_names_sync_after_edit() {
+        let source = "fnc uno() {\n    djq si = 1;\n    ret si;\n}\n\nfnc dos() {\n    djq sino = 2;\n    ret sino;\n}\n";
+        let mut parser = IncrementalParser::new(source).unwrap();
+        assert_matches_full_parse(&parser);
+
+        // The first token after the edit is `si`, which must line up with
+        // the stored token for relexing to stop there.
+        let offset = source.find("si;").unwrap();
+        let stats = parser.apply_edit(&TextEdit::insert(offset, " ")).unwrap();
+        assert_eq!(stats.reparsed_items, 1);
+        assert_eq!(stats.reused_items, 1);
+        assert!(stats.relexed_tokens < parser.tokens().len() / 2);
+        assert_matches_full_parse(&parser);
+    }
+
+    #[test]
+    fn test_out_of_bounds_edit_is_rejected() {
+        let mut parser = IncrementalParser::new(SOURCE).unwrap();
+        let len = SOURCE.len();
+        assert!(parser.apply_edit(&TextEdit::insert(len + 1, "x")).is_err());
+        assert_eq!(parser.source(), SOURCE);
+    }
+}
@@ -1,8 +1,10 @@
 use crate::err::{ParserError, Result};
+use crate::parser::Edition;
 use crate::position::{Position, Span};
+use serde::{Deserialize, Serialize};
 
 /// Represents the types of tokens in the Kymera language.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TokenType {
     // Keywords
     Des,    // Structure definition
@@ -12,7 +14,9 @@ pub enum TokenType {
     Forma,  // Struct declaration
     Ret,    // Return statement
     Wyo,    // While loop
-    Ate,    // If statement
+    Ate,    // If statement (legacy edition) or try block
+    Si,     // If statement
+    Sino,   // Else branch
     As,     // Import alias
     Idit,   // Variable declaration
     Spacs,  // Scope resolution operator (::)
@@ -22,7 +26,7 @@ pub enum TokenType {
     Spro,   // Async/await operator
     Res,    // Result type
     Djq,    // Variable declaration
-    Rev,    // Error propagation/handling
+    Rev,    // Else branch (legacy edition) or error handler
     Mth,    // Match statement
     Spa,    // For/foreach loop
     Optn,   // Option type
@@ -31,6 +35,9 @@ pub enum TokenType {
     Muta,   // Mutable designator
     Nmut,   // Immutable designator
     Ifz,    // Interface definition
+    In,     // Loop iteration binding
+    Break,  // Loop exit
+    Continue, // Loop continuation
     I8,     // 8-bit signed integer
     I16,    // 16-bit signed integer
     I32,    // 32-bit signed integer
@@ -50,14 +57,18 @@ pub enum TokenType {
     Bmt,    // Block comment
     Dmt,    // Documentation comment
     Verx,   // Verbose built-in AI debugger
+    AiRegion(String), // AI code generation region (|A> prompt <I|)
+    Whitespace, // Run of whitespace (lossless mode only)
 
     // Identifiers
     Identifier(String),
 
     // Literals
-    IntLiteral(i64),
-    FloatLiteral(f64),
-    StringLiteral(String),
+    IntLiteral(i128, Option<NumericSuffix>),
+    FloatLiteral(f64, Option<NumericSuffix>),
+    StringLiteral(String),       // "text" or r"text": an owned Strng
+    StiloLiteral(String),        // s"text" or sr"text": a Stilo slice
+    ByteStringLiteral(Vec<u8>),  // b"text" or br"text"
     BoolLiteral(bool),
     Nil,
 
@@ -82,6 +93,20 @@ pub enum TokenType {
     And,
     Or,
     Not,
+    FatArrow,
+    Arrow,
+    DotDot,
+    DotDotEq,
+    Question,
+    Amp,
+    Pipe,
+    Caret,
+    Tilde,
+    Shl,
+    AmpEq,
+    PipeEq,
+    CaretEq,
+    ShlEq,
 
     // Delimiters
     LParen,
@@ -94,6 +119,7 @@ pub enum TokenType {
     Dot,
     Semicolon,
     Colon,
+    Hash,   // Attribute marker
     Pydes,  // Python import
     Rudes,  // Rust import
 
@@ -101,8 +127,107 @@ pub enum TokenType {
     Eof,
 }
 
+/// The type named by a numeric literal suffix, as in `10u8` or `3.0f32`.
+///
+/// Every numeric keyword type has a suffix except `u128`: literal values are
+/// stored as `i128`, which cannot hold the upper half of its range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NumericSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isz,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usz,
+    F32,
+    F64,
+}
+
+impl NumericSuffix {
+    const ALL: [NumericSuffix; 13] = [
+        Self::I8, Self::I16, Self::I32, Self::I64, Self::I128, Self::Isz,
+        Self::U8, Self::U16, Self::U32, Self::U64, Self::Usz,
+        Self::F32, Self::F64,
+    ];
+
+    /// Returns the suffix spelled `text`, if any.
+    pub fn from_name(text: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|suffix| suffix.name() == text)
+    }
+
+    /// Returns the spelling of the suffix, which is also the name of its type.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::I128 => "i128",
+            Self::Isz => "isz",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::Usz => "usz",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        }
+    }
+
+    /// Returns true for the floating point suffixes.
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    /// Returns the largest value an integer literal with this suffix may
+    /// have. Architecture-dependent sizes are taken to be 64 bits.
+    fn max_int(self) -> i128 {
+        match self {
+            Self::I8 => i8::MAX.into(),
+            Self::I16 => i16::MAX.into(),
+            Self::I32 => i32::MAX.into(),
+            Self::I64 | Self::Isz => i64::MAX.into(),
+            Self::I128 | Self::F32 | Self::F64 => i128::MAX,
+            Self::U8 => u8::MAX.into(),
+            Self::U16 => u16::MAX.into(),
+            Self::U32 => u32::MAX.into(),
+            Self::U64 | Self::Usz => u64::MAX.into(),
+        }
+    }
+}
+
+/// The kind of value a string literal produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StringKind {
+    Strng,
+    Stilo,
+    Bytes,
+}
+
+impl TokenType {
+    /// Returns true for tokens that carry no syntactic meaning: whitespace,
+    /// comments, VERX triggers and AI regions. The parser never sees these;
+    /// they only appear in the output of [`Lexer::tokenize_lossless`].
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            TokenType::Whitespace
+                | TokenType::Cmt
+                | TokenType::Bmt
+                | TokenType::Dmt
+                | TokenType::Verx
+                | TokenType::AiRegion(_)
+        )
+    }
+}
+
 /// Represents a token with its type, value, and position in the source code.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Token {
     /// The type of the token.
     pub token_type: TokenType,
@@ -117,6 +242,10 @@ pub struct Lexer<'a> {
     source: &'a str,
     chars: std::iter::Peekable<std::str::Chars<'a>>,
     current_pos: Position,
+    /// Whether whitespace and comments are emitted as tokens.
+    keep_trivia: bool,
+    /// The grammar edition that decides which words are keywords.
+    edition: Edition,
 }
 
 impl<'a> Lexer<'a> {
@@ -126,9 +255,36 @@ impl<'a> Lexer<'a> {
             source,
             chars: source.chars().peekable(),
             current_pos: Position::new(1, 1, 0),
+            keep_trivia: false,
+            edition: Edition::V2,
+        }
+    }
+
+    /// Creates a lexer that starts at `start` within the given source code.
+    ///
+    /// `start` must lie on a token boundary, such as the end of a previously
+    /// lexed token. Spans of the produced tokens are relative to the whole
+    /// source, which is what incremental relexing relies on.
+    pub fn new_at(source: &'a str, start: Position) -> Self {
+        Self {
+            source,
+            chars: source[start.offset..].chars().peekable(),
+            current_pos: start,
+            keep_trivia: false,
+            edition: Edition::V2,
         }
     }
 
+    /// Sets the grammar edition the source is written in.
+    ///
+    /// By default every keyword is recognized. Under [`Edition::Legacy`],
+    /// `si` and `sino` are not keywords yet and lex as identifiers, so that
+    /// the tokens already are what the parser for that edition expects.
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+
     /// Returns the source code being lexed.
     pub fn source(&self) -> &str {
         self.source
@@ -151,10 +307,27 @@ impl<'a> Lexer<'a> {
         Ok(tokens)
     }
 
+    /// Tokenizes the entire source code, keeping whitespace and comments.
+    ///
+    /// The lexemes of the returned tokens concatenate back to the exact
+    /// source text, which makes this the input for the lossless syntax tree.
+    pub fn tokenize_lossless(&mut self) -> Result<Vec<Token>> {
+        self.keep_trivia = true;
+        let tokens = self.tokenize();
+        self.keep_trivia = false;
+        tokens
+    }
+
     /// Returns the next token from the source code.
     pub fn next_token(&mut self) -> Result<Option<Token>> {
-        self.skip_whitespace();
-        
+        if self.keep_trivia {
+            if let Some(token) = self.scan_whitespace() {
+                return Ok(Some(token));
+            }
+        } else {
+            self.skip_whitespace();
+        }
+
         let start_pos = self.current_pos;
         let next_char = self.peek();
 
@@ -163,27 +336,139 @@ impl<'a> Lexer<'a> {
             Some(c) => {
                 match c {
                     '0'..='9' => self.scan_number(),
+                    'b' | 'r' | 's' if Self::string_prefix(self.rest()).is_some() => {
+                        let (kind, raw, prefix_len) = Self::string_prefix(self.rest()).unwrap();
+                        self.advance_to(start_pos.offset + prefix_len);
+                        self.scan_string(start_pos, kind, raw)
+                    }
                     'a'..='z' | 'A'..='Z' | '_' => self.scan_identifier(),
-                    '"' => self.scan_string(),
+                    '"' => self.scan_string(start_pos, StringKind::Strng, false),
                     '/' => {
                         self.advance();
                         match self.peek() {
                             Some('/') => {
                                 self.skip_line_comment();
-                                self.next_token()
+                                let token_type = if self.slice_from(start_pos).starts_with("///") {
+                                    TokenType::Dmt
+                                } else {
+                                    TokenType::Cmt
+                                };
+                                self.trivia_or_next(token_type, start_pos)
                             }
                             Some('*') => {
                                 self.skip_block_comment()?;
-                                self.next_token()
+                                self.trivia_or_next(TokenType::Bmt, start_pos)
+                            }
+                            Some('=') => {
+                                self.advance();
+                                Ok(Some(self.make_token(TokenType::SlashEq, "/=".to_string(), start_pos)))
                             }
                             _ => Ok(Some(self.make_token(TokenType::Slash, "/".to_string(), start_pos)))
                         }
                     }
+                    '|' => {
+                        let rest = self.rest();
+                        if rest.starts_with("|A>") {
+                            self.scan_ai_region(start_pos)
+                        } else if rest.starts_with("|D>") {
+                            self.scan_marker_comment(TokenType::Dmt, start_pos)
+                        } else if rest.starts_with("|>") {
+                            if Self::is_verx_trigger(rest) {
+                                let len = rest.find("?x").unwrap_or(0) + 2;
+                                self.advance_to(start_pos.offset + len);
+                                self.trivia_or_next(TokenType::Verx, start_pos)
+                            } else {
+                                self.scan_marker_comment(TokenType::Cmt, start_pos)
+                            }
+                        } else if rest.starts_with("||") {
+                            self.advance_to(start_pos.offset + 2);
+                            Ok(Some(self.make_token(TokenType::Or, "||".to_string(), start_pos)))
+                        } else if rest.starts_with("|=") {
+                            self.advance_to(start_pos.offset + 2);
+                            Ok(Some(self.make_token(TokenType::PipeEq, "|=".to_string(), start_pos)))
+                        } else {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::Pipe, "|".to_string(), start_pos)))
+                        }
+                    }
+                    '<' => {
+                        let rest = self.rest();
+                        if rest.starts_with("<|") || rest.starts_with("<I|") || rest.starts_with("<D|") {
+                            Err(self.error("Comment closer without a matching opener"))
+                        } else if rest.starts_with("<<=") {
+                            self.advance_to(start_pos.offset + 3);
+                            Ok(Some(self.make_token(TokenType::ShlEq, "<<=".to_string(), start_pos)))
+                        } else if rest.starts_with("<<") {
+                            self.advance_to(start_pos.offset + 2);
+                            Ok(Some(self.make_token(TokenType::Shl, "<<".to_string(), start_pos)))
+                        } else if rest.starts_with("<=") {
+                            self.advance_to(start_pos.offset + 2);
+                            Ok(Some(self.make_token(TokenType::Le, "<=".to_string(), start_pos)))
+                        } else {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::Lt, "<".to_string(), start_pos)))
+                        }
+                    }
+                    '>' => {
+                        self.advance();
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::Ge, ">=".to_string(), start_pos)))
+                        } else {
+                            Ok(Some(self.make_token(TokenType::Gt, ">".to_string(), start_pos)))
+                        }
+                    }
+                    '!' => {
+                        self.advance();
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::Ne, "!=".to_string(), start_pos)))
+                        } else {
+                            Ok(Some(self.make_token(TokenType::Not, "!".to_string(), start_pos)))
+                        }
+                    }
+                    '&' => {
+                        self.advance();
+                        if self.peek() == Some('&') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::And, "&&".to_string(), start_pos)))
+                        } else if self.peek() == Some('=') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::AmpEq, "&=".to_string(), start_pos)))
+                        } else {
+                            Ok(Some(self.make_token(TokenType::Amp, "&".to_string(), start_pos)))
+                        }
+                    }
+                    '^' => {
+                        self.advance();
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::CaretEq, "^=".to_string(), start_pos)))
+                        } else {
+                            Ok(Some(self.make_token(TokenType::Caret, "^".to_string(), start_pos)))
+                        }
+                    }
+                    '~' => {
+                        self.advance();
+                        Ok(Some(self.make_token(TokenType::Tilde, "~".to_string(), start_pos)))
+                    }
+                    '%' => {
+                        self.advance();
+                        if self.peek() == Some('=') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::PercentEq, "%=".to_string(), start_pos)))
+                        } else {
+                            Ok(Some(self.make_token(TokenType::Percent, "%".to_string(), start_pos)))
+                        }
+                    }
                     '=' => {
                         self.advance();
                         if self.peek() == Some('=') {
                             self.advance();
                             Ok(Some(self.make_token(TokenType::EqEq, "==".to_string(), start_pos)))
+                        } else if self.peek() == Some('>') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::FatArrow, "=>".to_string(), start_pos)))
                         } else {
                             Ok(Some(self.make_token(TokenType::Eq, "=".to_string(), start_pos)))
                         }
@@ -202,6 +487,9 @@ impl<'a> Lexer<'a> {
                         if self.peek() == Some('=') {
                             self.advance();
                             Ok(Some(self.make_token(TokenType::MinusEq, "-=".to_string(), start_pos)))
+                        } else if self.peek() == Some('>') {
+                            self.advance();
+                            Ok(Some(self.make_token(TokenType::Arrow, "->".to_string(), start_pos)))
                         } else {
                             Ok(Some(self.make_token(TokenType::Minus, "-".to_string(), start_pos)))
                         }
@@ -239,18 +527,36 @@ impl<'a> Lexer<'a> {
                         self.advance();
                         Ok(Some(self.make_token(TokenType::RBracket, "]".to_string(), start_pos)))
                     }
+                    '#' => {
+                        self.advance();
+                        Ok(Some(self.make_token(TokenType::Hash, "#".to_string(), start_pos)))
+                    }
                     ',' => {
                         self.advance();
                         Ok(Some(self.make_token(TokenType::Comma, ",".to_string(), start_pos)))
                     }
                     '.' => {
                         self.advance();
-                        Ok(Some(self.make_token(TokenType::Dot, ".".to_string(), start_pos)))
+                        if self.peek() == Some('.') {
+                            self.advance();
+                            if self.peek() == Some('=') {
+                                self.advance();
+                                Ok(Some(self.make_token(TokenType::DotDotEq, "..=".to_string(), start_pos)))
+                            } else {
+                                Ok(Some(self.make_token(TokenType::DotDot, "..".to_string(), start_pos)))
+                            }
+                        } else {
+                            Ok(Some(self.make_token(TokenType::Dot, ".".to_string(), start_pos)))
+                        }
                     }
                     ';' => {
                         self.advance();
                         Ok(Some(self.make_token(TokenType::Semicolon, ";".to_string(), start_pos)))
                     }
+                    '?' => {
+                        self.advance();
+                        Ok(Some(self.make_token(TokenType::Question, "?".to_string(), start_pos)))
+                    }
                     ':' => {
                         self.advance();
                         if self.peek() == Some('>') {
@@ -266,40 +572,239 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    /// Scans a string literal.
-    fn scan_string(&mut self) -> Result<Option<Token>> {
-        let start_pos = self.current_pos;
-        let mut string = String::new();
-        
+    /// Returns true if `rest` starts with a '.' followed by a digit.
+    fn starts_fraction(rest: &str) -> bool {
+        rest.strip_prefix('.')
+            .and_then(|after| after.chars().next())
+            .is_some_and(|c| c.is_ascii_digit())
+    }
+
+    /// Returns the kind, rawness and prefix length of a prefixed string
+    /// literal (`b"`, `br"`, `r"`, `s"`, `sr"`) starting `rest`, if any.
+    fn string_prefix(rest: &str) -> Option<(StringKind, bool, usize)> {
+        const PREFIXES: [(&str, StringKind, bool); 5] = [
+            ("br", StringKind::Bytes, true),
+            ("sr", StringKind::Stilo, true),
+            ("b", StringKind::Bytes, false),
+            ("s", StringKind::Stilo, false),
+            ("r", StringKind::Strng, true),
+        ];
+        PREFIXES.into_iter().find_map(|(prefix, kind, raw)| {
+            let after = rest.strip_prefix(prefix)?;
+            let opens = if raw {
+                after.trim_start_matches('#').starts_with('"')
+            } else {
+                after.starts_with('"')
+            };
+            opens.then_some((kind, raw, prefix.len()))
+        })
+    }
+
+    /// Scans a string literal whose prefix, if any, ends at the current
+    /// position.
+    ///
+    /// Raw strings (`r"..."`, `r#"..."#`) take their contents verbatim and
+    /// end at a quote followed by as many `#` as opened them. Other strings
+    /// support the `\n`, `\r`, `\t`, `\0`, `\\`, `\"`, `\'`, `\xNN` and
+    /// `\u{NNNN}` escapes. Byte strings only accept ASCII and `\xNN` may
+    /// produce any byte in them, but `\u{...}` is not allowed.
+    fn scan_string(&mut self, start_pos: Position, kind: StringKind, raw: bool) -> Result<Option<Token>> {
+        let mut hashes = 0;
+        if raw {
+            while self.peek() == Some('#') {
+                self.advance();
+                hashes += 1;
+            }
+        }
         self.advance(); // Skip opening quote
-        
-        while let Some(c) = self.peek() {
-            if c == '"' {
+
+        let mut bytes = Vec::new();
+        loop {
+            let char_pos = self.current_pos;
+            let Some(c) = self.peek() else {
+                return Err(ParserError::lexer_error(
+                    Span::new(start_pos, self.current_pos),
+                    "Unterminated string literal",
+                ));
+            };
+
+            if c == '"' && (!raw || self.rest()[1..].starts_with(&"#".repeat(hashes))) {
                 self.advance(); // Skip closing quote
-                return Ok(Some(self.make_token(
-                    TokenType::StringLiteral(string.clone()),
-                    format!("\"{}\"", string),
-                    start_pos
-                )));
+                self.advance_to(self.current_pos.offset + hashes);
+                break;
+            }
+
+            if c == '\\' && !raw {
+                let value = self.scan_escape(kind)?;
+                if kind == StringKind::Bytes {
+                    bytes.push(value as u8);
+                } else {
+                    let c = char::from_u32(value).expect("escapes are checked to be valid chars");
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                continue;
+            }
+
+            self.advance();
+            if kind == StringKind::Bytes && !c.is_ascii() {
+                return Err(ParserError::lexer_error(
+                    Span::new(char_pos, self.current_pos),
+                    "Byte string literals may only contain ASCII characters",
+                ));
+            }
+            bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+
+        let lexeme = self.slice_from(start_pos);
+        let token_type = match kind {
+            StringKind::Bytes => TokenType::ByteStringLiteral(bytes),
+            // Only whole chars were pushed, so the bytes are valid UTF-8.
+            StringKind::Strng => TokenType::StringLiteral(String::from_utf8(bytes).expect("valid UTF-8")),
+            StringKind::Stilo => TokenType::StiloLiteral(String::from_utf8(bytes).expect("valid UTF-8")),
+        };
+        Ok(Some(self.make_token(token_type, lexeme, start_pos)))
+    }
+
+    /// Scans an escape sequence starting at the current `\\` and returns
+    /// the code point, or byte value in a byte string, it denotes.
+    fn scan_escape(&mut self, kind: StringKind) -> Result<u32> {
+        let start_pos = self.current_pos;
+        self.advance(); // Skip '\\'
+        let escape_error = |lexer: &Self, message: String| {
+            ParserError::lexer_error(Span::new(start_pos, lexer.current_pos), message)
+        };
+
+        let Some(c) = self.advance() else {
+            return Err(escape_error(self, "Unterminated escape sequence".to_string()));
+        };
+        match c {
+            'n' => Ok('\n' as u32),
+            'r' => Ok('\r' as u32),
+            't' => Ok('\t' as u32),
+            '0' => Ok(0),
+            '\\' | '"' | '\'' => Ok(c as u32),
+            'x' => {
+                let digits: String = self.rest().chars().take(2).collect();
+                let value = (digits.len() == 2)
+                    .then(|| u32::from_str_radix(&digits, 16).ok())
+                    .flatten();
+                self.advance_to(self.current_pos.offset + digits.len());
+                match value {
+                    Some(value) if value <= 0x7F || kind == StringKind::Bytes => Ok(value),
+                    Some(_) => Err(escape_error(self, format!(
+                        "\\x{} is out of range; use \\u{{...}} for characters above \\x7F", digits
+                    ))),
+                    None => Err(escape_error(self, "Expected two hex digits after \\x".to_string())),
+                }
+            }
+            'u' if kind == StringKind::Bytes => {
+                Err(escape_error(self, "Unicode escapes are not allowed in byte strings".to_string()))
             }
-            
-            if c == '\\' {
+            'u' => {
+                if self.peek() != Some('{') {
+                    return Err(escape_error(self, "Expected \\u{...}".to_string()));
+                }
                 self.advance();
-                match self.peek() {
-                    Some('n') => { string.push('\n'); self.advance(); }
-                    Some('r') => { string.push('\r'); self.advance(); }
-                    Some('t') => { string.push('\t'); self.advance(); }
-                    Some('\\') => { string.push('\\'); self.advance(); }
-                    Some('"') => { string.push('"'); self.advance(); }
-                    Some(c) => return Err(self.error(format!("Invalid escape sequence: \\{}", c))),
-                    None => return Err(self.error("Unterminated escape sequence")),
+                // Stop after a seventh digit so that the error span stays
+                // within the escape however long the run of digits is.
+                let mut digits = String::new();
+                while let Some(c) = self.peek().filter(|c| c.is_ascii_hexdigit() || *c == '_') {
+                    self.advance();
+                    if c != '_' {
+                        digits.push(c);
+                        if digits.len() > 6 {
+                            break;
+                        }
+                    }
+                }
+                if digits.is_empty() || digits.len() > 6 {
+                    return Err(escape_error(self, "Unicode escapes take one to six hex digits".to_string()));
+                }
+                if self.peek() != Some('}') {
+                    return Err(escape_error(self, "Expected `}` to close the unicode escape".to_string()));
+                }
+                self.advance();
+                match u32::from_str_radix(&digits, 16).ok().filter(|value| char::from_u32(*value).is_some()) {
+                    Some(value) => Ok(value),
+                    None => Err(escape_error(self, format!("Invalid unicode escape: \\u{{{}}}", digits))),
                 }
-            } else {
-                string.push(self.advance().unwrap());
             }
+            c => Err(escape_error(self, format!("Invalid escape sequence: \\{}", c))),
         }
-        
-        Err(self.error("Unterminated string literal"))
+    }
+
+    /// Returns true if the `|>` at the start of `rest` is a VERX trigger
+    /// (`|> ?x`).
+    fn is_verx_trigger(rest: &str) -> bool {
+        let after = rest[2..].trim_start_matches([' ', '\t']);
+        after.starts_with("?x")
+            && !after[2..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    /// Scans a `|>` comment or `|D>` documentation comment.
+    ///
+    /// A comment ends after the first `<|` (or `<D|`) on its opening line.
+    /// Without a closer on that line it becomes a block comment reaching the
+    /// next line that starts with a closer, provided no other comment opener
+    /// comes first; otherwise it is a line comment ending at the end of the
+    /// line. Closers later on a line, such as one inside a string, do not end
+    /// a block comment.
+    fn scan_marker_comment(&mut self, token_type: TokenType, start_pos: Position) -> Result<Option<Token>> {
+        let rest = self.rest();
+        let body_start = if token_type == TokenType::Dmt { 3 } else { 2 };
+        let body = &rest[body_start..];
+
+        let line_end = body.find('\n').unwrap_or(body.len());
+        let closer = ["<|", "<D|"]
+            .iter()
+            .filter_map(|closer| body[..line_end].find(closer).map(|at| (at, at + closer.len())))
+            .min()
+            .or_else(|| Self::find_line_closer(&body[line_end..]).map(|(at, end)| (line_end + at, line_end + end)));
+        let next_opener = ["|>", "|D>", "|A>"]
+            .iter()
+            .filter_map(|opener| body.find(opener))
+            .min();
+
+        let (len, token_type) = match closer {
+            Some((at, end)) if at < line_end => (end, token_type),
+            Some((at, end)) if next_opener.is_none_or(|opener| at < opener) => {
+                let token_type = if token_type == TokenType::Cmt { TokenType::Bmt } else { token_type };
+                (end, token_type)
+            }
+            _ => (line_end, token_type),
+        };
+
+        self.advance_to(start_pos.offset + body_start + len);
+        self.trivia_or_next(token_type, start_pos)
+    }
+
+    /// Returns the range of the first `<|` or `<D|` in `text` that is the
+    /// first non-blank text of its line.
+    fn find_line_closer(text: &str) -> Option<(usize, usize)> {
+        let mut line_start = 0;
+        for line in text.split_inclusive('\n') {
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            if let Some(closer) = ["<|", "<D|"].iter().find(|closer| line[indent..].starts_with(*closer)) {
+                let at = line_start + indent;
+                return Some((at, at + closer.len()));
+            }
+            line_start += line.len();
+        }
+        None
+    }
+
+    /// Scans an AI code generation region, `|A> prompt <I|`.
+    fn scan_ai_region(&mut self, start_pos: Position) -> Result<Option<Token>> {
+        let rest = self.rest();
+        let Some(end) = rest.find("<I|") else {
+            return Err(self.error("Unterminated AI region, expected '<I|'"));
+        };
+        let prompt = rest[3..end].trim().to_string();
+        self.advance_to(start_pos.offset + end + 3);
+        self.trivia_or_next(TokenType::AiRegion(prompt), start_pos)
     }
 
     /// Skips a line comment.
@@ -352,6 +857,7 @@ impl<'a> Lexer<'a> {
     fn advance(&mut self) -> Option<char> {
         let c = self.chars.next();
         if let Some(c) = c {
+            self.current_pos.offset += c.len_utf8();
             if c == '\n' {
                 self.current_pos.newline();
             } else {
@@ -370,6 +876,43 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Returns the source text from the current position to the end.
+    fn rest(&self) -> &'a str {
+        &self.source[self.current_pos.offset..]
+    }
+
+    /// Advances until the current position reaches the byte `offset`.
+    fn advance_to(&mut self, offset: usize) {
+        while self.current_pos.offset < offset && self.advance().is_some() {}
+    }
+
+    /// Returns the source text between `start` and the current position.
+    fn slice_from(&self, start: Position) -> String {
+        self.source[start.offset..self.current_pos.offset].to_string()
+    }
+
+    /// Emits a comment as a trivia token in lossless mode, or moves on to the
+    /// next significant token otherwise.
+    fn trivia_or_next(&mut self, token_type: TokenType, start_pos: Position) -> Result<Option<Token>> {
+        if self.keep_trivia {
+            let lexeme = self.slice_from(start_pos);
+            Ok(Some(self.make_token(token_type, lexeme, start_pos)))
+        } else {
+            self.next_token()
+        }
+    }
+
+    /// Scans a run of whitespace into a trivia token, if there is one.
+    fn scan_whitespace(&mut self) -> Option<Token> {
+        let start_pos = self.current_pos;
+        self.skip_whitespace();
+        if self.current_pos.offset == start_pos.offset {
+            return None;
+        }
+        let lexeme = self.slice_from(start_pos);
+        Some(self.make_token(TokenType::Whitespace, lexeme, start_pos))
+    }
+
     /// Skips whitespace characters.
     fn skip_whitespace(&mut self) {
         while let Some(c) = self.peek() {
@@ -404,8 +947,11 @@ impl<'a> Lexer<'a> {
             "ret" => TokenType::Ret,
             "wyo" => TokenType::Wyo,
             "ate" => TokenType::Ate,
+            "si" if self.edition == Edition::V2 => TokenType::Si,
+            "sino" if self.edition == Edition::V2 => TokenType::Sino,
             "as" => TokenType::As,
             "idit" => TokenType::Idit,
+            "soy" => TokenType::Soy,
             "djq" => TokenType::Djq,
             "rev" => TokenType::Rev,
             "mth" => TokenType::Mth,
@@ -416,6 +962,12 @@ impl<'a> Lexer<'a> {
             "muta" => TokenType::Muta,
             "nmut" => TokenType::Nmut,
             "ifz" => TokenType::Ifz,
+            "in" => TokenType::In,
+            "snc" => TokenType::Snc,
+            "xnc" => TokenType::Xnc,
+            "spro" => TokenType::Spro,
+            "break" => TokenType::Break,
+            "continue" => TokenType::Continue,
             "i8" => TokenType::I8,
             "i16" => TokenType::I16,
             "i32" => TokenType::I32,
@@ -440,27 +992,117 @@ impl<'a> Lexer<'a> {
         Ok(Some(self.make_token(token_type, lexeme, start_pos)))
     }
 
-    /// Scans a number literal.
+    /// Scans a number literal: a decimal, `0x`, `0o` or `0b` integer, or a
+    /// decimal float with an optional exponent. Digits may be separated by
+    /// `_`, and a type suffix such as `u8` or `f32` may follow.
+    ///
+    /// Unsuffixed integers must fit in an `i64`. Literals that do not fit in
+    /// their type are reported with the span of the whole literal.
     fn scan_number(&mut self) -> Result<Option<Token>> {
         let start_pos = self.current_pos;
-        let mut lexeme = String::new();
-        let mut is_float = false;
+        let rest = self.rest();
+        let bytes = rest.as_bytes();
+        let is_digit = |radix: u32, index: usize| {
+            bytes.get(index).is_some_and(|&b| b == b'_' || (b as char).is_digit(radix))
+        };
 
-        while let Some(c) = self.peek() {
-            if c.is_ascii_digit() {
-                lexeme.push(self.advance().unwrap());
-            } else if c == '.' && !is_float {
+        let radix = match rest.get(..2) {
+            Some("0x") => 16,
+            Some("0o") => 8,
+            Some("0b") => 2,
+            _ => 10,
+        };
+        let digits_start = if radix == 10 { 0 } else { 2 };
+        let mut len = digits_start;
+        let mut is_float = false;
+        while is_digit(radix, len) {
+            len += 1;
+        }
+        if radix == 10 {
+            // Only a '.' followed by a digit is a decimal point, so that
+            // `1..5` is a range and `1.abs()` a method call.
+            if Self::starts_fraction(&rest[len..]) {
                 is_float = true;
-                lexeme.push(self.advance().unwrap());
-            } else {
-                break;
+                len += 1;
+                while is_digit(10, len) {
+                    len += 1;
+                }
+            }
+            if matches!(bytes.get(len), Some(b'e' | b'E')) {
+                let mut exponent = len + 1;
+                if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+                    exponent += 1;
+                }
+                if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+                    is_float = true;
+                    len = exponent;
+                    while is_digit(10, len) {
+                        len += 1;
+                    }
+                }
             }
         }
+        let digits: String = rest[digits_start..len].chars().filter(|c| *c != '_').collect();
+
+        // The suffix is whatever identifier characters follow the digits.
+        self.advance_to(start_pos.offset + len);
+        let suffix_pos = self.current_pos;
+        let suffix_text = rest[len..]
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or_default();
+        self.advance_to(suffix_pos.offset + suffix_text.len());
+        let span = Span::new(start_pos, self.current_pos);
+        let lexeme = self.slice_from(start_pos);
 
-        let token_type = if is_float {
-            TokenType::FloatLiteral(lexeme.parse().map_err(|_| self.error("Invalid float literal"))?)
+        let suffix = match suffix_text {
+            "" => None,
+            "u128" => return Err(ParserError::lexer_error(
+                Span::new(suffix_pos, self.current_pos),
+                "Number literals cannot have a `u128` suffix; literals are limited to the range of i128",
+            )),
+            text => Some(NumericSuffix::from_name(text).ok_or_else(|| ParserError::lexer_error(
+                Span::new(suffix_pos, self.current_pos),
+                format!("Invalid suffix `{}` for number literal", text),
+            ))?),
+        };
+        if digits.is_empty() {
+            return Err(ParserError::lexer_error(span, format!("Missing digits in number literal `{}`", lexeme)));
+        }
+
+        let token_type = if is_float || suffix.is_some_and(NumericSuffix::is_float) {
+            if radix != 10 {
+                return Err(ParserError::lexer_error(span, "Only decimal literals can be floats"));
+            }
+            if suffix.is_some_and(|suffix| !suffix.is_float()) {
+                return Err(ParserError::lexer_error(span, format!(
+                    "Float literal `{}` cannot have an integer suffix", lexeme
+                )));
+            }
+            let value: f64 = digits.parse().map_err(|_| ParserError::lexer_error(span, "Invalid float literal"))?;
+            let in_range = match suffix {
+                Some(NumericSuffix::F32) => (value as f32).is_finite(),
+                _ => value.is_finite(),
+            };
+            if !in_range {
+                return Err(ParserError::lexer_error(span, format!(
+                    "Float literal `{}` is out of range for {}",
+                    lexeme,
+                    suffix.map_or("f64", NumericSuffix::name)
+                )));
+            }
+            TokenType::FloatLiteral(value, suffix)
         } else {
-            TokenType::IntLiteral(lexeme.parse().map_err(|_| self.error("Invalid integer literal"))?)
+            let max = suffix.map_or(i64::MAX.into(), NumericSuffix::max_int);
+            let value = i128::from_str_radix(&digits, radix)
+                .ok()
+                .filter(|value| *value <= max)
+                .ok_or_else(|| ParserError::lexer_error(span, format!(
+                    "Integer literal `{}` is out of range for {}",
+                    lexeme,
+                    suffix.map_or("i64", NumericSuffix::name)
+                )))?;
+            TokenType::IntLiteral(value, suffix)
         };
 
         Ok(Some(self.make_token(token_type, lexeme, start_pos)))
@@ -473,4 +1115,313 @@ impl<'a> Lexer<'a> {
             span: Span::new(self.current_pos, self.current_pos),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_types(source: &str) -> Vec<TokenType> {
+        Lexer::new(source)
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect()
+    }
+
+    fn lossless_types(source: &str) -> Vec<TokenType> {
+        Lexer::new(source)
+            .tokenize_lossless()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.token_type)
+            .filter(|token_type| *token_type != TokenType::Whitespace)
+            .collect()
+    }
+
+    #[test]
+    fn test_marker_comments_are_skipped() {
+        assert_eq!(
+            token_types("|> note <| djq x = 1; |> trailing"),
+            vec![
+                TokenType::Djq,
+                TokenType::Identifier("x".to_string()),
+                TokenType::Eq,
+                TokenType::IntLiteral(1, None),
+                TokenType::Semicolon,
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_marker_comment_kinds() {
+        assert_eq!(
+            lossless_types("|> one line\nfnc\n|> first\n   second\n   <|\n|D> Docs <|\n|> x <| |> ?x"),
+            vec![
+                TokenType::Cmt,
+                TokenType::Fnc,
+                TokenType::Bmt,
+                TokenType::Dmt,
+                TokenType::Cmt,
+                TokenType::Verx,
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_comment_does_not_reach_past_next_opener() {
+        assert_eq!(
+            token_types("|> open\ndjq x = 1;\n|> closed <|"),
+            token_types("djq x = 1;")
+        );
+    }
+
+    #[test]
+    fn test_block_comment_closes_only_at_line_start() {
+        // The `<|` inside the string does not close the comment, so the
+        // first line is a line comment and the string is lexed as code.
+        assert_eq!(
+            token_types("|> note\ndjq s = \"<| \";\n"),
+            vec![
+                TokenType::Djq,
+                TokenType::Identifier("s".to_string()),
+                TokenType::Eq,
+                TokenType::StringLiteral("<| ".to_string()),
+                TokenType::Semicolon,
+                TokenType::Eof,
+            ]
+        );
+        assert_eq!(token_types("|> a\nfnc\n  <D| djq"), vec![TokenType::Djq, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_ai_region() {
+        let tokens = Lexer::new("fnc |A> generate sorting fn <I|").tokenize_lossless().unwrap();
+        let region = &tokens[2];
+        assert_eq!(region.token_type, TokenType::AiRegion("generate sorting fn".to_string()));
+        assert_eq!(region.lexeme, "|A> generate sorting fn <I|");
+        assert_eq!(region.span.start.column, 5);
+        assert_eq!(region.span.end.offset, 31);
+
+        assert!(Lexer::new("|A> never closed").tokenize().is_err());
+    }
+
+    #[test]
+    fn test_stray_closer_is_an_error() {
+        assert!(Lexer::new("djq x = 1; <|").tokenize().is_err());
+    }
+
+    #[test]
+    fn test_comparison_and_logical_operators() {
+        assert_eq!(
+            token_types("< <= > >= != ! && || % %= /= => -> .."),
+            vec![
+                TokenType::Lt,
+                TokenType::Le,
+                TokenType::Gt,
+                TokenType::Ge,
+                TokenType::Ne,
+                TokenType::Not,
+                TokenType::And,
+                TokenType::Or,
+                TokenType::Percent,
+                TokenType::PercentEq,
+                TokenType::SlashEq,
+                TokenType::FatArrow,
+                TokenType::Arrow,
+                TokenType::DotDot,
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitwise_and_range_operators() {
+        assert_eq!(
+            token_types("& | ^ ~ << &= |= ^= <<= >> ..= #"),
+            vec![
+                TokenType::Amp,
+                TokenType::Pipe,
+                TokenType::Caret,
+                TokenType::Tilde,
+                TokenType::Shl,
+                TokenType::AmpEq,
+                TokenType::PipeEq,
+                TokenType::CaretEq,
+                TokenType::ShlEq,
+                TokenType::Gt,
+                TokenType::Gt,
+                TokenType::DotDotEq,
+                TokenType::Hash,
+                TokenType::Eof,
+            ]
+        );
+        assert_eq!(
+            token_types("1..5 2.5"),
+            vec![
+                TokenType::IntLiteral(1, None),
+                TokenType::DotDot,
+                TokenType::IntLiteral(5, None),
+                TokenType::FloatLiteral(2.5, None),
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_legacy_edition_names() {
+        let legacy = |source| {
+            Lexer::new(source)
+                .with_edition(Edition::Legacy)
+                .tokenize()
+                .unwrap()
+                .into_iter()
+                .map(|token| token.token_type)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            legacy("si sino ate"),
+            vec![
+                TokenType::Identifier("si".to_string()),
+                TokenType::Identifier("sino".to_string()),
+                TokenType::Ate,
+                TokenType::Eof,
+            ]
+        );
+        assert_eq!(token_types("si sino"), vec![TokenType::Si, TokenType::Sino, TokenType::Eof]);
+    }
+
+    #[test]
+    fn test_for_arrow() {
+        // Whether `4>` means `spa` depends on where it appears, so the parser
+        // decides.
+        assert_eq!(token_types("4> x"), token_types("4>x"));
+        assert_eq!(
+            token_types("4>x"),
+            vec![
+                TokenType::IntLiteral(4, None),
+                TokenType::Gt,
+                TokenType::Identifier("x".to_string()),
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    fn lex_error(source: &str) -> ParserError {
+        Lexer::new(source).tokenize().unwrap_err()
+    }
+
+    #[test]
+    fn test_number_literals() {
+        assert_eq!(
+            token_types("0xff 0o17 0b1010_1010 1_000_000 1e3 2.5E-2 10u8 3.0f32 1isz 7f64"),
+            vec![
+                TokenType::IntLiteral(255, None),
+                TokenType::IntLiteral(15, None),
+                TokenType::IntLiteral(170, None),
+                TokenType::IntLiteral(1_000_000, None),
+                TokenType::FloatLiteral(1000.0, None),
+                TokenType::FloatLiteral(0.025, None),
+                TokenType::IntLiteral(10, Some(NumericSuffix::U8)),
+                TokenType::FloatLiteral(3.0, Some(NumericSuffix::F32)),
+                TokenType::IntLiteral(1, Some(NumericSuffix::Isz)),
+                TokenType::FloatLiteral(7.0, Some(NumericSuffix::F64)),
+                TokenType::Eof,
+            ]
+        );
+        assert_eq!(
+            token_types("18446744073709551615u64"),
+            vec![TokenType::IntLiteral(u64::MAX as i128, Some(NumericSuffix::U64)), TokenType::Eof]
+        );
+    }
+
+    #[test]
+    fn test_number_literal_errors_have_precise_spans() {
+        let err = lex_error("djq x = 256u8;");
+        assert!(err.message().contains("out of range for u8"));
+        let span = err.span().unwrap();
+        assert_eq!((span.start.offset, span.end.offset), (8, 13));
+
+        let err = lex_error("djq y = 9223372036854775808;");
+        assert!(err.message().contains("out of range for i64"));
+
+        let err = lex_error("10abc");
+        let span = err.span().unwrap();
+        assert_eq!((span.start.offset, span.end.offset), (2, 5));
+
+        assert!(lex_error("1e39f32").message().contains("out of range for f32"));
+        assert!(lex_error("1.5u8").message().contains("integer suffix"));
+        assert!(lex_error("0x").message().contains("Missing digits"));
+    }
+
+    #[test]
+    fn test_number_literal_bounds() {
+        assert_eq!(
+            token_types("9223372036854775807 170141183460469231731687303715884105727i128"),
+            vec![
+                TokenType::IntLiteral(i64::MAX.into(), None),
+                TokenType::IntLiteral(i128::MAX, Some(NumericSuffix::I128)),
+                TokenType::Eof,
+            ]
+        );
+        let err = lex_error("170141183460469231731687303715884105728i128");
+        assert!(err.message().contains("out of range for i128"));
+        let err = lex_error("18446744073709551616u64");
+        assert!(err.message().contains("out of range for u64"));
+
+        let err = lex_error("djq z = 340282366920938463463374607431768211455u128;");
+        assert!(err.message().contains("`u128` suffix"));
+        let span = err.span().unwrap();
+        assert_eq!((span.start.offset, span.end.offset), (47, 51));
+    }
+
+    #[test]
+    fn test_string_literals() {
+        assert_eq!(
+            token_types(r####""a\u{1F600}\x41" s"slice" r"C:\raw" r#"say "hi""# b"\xff\n" br"\d""####),
+            vec![
+                TokenType::StringLiteral("a\u{1F600}A".to_string()),
+                TokenType::StiloLiteral("slice".to_string()),
+                TokenType::StringLiteral(r"C:\raw".to_string()),
+                TokenType::StringLiteral(r#"say "hi""#.to_string()),
+                TokenType::ByteStringLiteral(vec![0xff, b'\n']),
+                TokenType::ByteStringLiteral(b"\\d".to_vec()),
+                TokenType::Eof,
+            ]
+        );
+        // Identifiers that merely start with a prefix letter are unaffected.
+        assert_eq!(
+            token_types("soy rs b"),
+            vec![
+                TokenType::Soy,
+                TokenType::Identifier("rs".to_string()),
+                TokenType::Identifier("b".to_string()),
+                TokenType::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_literal_errors() {
+        let err = lex_error(r#"djq s = "bad \q";"#);
+        let span = err.span().unwrap();
+        assert_eq!((span.start.offset, span.end.offset), (13, 15));
+        assert!(lex_error(r#""\u{110000}""#).message().contains("Invalid unicode escape"));
+        // An unclosed escape stops at the first character that cannot
+        // continue it instead of searching ahead for a `}`.
+        let err = lex_error(r#"djq s = "\u{41" + x }"#);
+        assert!(err.message().contains("Expected `}`"));
+        let span = err.span().unwrap();
+        assert_eq!((span.start.offset, span.end.offset), (9, 14));
+        let err = lex_error(r#""\u{1234567}""#);
+        assert!(err.message().contains("one to six hex digits"));
+        let span = err.span().unwrap();
+        assert_eq!((span.start.offset, span.end.offset), (1, 11));
+        assert!(lex_error(r#""\xff""#).message().contains("out of range"));
+        assert!(lex_error(r#"b"\u{41}""#).message().contains("not allowed in byte strings"));
+        assert!(lex_error(r#"b"ñ""#).message().contains("ASCII"));
+        assert!(lex_error(r##"r#"open"##).message().contains("Unterminated"));
+    }
+}
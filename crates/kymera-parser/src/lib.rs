@@ -1,14 +1,31 @@
 //! Parser implementation for the Kymera programming language.
 
 pub mod ast;
+pub mod cst;
+pub mod diagnostic;
 pub mod err;
+pub mod fold;
+pub mod formatter;
+pub mod incremental;
 pub mod lexer;
+pub mod node_map;
 pub mod parser;
 pub mod position;
+pub mod serial;
 pub mod utils;
+pub mod visit;
+pub mod visit_mut;
 
 pub use ast::{AstNode, Expression, Statement};
+pub use cst::{SyntaxNode, SyntaxTree};
+pub use diagnostic::Renderer;
 pub use err::{ParserError as Error, Result};
+pub use fold::Fold;
+pub use formatter::{format, BraceStyle, FormatOptions};
+pub use incremental::{IncrementalParser, TextEdit};
 pub use lexer::{Lexer, Token, TokenType};
-pub use parser::Parser;
-pub use position::{Position, Span};
\ No newline at end of file
+pub use node_map::{NodeId, NodeKind, NodeMap};
+pub use parser::{Edition, Parser};
+pub use position::{Position, Span};
+pub use visit::Visitor;
+pub use visit_mut::VisitorMut;
\ No newline at end of file
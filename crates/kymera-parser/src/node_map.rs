@@ -0,0 +1,360 @@
+//! Node identities, parent links and position lookup for the AST.
+//!
+//! [`NodeMap::build`] numbers the nodes of a parsed file in pre-order, so the
+//! same tree always gets the same [`NodeId`]s, and records the parent, span
+//! and [`NodeKind`] of each one. [`NodeMap::node_at_offset`] answers "what am
+//! I inside?" for a cursor position by returning the innermost node there
+//! followed by its ancestors.
+//!
+//! Nodes that only wrap another node without adding syntax, such as the
+//! statement around a function definition or the expression around a
+//! literal, are not numbered separately.
+//!
+//! The AST types do not store their ids. To find the id of a node you hold,
+//! look it up by span and kind with [`NodeMap::id_of`].
+//!
+//! Ids are positions in pre-order, so they are only stable while the tree is
+//! unchanged. After an edit, even one reparsed incrementally by
+//! [`IncrementalParser`](crate::incremental::IncrementalParser), rebuild the
+//! map: every node after the first added or removed one is renumbered,
+//! including nodes in untouched items. Spans of untouched nodes are shifted
+//! rather than lost, so an id held across an edit can be carried over by
+//! looking its shifted span up in the new map.
+
+use crate::ast::{
+    Attribute, AstNode, EnumVariant, Expression, Function, GenericParam, InterfaceMethod, Literal, MatchArm, Param,
+    Path, Pattern, Statement, StructField, TypeExpr, UseTree,
+};
+use crate::position::Span;
+use crate::visit::{self, Visitor};
+use std::collections::HashMap;
+
+/// Identifies a node within a [`NodeMap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// Returns the pre-order index of the node.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The kind of node a [`NodeId`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// A statement or item other than a function.
+    Statement,
+    /// An expression other than a literal or path.
+    Expression,
+    /// A function or method definition.
+    Function,
+    /// A method declared by an interface.
+    InterfaceMethod,
+    /// A function parameter.
+    Param,
+    /// A generic parameter.
+    GenericParam,
+    /// An item attribute.
+    Attribute,
+    /// A field of a struct or struct-style variant.
+    StructField,
+    /// An enum variant.
+    EnumVariant,
+    /// A match arm.
+    MatchArm,
+    /// A pattern other than a literal.
+    Pattern,
+    /// A type annotation.
+    Type,
+    /// A `:>`-separated path.
+    Path,
+    /// The use tree of an import.
+    UseTree,
+    /// A literal.
+    Literal,
+    /// A region the parser could not parse.
+    Error,
+}
+
+/// What a [`NodeMap`] records about a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeData {
+    /// The enclosing node, or `None` for a top-level item.
+    pub parent: Option<NodeId>,
+    /// The location of the node in the source code.
+    pub span: Span,
+    /// The kind of node.
+    pub kind: NodeKind,
+}
+
+/// Side table of node identities for a parsed file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeMap {
+    nodes: Vec<NodeData>,
+    /// The outermost node of each span and kind.
+    ids: HashMap<(Span, NodeKind), NodeId>,
+}
+
+impl NodeMap {
+    /// Numbers the nodes of a parsed file.
+    pub fn build(nodes: &[AstNode]) -> Self {
+        let mut builder = Builder::default();
+        for node in nodes {
+            builder.visit_node(node);
+        }
+        let mut ids = HashMap::with_capacity(builder.nodes.len());
+        for (index, data) in builder.nodes.iter().enumerate() {
+            ids.entry((data.span, data.kind)).or_insert(NodeId(index as u32));
+        }
+        Self { nodes: builder.nodes, ids }
+    }
+
+    /// Returns the number of nodes in the map.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the map has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns what the map records about a node.
+    pub fn get(&self, id: NodeId) -> Option<&NodeData> {
+        self.nodes.get(id.index())
+    }
+
+    /// Returns the id of the node with the given span and kind. If several
+    /// nodes of that kind share the span, returns the outermost one.
+    pub fn id_of(&self, span: Span, kind: NodeKind) -> Option<NodeId> {
+        self.ids.get(&(span, kind)).copied()
+    }
+
+    /// Returns the parent of a node.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.get(id).and_then(|data| data.parent)
+    }
+
+    /// Returns the span of a node.
+    pub fn span(&self, id: NodeId) -> Option<Span> {
+        self.get(id).map(|data| data.span)
+    }
+
+    /// Returns the kind of a node.
+    pub fn kind(&self, id: NodeId) -> Option<NodeKind> {
+        self.get(id).map(|data| data.kind)
+    }
+
+    /// Returns the parent, grandparent and so on of a node, nearest first.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), move |&id| self.parent(id))
+    }
+
+    /// Returns every node in pre-order with what the map records about it.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &NodeData)> {
+        self.nodes.iter().enumerate().map(|(index, data)| (NodeId(index as u32), data))
+    }
+
+    /// Returns the innermost node whose span contains the byte offset,
+    /// followed by its ancestors. Spans are half-open, so an offset at the
+    /// end of a node is outside it. Returns an empty list if no node
+    /// contains the offset.
+    pub fn node_at_offset(&self, offset: usize) -> Vec<NodeId> {
+        // Descendants follow their ancestors in pre-order, and siblings do
+        // not overlap, so the last node containing the offset is the
+        // innermost one.
+        let innermost = self
+            .iter()
+            .filter(|(_, data)| data.span.start.offset <= offset && offset < data.span.end.offset)
+            .map(|(id, _)| id)
+            .last();
+        match innermost {
+            Some(id) => std::iter::once(id).chain(self.ancestors(id)).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Numbers nodes while walking the AST.
+#[derive(Default)]
+struct Builder {
+    nodes: Vec<NodeData>,
+    /// The nodes enclosing the one being walked.
+    stack: Vec<NodeId>,
+}
+
+impl Builder {
+    /// Records a node, then walks its children with it as their parent.
+    fn record(&mut self, kind: NodeKind, span: Span, walk: impl FnOnce(&mut Self)) {
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(NodeData {
+            parent: self.stack.last().copied(),
+            span,
+            kind,
+        });
+        self.stack.push(id);
+        walk(self);
+        self.stack.pop();
+    }
+}
+
+impl Visitor for Builder {
+    fn visit_node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Error(span) => self.record(NodeKind::Error, *span, |_| {}),
+            _ => visit::walk_node(self, node),
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Function(_) | Statement::Expression(_) => visit::walk_statement(self, statement),
+            _ => self.record(NodeKind::Statement, statement.span(), |b| visit::walk_statement(b, statement)),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Literal(_) | Expression::Path(_) => visit::walk_expression(self, expression),
+            _ => self.record(NodeKind::Expression, expression.span(), |b| visit::walk_expression(b, expression)),
+        }
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.record(NodeKind::Function, function.span, |b| visit::walk_function(b, function));
+    }
+
+    fn visit_interface_method(&mut self, method: &InterfaceMethod) {
+        self.record(NodeKind::InterfaceMethod, method.span, |b| visit::walk_interface_method(b, method));
+    }
+
+    fn visit_param(&mut self, param: &Param) {
+        self.record(NodeKind::Param, param.span, |b| visit::walk_param(b, param));
+    }
+
+    fn visit_generic_param(&mut self, param: &GenericParam) {
+        self.record(NodeKind::GenericParam, param.span, |b| visit::walk_generic_param(b, param));
+    }
+
+    fn visit_attribute(&mut self, attribute: &Attribute) {
+        self.record(NodeKind::Attribute, attribute.span, |b| visit::walk_attribute(b, attribute));
+    }
+
+    fn visit_struct_field(&mut self, field: &StructField) {
+        self.record(NodeKind::StructField, field.span, |b| visit::walk_struct_field(b, field));
+    }
+
+    fn visit_enum_variant(&mut self, variant: &EnumVariant) {
+        self.record(NodeKind::EnumVariant, variant.span, |b| visit::walk_enum_variant(b, variant));
+    }
+
+    fn visit_match_arm(&mut self, arm: &MatchArm) {
+        self.record(NodeKind::MatchArm, arm.span, |b| visit::walk_match_arm(b, arm));
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Literal(_) => visit::walk_pattern(self, pattern),
+            _ => self.record(NodeKind::Pattern, pattern.span(), |b| visit::walk_pattern(b, pattern)),
+        }
+    }
+
+    fn visit_type(&mut self, ty: &TypeExpr) {
+        self.record(NodeKind::Type, ty.span(), |b| visit::walk_type(b, ty));
+    }
+
+    fn visit_path(&mut self, path: &Path) {
+        self.record(NodeKind::Path, path.span, |b| visit::walk_path(b, path));
+    }
+
+    fn visit_use_tree(&mut self, tree: &UseTree) {
+        self.record(NodeKind::UseTree, tree.span, |b| visit::walk_use_tree(b, tree));
+    }
+
+    fn visit_literal(&mut self, literal: &Literal) {
+        self.record(NodeKind::Literal, literal.span(), |b| visit::walk_literal(b, literal));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_node_at_offset_returns_ancestors() {
+        let source = "#[inline] fnc f(a: i32) { ret usar(a, 1); }\nforma P { x: i32 }";
+        let nodes = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let map = NodeMap::build(&nodes);
+        assert_eq!(map, NodeMap::build(&nodes));
+
+        let kinds = |offset| -> Vec<NodeKind> {
+            map.node_at_offset(offset).into_iter().map(|id| map.kind(id).unwrap()).collect()
+        };
+        let offset = source.find("a, 1").unwrap();
+        assert_eq!(
+            kinds(offset),
+            [NodeKind::Expression, NodeKind::Expression, NodeKind::Statement, NodeKind::Function]
+        );
+        let innermost = map.node_at_offset(offset)[0];
+        let span = map.span(innermost).unwrap();
+        assert_eq!(&source[span.start.offset..span.end.offset], "a");
+
+        assert_eq!(kinds(source.find("i32").unwrap()), [NodeKind::Type, NodeKind::Param, NodeKind::Function]);
+        assert_eq!(kinds(source.find("inline").unwrap()), [NodeKind::Attribute, NodeKind::Function]);
+        assert_eq!(kinds(source.find("x:").unwrap()), [NodeKind::StructField, NodeKind::Statement]);
+        assert!(map.node_at_offset(source.find('\n').unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_parent_get_and_ancestors() {
+        let source = "fnc f() { djq x = 1 + 2 * 3; }";
+        let nodes = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let map = NodeMap::build(&nodes);
+        let text = |id| {
+            let span = map.span(id).unwrap();
+            &source[span.start.offset..span.end.offset]
+        };
+
+        let root = NodeId(0);
+        assert_eq!(map.get(root).unwrap().kind, NodeKind::Function);
+        assert_eq!(map.get(root).unwrap().parent, None);
+        assert_eq!(map.ancestors(root).count(), 0);
+        assert!(map.get(NodeId(map.len() as u32)).is_none());
+        assert_eq!(map.parent(NodeId(map.len() as u32)), None);
+
+        let (two, _) = map
+            .iter()
+            .find(|(id, data)| data.kind == NodeKind::Literal && text(*id) == "2")
+            .unwrap();
+        let ancestors: Vec<_> = map.ancestors(two).collect();
+        assert_eq!(ancestors.first().copied(), map.parent(two));
+        assert_eq!(ancestors.last().copied(), Some(root));
+        assert_eq!(
+            ancestors.iter().map(|&id| text(id)).collect::<Vec<_>>(),
+            ["2 * 3", "1 + 2 * 3", "djq x = 1 + 2 * 3;", source]
+        );
+        for pair in ancestors.windows(2) {
+            assert_eq!(map.parent(pair[0]), Some(pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_id_of() {
+        let source = "fnc f(a: i32) -> i32 { ret a * 2; }";
+        let nodes = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let map = NodeMap::build(&nodes);
+
+        for (id, data) in map.iter() {
+            let found = map.id_of(data.span, data.kind).unwrap();
+            assert!(found <= id);
+            assert_eq!(map.get(found), Some(data));
+        }
+        let AstNode::Statement(Statement::Function(function)) = &nodes[0] else { panic!("expected a function") };
+        let param = map.id_of(function.params[0].span, NodeKind::Param).unwrap();
+        assert_eq!(map.parent(param), map.id_of(function.span, NodeKind::Function));
+        assert_eq!(map.id_of(function.span, NodeKind::Param), None);
+    }
+}
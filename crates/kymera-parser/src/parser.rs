@@ -1,53 +1,293 @@
 use crate::err::{ParserError, Result};
 use crate::lexer::{Token, TokenType};
 use crate::position::{Position, Span};
-use crate::ast::{AstNode, BinaryOp, Declaration, Expression, Function, IfStatement, 
-    Literal, LoopStatement, ReturnStatement, Statement, Struct, UnaryOp, Enum, Import, FunctionCall, Assignment};
+use crate::ast::{AstNode, Attribute, AttributeArg, BinaryOp, BuiltinCall, Declaration, Expression, Function, IfStatement, 
+    Literal, LoopStatement, ReturnStatement, Statement, Struct, UnaryOp, Enum, Import, FunctionCall, Assignment,
+    MethodCall, FieldAccess, ArrayAccess, EnumVariant, VariantPayload, StructLiteral, FieldInit, Await, SyncBlock, Propagate, TryCatch,
+    Implementation, Receiver, Param, Mutability, Interface, InterfaceMethod, GenericParam,
+    TypeExpr, NamedType, FunctionTypeExpr, StructField, Path, UseTree, UseTreeKind, ForStatement, LoopControl, Match, MatchArm, Pattern, VariantPattern, StructPattern, FieldPattern};
+use std::ops::Range;
 use tracing::debug;
 
+/// Type names that denote primitive types without being keywords.
+const PRIMITIVE_TYPES: &[&str] = &["bool", "Strng", "Stilo"];
+
+/// The edition of the grammar a source file is written in.
+///
+/// The editions differ in how conditionals and error handling are spelled:
+///
+/// | Construct      | `Legacy`            | `V2`                     |
+/// |----------------|---------------------|--------------------------|
+/// | conditional    | `ate c { } rev { }` | `si c { } sino { }`      |
+/// | match guard    | `x ate c => ...`    | `x si c => ...`          |
+/// | error handling | -                   | `ate { } rev err { }`    |
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Edition {
+    /// The original grammar, in which `ate`/`rev` spell `if`/`else`.
+    #[default]
+    Legacy,
+    /// The grammar in which `ate`/`rev` spell try/catch.
+    V2,
+}
+
 /// Parser for the Kymera language.
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    /// The grammar edition being parsed.
+    edition: Edition,
+    /// Whether syntax errors are recorded and skipped instead of returned.
+    recovering: bool,
+    /// Errors recorded while recovering.
+    errors: Vec<ParserError>,
+    /// Number of enclosing `{ }` blocks at the current token.
+    block_depth: usize,
+    /// Set while parsing an expression followed by a block, such as a loop
+    /// condition, where `name {` starts the block rather than a struct literal.
+    no_struct_literal: bool,
 }
 
 impl Parser {
-    /// Creates a new parser for the given tokens.
+    /// Creates a new parser for the given tokens using the legacy edition.
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self::with_edition(tokens, Edition::Legacy)
+    }
+
+    /// Creates a new parser for the given tokens and grammar edition.
+    pub fn with_edition(mut tokens: Vec<Token>, edition: Edition) -> Self {
+        // `si` and `sino` only became keywords in V2, so legacy files may
+        // use them as names. A lexer set to the legacy edition already
+        // produces identifiers; this covers tokens lexed without one.
+        if edition == Edition::Legacy {
+            for token in &mut tokens {
+                if matches!(token.token_type, TokenType::Si | TokenType::Sino) {
+                    token.token_type = TokenType::Identifier(token.lexeme.clone());
+                }
+            }
+        }
+        Self {
+            tokens,
+            current: 0,
+            edition,
+            recovering: false,
+            errors: Vec::new(),
+            block_depth: 0,
+            no_struct_literal: false,
+        }
     }
 
     /// Parses the tokens and returns a vector of AST nodes.
     pub fn parse(&mut self) -> Result<Vec<AstNode>> {
         let mut nodes = Vec::new();
         while !self.is_at_end() {
-            nodes.push(self.parse_statement()?);
+            let start = self.current;
+            let node = self.parse_statement().map_err(|e| self.note_unclosed(e, start))?;
+            nodes.push(node);
         }
 
         debug!("Parsed AST: {:?}", nodes);
         Ok(nodes)
     }
 
+    /// Parses the tokens, recovering from syntax errors at statement boundaries.
+    ///
+    /// Unlike [`Parser::parse`], this never stops at the first error. Each
+    /// statement that fails to parse is replaced by an [`AstNode::Error`]
+    /// covering the skipped tokens, and parsing resumes at the next `;`, `}`
+    /// or declaration keyword. Returns the partial AST together with every
+    /// error encountered.
+    pub fn parse_with_recovery(&mut self) -> (Vec<AstNode>, Vec<ParserError>) {
+        let (items, errors) = self.parse_items_with_recovery();
+        (items.into_iter().map(|(node, _)| node).collect(), errors)
+    }
+
+    /// Like [`Parser::parse_with_recovery`], but also returns the range of
+    /// token indices each top-level node was parsed from.
+    pub(crate) fn parse_items_with_recovery(&mut self) -> (Vec<(AstNode, Range<usize>)>, Vec<ParserError>) {
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+        while !self.is_at_end() {
+            let start = self.current;
+            let (node, item_errors) = self.parse_item_with_recovery();
+            errors.extend(item_errors);
+            items.push((node, start..self.current));
+        }
+
+        debug!("Parsed {} items with {} errors", items.len(), errors.len());
+        (items, errors)
+    }
+
+    /// Parses a single top-level node at the current token, recovering from
+    /// syntax errors, and returns it with the errors it produced.
+    pub(crate) fn parse_item_with_recovery(&mut self) -> (AstNode, Vec<ParserError>) {
+        self.recovering = true;
+        self.errors.clear();
+        self.block_depth = 0;
+        self.no_struct_literal = false;
+        let start = self.current;
+        let node = self.parse_statement_recovering();
+        self.recovering = false;
+        let errors = std::mem::take(&mut self.errors)
+            .into_iter()
+            .map(|e| self.note_unclosed(e, start))
+            .collect();
+        (node, errors)
+    }
+
+    /// Wraps an error caused by the input ending early in
+    /// [`ParserError::Unclosed`], pointing at the innermost `(`, `[` or `{`
+    /// opened since token `start` that is never closed.
+    fn note_unclosed(&self, error: ParserError, start: usize) -> ParserError {
+        if !error.at_end() {
+            return error;
+        }
+        let mut open = Vec::new();
+        for token in &self.tokens[start.min(self.tokens.len())..] {
+            match token.token_type {
+                TokenType::LParen | TokenType::LBracket | TokenType::LBrace => open.push(token.span),
+                TokenType::RParen | TokenType::RBracket | TokenType::RBrace => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+        match open.pop() {
+            Some(delimiter) => ParserError::Unclosed {
+                error: Box::new(error),
+                delimiter,
+            },
+            None => error,
+        }
+    }
+
+    /// Returns the index of the current token.
+    pub(crate) fn token_index(&self) -> usize {
+        self.current
+    }
+
+    /// Moves the parser to the token at `index`.
+    pub(crate) fn seek(&mut self, index: usize) {
+        self.current = index;
+    }
+
+    /// Consumes the parser, handing back its tokens.
+    pub(crate) fn into_tokens(self) -> Vec<Token> {
+        self.tokens
+    }
+
+    /// Parses a statement, replacing it with an error node if it fails.
+    fn parse_statement_recovering(&mut self) -> AstNode {
+        let start = self.current;
+        match self.parse_statement() {
+            Ok(node) => node,
+            Err(err) => {
+                debug!("Recovering from parser error: {}", err);
+                self.errors.push(err);
+                self.synchronize(start);
+                let start_pos = self.tokens[start].span.start;
+                let end_pos = if self.current > start {
+                    self.tokens[self.current - 1].span.end
+                } else {
+                    start_pos
+                };
+                AstNode::Error(Span::new(start_pos, end_pos))
+            }
+        }
+    }
+
+    /// Skips tokens until the parser reaches a statement boundary.
+    ///
+    /// A `;` is consumed and ends the skipped region. A `}` closing an
+    /// enclosing block, or a keyword that starts a new declaration, ends the
+    /// region without being consumed. Balanced `{ }` groups are skipped as a
+    /// whole. At least one token past `start` is always consumed so recovery
+    /// makes progress.
+    fn synchronize(&mut self, start: usize) {
+        let mut depth = 0usize;
+        while !self.is_at_end() {
+            let token_type = &self.tokens[self.current].token_type;
+            match token_type {
+                TokenType::LBrace => depth += 1,
+                TokenType::RBrace if depth > 0 => depth -= 1,
+                TokenType::RBrace => {
+                    if self.current == start && self.block_depth == 0 {
+                        // A stray '}' at the top level; drop it.
+                        self.advance();
+                    }
+                    return;
+                }
+                TokenType::Semicolon if depth == 0 => {
+                    self.advance();
+                    return;
+                }
+                _ if depth == 0 && self.current > start && Self::starts_statement(token_type) => {
+                    return;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Returns true if the token type begins a declaration or statement that
+    /// recovery can resume at.
+    fn starts_statement(token_type: &TokenType) -> bool {
+        matches!(
+            token_type,
+            TokenType::Fnc
+                | TokenType::Xnc
+                | TokenType::Snc
+                | TokenType::Forma
+                | TokenType::Enum
+                | TokenType::Imp
+                | TokenType::Ifz
+                | TokenType::Djq
+                | TokenType::Ret
+                | TokenType::Wyo
+                | TokenType::Spa
+                | TokenType::Break
+                | TokenType::Continue
+                | TokenType::Ate
+                | TokenType::Si
+                | TokenType::Des
+                | TokenType::Pydes
+                | TokenType::Rudes
+                | TokenType::Hash
+        )
+    }
+
     /// Parses a statement.
     fn parse_statement(&mut self) -> Result<AstNode> {
         match self.peek()?.token_type {
-            TokenType::Pydes | TokenType::Rudes => {
+            TokenType::Des | TokenType::Pydes | TokenType::Rudes => {
                 let import = self.parse_import()?;
                 Ok(AstNode::Statement(Statement::Import(import)))
             }
-            TokenType::Fnc => self.parse_function(),
-            TokenType::Forma => self.parse_struct(),
-            TokenType::Enum => self.parse_enum(),
+            TokenType::Hash => self.parse_attributed_item(),
+            TokenType::Fnc | TokenType::Xnc => {
+                let function = self.parse_function(Vec::new())?;
+                Ok(AstNode::Statement(Statement::Function(function)))
+            }
+            TokenType::Snc => self.parse_sync_block(),
+            TokenType::Imp => self.parse_implementation(Vec::new()),
+            TokenType::Ifz => self.parse_interface(),
+            TokenType::Forma => self.parse_struct(Vec::new()),
+            TokenType::Enum => self.parse_enum(Vec::new()),
             TokenType::Ret => self.parse_return_statement(),
-            TokenType::Wyo => self.parse_loop_statement(),
-            TokenType::Ate => self.parse_if_statement(),
+            TokenType::Wyo => self.parse_loop_statement(None),
+            TokenType::Spa => self.parse_for_statement(None),
+            TokenType::IntLiteral(..) if self.starts_for_arrow_loop() => self.parse_for_statement(None),
+            TokenType::Break | TokenType::Continue => self.parse_loop_control(),
+            TokenType::Identifier(_) if self.starts_labeled_loop() => self.parse_labeled_loop(),
+            TokenType::Ate if self.edition == Edition::Legacy => self.parse_if_statement(),
+            TokenType::Ate => self.parse_try_statement(),
+            TokenType::Si if self.edition == Edition::V2 => self.parse_if_statement(),
             TokenType::Djq => self.parse_declaration(),
-            TokenType::Idit => {
-                let next_token = self.peek_next()?;
-                match next_token.token_type {
-                    TokenType::Eq => self.parse_assignment(),
-                    _ => self.parse_expression_statement(),
-                }
+            TokenType::Mth => {
+                // A match in statement position needs no trailing ';'.
+                let match_expr = self.parse_match()?;
+                self.match_token(TokenType::Semicolon);
+                Ok(AstNode::Statement(Statement::Expression(Expression::Match(match_expr))))
             }
             _ => self.parse_expression_statement(),
         }
@@ -58,45 +298,10 @@ impl Parser {
         let start_pos = self.current_token()?.span.start;
         let import_type = self.current_token()?.token_type.clone();
 
-        // Consume the import keyword (pydes or rudes)
+        // Consume the import keyword (des, pydes or rudes)
         self.advance();
 
-        // Parse the import path
-        let path = match &self.current_token()?.token_type {
-            TokenType::Identifier(_) => {
-                let path = self.current_token()?.lexeme.clone();
-                self.advance();
-                path
-            }
-            _ => {
-                return Err(ParserError::UnexpectedToken {
-                    expected: "identifier".to_string(),
-                    found: self.current_token()?.lexeme.clone(),
-                    span: self.current_token()?.span,
-                });
-            }
-        };
-
-        // Check for optional alias
-        let alias = if let TokenType::As = self.current_token()?.token_type {
-            self.advance(); // Consume 'as'
-            match &self.current_token()?.token_type {
-                TokenType::Identifier(_) => {
-                    let alias = self.current_token()?.lexeme.clone();
-                    self.advance();
-                    Some(alias)
-                }
-                _ => {
-                    return Err(ParserError::UnexpectedToken {
-                        expected: "identifier".to_string(),
-                        found: self.current_token()?.lexeme.clone(),
-                        span: self.current_token()?.span,
-                    });
-                }
-            }
-        } else {
-            None
-        };
+        let tree = self.parse_use_tree()?;
 
         // Expect semicolon
         if self.current_token()?.token_type != TokenType::Semicolon {
@@ -111,32 +316,177 @@ impl Parser {
 
         Ok(Import {
             import_type,
-            path,
-            alias,
+            tree,
+            span: Span::new(start_pos, end_pos),
+        })
+    }
+
+    /// Parses a use tree such as `core:>{math, io:>File as F}` or `core:>*`.
+    fn parse_use_tree(&mut self) -> Result<UseTree> {
+        let start_pos = self.current_token()?.span.start;
+        let mut segments = Vec::new();
+        let mut prefix_end = start_pos;
+
+        let kind = loop {
+            if self.match_token(TokenType::LBrace) {
+                let mut trees = Vec::new();
+                while !self.check(TokenType::RBrace) && !self.is_at_end() {
+                    trees.push(self.parse_use_tree()?);
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RBrace)?; // Consume '}'
+                break UseTreeKind::Nested(trees);
+            }
+            if !segments.is_empty() && self.match_token(TokenType::Star) {
+                break UseTreeKind::Glob;
+            }
+
+            let segment = self.consume(TokenType::Identifier(String::new()))?;
+            segments.push(segment.lexeme);
+            prefix_end = segment.span.end;
+            if !self.match_token(TokenType::Spacs) {
+                let alias = if self.match_token(TokenType::As) {
+                    Some(self.consume(TokenType::Identifier(String::new()))?.lexeme)
+                } else {
+                    None
+                };
+                break UseTreeKind::Simple(alias);
+            }
+        };
+
+        let end_pos = self.previous_token()?.span.end;
+        Ok(UseTree {
+            prefix: Path {
+                segments,
+                span: Span::new(start_pos, prefix_end),
+            },
+            kind,
             span: Span::new(start_pos, end_pos),
         })
     }
 
+    /// Parses a `:>`-separated path of identifiers.
+    fn parse_path(&mut self) -> Result<Path> {
+        let first = self.consume(TokenType::Identifier(String::new()))?;
+        self.parse_path_from(first)
+    }
+
+    /// Parses the rest of a path whose first segment has been consumed.
+    fn parse_path_from(&mut self, first: Token) -> Result<Path> {
+        let mut segments = vec![first.lexeme];
+        let mut end_pos = first.span.end;
+        while self.match_token(TokenType::Spacs) {
+            let segment = self.consume(TokenType::Identifier(String::new()))?;
+            segments.push(segment.lexeme);
+            end_pos = segment.span.end;
+        }
+        Ok(Path {
+            segments,
+            span: Span::new(first.span.start, end_pos),
+        })
+    }
+
+    /// Parses an item preceded by `#[...]` attributes.
+    fn parse_attributed_item(&mut self) -> Result<AstNode> {
+        let attributes = self.parse_attributes()?;
+        match self.peek()?.token_type {
+            TokenType::Fnc | TokenType::Xnc => {
+                let function = self.parse_function(attributes)?;
+                Ok(AstNode::Statement(Statement::Function(function)))
+            }
+            TokenType::Imp => self.parse_implementation(attributes),
+            TokenType::Forma => self.parse_struct(attributes),
+            TokenType::Enum => self.parse_enum(attributes),
+            _ => Err(self.error("Attributes can only be applied to functions, structs, enums and imp blocks")),
+        }
+    }
+
+    /// Parses any number of `#[name]` or `#[name(args)]` attributes.
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>> {
+        let mut attributes = Vec::new();
+        while self.check(TokenType::Hash) {
+            let start_pos = self.current_token()?.span.start;
+            self.advance(); // Consume '#'
+            self.consume(TokenType::LBracket)?; // Consume '['
+            let name = self.consume(TokenType::Identifier(String::new()))?.lexeme;
+
+            let mut args = Vec::new();
+            if self.match_token(TokenType::LParen) {
+                while !self.check(TokenType::RParen) && !self.is_at_end() {
+                    args.push(self.parse_attribute_arg()?);
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RParen)?; // Consume ')'
+            }
+
+            self.consume(TokenType::RBracket)?; // Consume ']'
+            let end_pos = self.previous_token()?.span.end;
+            attributes.push(Attribute {
+                name,
+                args,
+                span: Span::new(start_pos, end_pos),
+            });
+        }
+        Ok(attributes)
+    }
+
+    /// Parses a literal, `name` or `name = literal` attribute argument.
+    fn parse_attribute_arg(&mut self) -> Result<AttributeArg> {
+        if !self.check(TokenType::Identifier(String::new())) {
+            return Ok(AttributeArg::Literal(self.parse_literal()?));
+        }
+        let name = self.consume(TokenType::Identifier(String::new()))?;
+        if self.match_token(TokenType::Eq) {
+            let value = self.parse_literal()?;
+            let span = Span::new(name.span.start, value.span().end);
+            Ok(AttributeArg::NameValue(name.lexeme, value, span))
+        } else {
+            Ok(AttributeArg::Identifier(name.lexeme, name.span))
+        }
+    }
+
     // Parses a struct definition
-    fn parse_struct(&mut self) -> Result<AstNode> {
+    fn parse_struct(&mut self, attributes: Vec<Attribute>) -> Result<AstNode> {
         let start_pos = self.current_token()?.span.start;
-        self.consume(TokenType::Des)?; // Consume 'des'
+        self.consume(TokenType::Forma)?; // Consume 'forma'
         let name_token = self.consume(TokenType::Identifier(String::new()))?;
         let name = name_token.lexeme.clone();
+        let fields = self.parse_struct_fields()?;
+        let end_pos = self.previous_token()?.span.end;
+
+        Ok(AstNode::Statement(Statement::Struct(Struct {
+            attributes,
+            name,
+            fields,
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
 
+    /// Parses a `{ name: Type, ... }` list of field declarations.
+    fn parse_struct_fields(&mut self) -> Result<Vec<StructField>> {
         self.consume(TokenType::LBrace)?; // Consume '{'
 
         let mut fields = Vec::new();
         while !self.check(TokenType::RBrace) && !self.is_at_end() {
+            let attributes = self.parse_attributes()?;
             let field_name_token = self.consume(TokenType::Identifier(String::new()))?;
             let field_name = field_name_token.lexeme.clone();
 
             self.consume(TokenType::Colon)?; // Consume ':'
 
-            let field_type_token = self.consume(TokenType::Identifier(String::new()))?;
-            let field_type = field_type_token.lexeme.clone();
+            let field_type = self.parse_type()?;
+            let end_pos = self.previous_token()?.span.end;
 
-            fields.push((field_name, field_type));
+            fields.push(StructField {
+                attributes,
+                name: field_name,
+                ty: field_type,
+                span: Span::new(field_name_token.span.start, end_pos),
+            });
 
             if !self.match_token(TokenType::Comma) {
                 break;
@@ -144,17 +494,11 @@ impl Parser {
         }
 
         self.consume(TokenType::RBrace)?; // Consume '}'
-        let end_pos = self.previous_token()?.span.end;
-
-        Ok(AstNode::Statement(Statement::Struct(Struct {
-            name,
-            fields,
-            span: Span::new(start_pos, end_pos),
-        })))
+        Ok(fields)
     }
 
     // Parses an enum definition
-    fn parse_enum(&mut self) -> Result<AstNode> {
+    fn parse_enum(&mut self, attributes: Vec<Attribute>) -> Result<AstNode> {
         let start_pos = self.current_token()?.span.start;
         self.consume(TokenType::Enum)?; // Consume 'enum'
         let name_token = self.consume(TokenType::Identifier(String::new()))?;
@@ -165,8 +509,19 @@ impl Parser {
         let mut variants = Vec::new();
         while !self.check(TokenType::RBrace) && !self.is_at_end() {
             let variant_name_token = self.consume(TokenType::Identifier(String::new()))?;
-            let variant_name = variant_name_token.lexeme.clone();
-            variants.push(variant_name);
+            let payload = if self.match_token(TokenType::LParen) {
+                VariantPayload::Tuple(self.parse_type_list(TokenType::RParen)?)
+            } else if self.check(TokenType::LBrace) {
+                VariantPayload::Struct(self.parse_struct_fields()?)
+            } else {
+                VariantPayload::Unit
+            };
+            let end_pos = self.previous_token()?.span.end;
+            variants.push(EnumVariant {
+                name: variant_name_token.lexeme,
+                payload,
+                span: Span::new(variant_name_token.span.start, end_pos),
+            });
 
             if !self.match_token(TokenType::Comma) {
                 break;
@@ -177,275 +532,802 @@ impl Parser {
         let end_pos = self.previous_token()?.span.end;
 
         Ok(AstNode::Statement(Statement::Enum(Enum {
+            attributes,
             name,
             variants,
             span: Span::new(start_pos, end_pos),
         })))
     }
 
-    /// Parses a function definition.
-    fn parse_function(&mut self) -> Result<AstNode> {
+    /// Parses an implementation block.
+    fn parse_implementation(&mut self, attributes: Vec<Attribute>) -> Result<AstNode> {
         let start_pos = self.current_token()?.span.start;
-        self.consume(TokenType::Fnc)?; // Consume 'fnc'
-        let name_token = self.consume(TokenType::Identifier(String::new()))?;
-        let name = name_token.lexeme.clone();
+        self.consume(TokenType::Imp)?; // Consume 'imp'
+        let first = self.parse_type()?;
 
-        self.consume(TokenType::LParen)?; // Consume '('
-        let mut params = Vec::new();
-        if !self.check(TokenType::RParen) {
-            loop {
-                let param_token = self.consume(TokenType::Identifier(String::new()))?;
-                params.push(param_token.lexeme.clone());
-                if !self.match_token(TokenType::Comma) {
-                    break;
-                }
-            }
-        }
-        self.consume(TokenType::RParen)?; // Consume ')'
+        // `imp Interface spa Type` implements an interface for a type.
+        let (target, interface) = if self.match_spa() {
+            (self.parse_type()?, Some(first))
+        } else {
+            (first, None)
+        };
 
-        let body = self.parse_block_statement()?;
+        self.consume(TokenType::LBrace)?; // Consume '{'
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RBrace) && !self.is_at_end() {
+            let method_attributes = self.parse_attributes()?;
+            methods.push(self.parse_function(method_attributes)?);
+        }
+        self.consume(TokenType::RBrace)?; // Consume '}'
         let end_pos = self.previous_token()?.span.end;
 
-        Ok(AstNode::Statement(Statement::Function(Function {
-            name,
-            params,
-            body,
-            span: Span::new(start_pos, end_pos),
-        })))
-    }
-
-    /// Parses a return statement.
-    fn parse_return_statement(&mut self) -> Result<AstNode> {
-        let start_pos = self.current_token()?.span.start;
-        self.consume(TokenType::Ret)?; // Consume 'ret'
-        let value = self.parse_expression()?;
-        self.consume(TokenType::Semicolon)?; // Consume ';'
-        let end_pos = self.previous_token()?.span.end;
-        Ok(AstNode::Statement(Statement::ReturnStatement(ReturnStatement {
-            value: Box::new(value),
+        Ok(AstNode::Statement(Statement::Implementation(Implementation {
+            attributes,
+            target,
+            interface,
+            methods,
             span: Span::new(start_pos, end_pos),
         })))
     }
 
-    /// Parses an if statement.
-    fn parse_if_statement(&mut self) -> Result<AstNode> {
+    /// Parses an interface definition.
+    fn parse_interface(&mut self) -> Result<AstNode> {
         let start_pos = self.current_token()?.span.start;
-        self.consume(TokenType::Ate)?; // Consume 'ate'
-        let condition = self.parse_expression()?;
-        let body = self.parse_block_statement()?;
-        let else_body = if self.match_token(TokenType::Rev) {
-            Some(self.parse_block_statement()?)
-        } else {
-            None
-        };
-        let end_pos = self.previous_token()?.span.end;
-        Ok(AstNode::Statement(Statement::IfStatement(IfStatement {
-            condition: Box::new(condition),
-            body,
-            else_body,
-            span: Span::new(start_pos, end_pos),
-        })))
-    }
+        self.consume(TokenType::Ifz)?; // Consume 'ifz'
+        let name = self.consume(TokenType::Identifier(String::new()))?.lexeme;
+        let generics = self.parse_generic_params()?;
 
-    /// Parses a loop statement.
-    fn parse_loop_statement(&mut self) -> Result<AstNode> {
-        let start_pos = self.current_token()?.span.start;
-        self.consume(TokenType::Wyo)?; // Consume 'wyo'
-        let condition = self.parse_expression()?;
-        let body = self.parse_block_statement()?;
-        let end_pos = self.previous_token()?.span.end;
-        Ok(AstNode::Statement(Statement::LoopStatement(LoopStatement {
-            condition: Box::new(condition),
-            body,
-            span: Span::new(start_pos, end_pos),
-        })))
-    }
+        let mut super_interfaces = Vec::new();
+        if self.match_token(TokenType::Colon) {
+            loop {
+                super_interfaces.push(self.parse_type()?);
+                if !self.match_token(TokenType::Plus) {
+                    break;
+                }
+            }
+        }
 
-    /// Parses a block statement.
-    fn parse_block_statement(&mut self) -> Result<Vec<AstNode>> {
         self.consume(TokenType::LBrace)?; // Consume '{'
-        let mut statements = Vec::new();
+        let mut methods = Vec::new();
         while !self.check(TokenType::RBrace) && !self.is_at_end() {
-            statements.push(self.parse_statement()?);
+            methods.push(self.parse_interface_method()?);
         }
         self.consume(TokenType::RBrace)?; // Consume '}'
-        Ok(statements)
-    }
-
-    /// Parses a declaration statement.
-    fn parse_declaration(&mut self) -> Result<AstNode> {
-        let start_pos = self.current_token()?.span.start;
-        self.consume(TokenType::Djq)?; // Consume 'djq'
-        let name_token = self.consume(TokenType::Identifier(String::new()))?;
-        let name = name_token.lexeme.clone();
-        self.consume(TokenType::Eq)?; // Consume '='
-        let value = self.parse_literal()?;
-        self.consume(TokenType::Semicolon)?; // Consume ';'
         let end_pos = self.previous_token()?.span.end;
-        Ok(AstNode::Statement(Statement::Declaration(Declaration {
+
+        Ok(AstNode::Statement(Statement::Interface(Interface {
             name,
-            value,
+            generics,
+            super_interfaces,
+            methods,
             span: Span::new(start_pos, end_pos),
         })))
     }
 
-    /// Parses an assignment statement.
-    fn parse_assignment(&mut self) -> Result<AstNode> {
+    /// Parses an interface method, which ends in either `;` or a default body.
+    fn parse_interface_method(&mut self) -> Result<InterfaceMethod> {
         let start_pos = self.current_token()?.span.start;
-        let name_token = self.consume(TokenType::Identifier(String::new()))?;
-        let name = name_token.lexeme.clone();
-        self.consume(TokenType::Eq)?; // Consume '='
-        let value = self.parse_expression()?;
-        self.consume(TokenType::Semicolon)?; // Consume ';'
+        let is_async = self.match_token(TokenType::Xnc);
+        self.consume(TokenType::Fnc)?; // Consume 'fnc'
+        let name = self.consume(TokenType::Identifier(String::new()))?.lexeme;
+        let generics = self.parse_generic_params()?;
+        let (receiver, params) = self.parse_params()?;
+        let return_type = self.parse_return_type()?;
+
+        let body = if self.match_token(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.parse_block_statement()?)
+        };
         let end_pos = self.previous_token()?.span.end;
-        Ok(AstNode::Statement(Statement::Assignment(Assignment {
+
+        Ok(InterfaceMethod {
             name,
-            value: Box::new(value),
+            is_async,
+            generics,
+            receiver,
+            params,
+            return_type,
+            body,
             span: Span::new(start_pos, end_pos),
-        })))
-    }
-
-    /// Parses an expression.
-    fn parse_expression(&mut self) -> Result<AstNode> {
-        self.parse_assignment_expression()
+        })
     }
 
-    /// Parses an assignment expression.
-    fn parse_assignment_expression(&mut self) -> Result<AstNode> {
-        let left = self.parse_or_expression()?;
-        if self.match_token(TokenType::Eq) {
-            let start_pos = self.current_token()?.span.start;
-            let right = self.parse_assignment_expression()?;
+    /// Parses an optional `<T, U: Bound + Otro>` generic parameter list.
+    fn parse_generic_params(&mut self) -> Result<Vec<GenericParam>> {
+        let mut generics = Vec::new();
+        if !self.match_token(TokenType::Lt) {
+            return Ok(generics);
+        }
+        while !self.check(TokenType::Gt) && !self.is_at_end() {
+            let name_token = self.consume(TokenType::Identifier(String::new()))?;
+            let mut bounds = Vec::new();
+            if self.match_token(TokenType::Colon) {
+                loop {
+                    bounds.push(self.parse_type()?);
+                    if !self.match_token(TokenType::Plus) {
+                        break;
+                    }
+                }
+            }
             let end_pos = self.previous_token()?.span.end;
-            if let AstNode::Expression(Expression::Identifier(name, _)) = left {
-                Ok(AstNode::Statement(Statement::Assignment(Assignment {
-                    name,
-                    value: Box::new(right),
-                    span: Span::new(start_pos, end_pos),
-                })))
-            } else {
-                Err(ParserError::Parser {
-                    message: "Invalid assignment target".to_string(),
-                    span: Span::new(start_pos, end_pos),
-                })
+            generics.push(GenericParam {
+                name: name_token.lexeme,
+                bounds,
+                span: Span::new(name_token.span.start, end_pos),
+            });
+            if !self.match_token(TokenType::Comma) {
+                break;
             }
-        } else {
-            Ok(left)
         }
+        self.consume(TokenType::Gt)?; // Consume '>'
+        Ok(generics)
     }
 
-    // Parses an 'or' expression.
-    fn parse_or_expression(&mut self) -> Result<AstNode> {
-        let mut left = self.parse_and_expression()?;
-        while self.match_token(TokenType::Or) {
-            let start_pos = self.current_token()?.span.start;
-            let op = self.previous_token()?.lexeme.clone();
-            let right = self.parse_and_expression()?;
-            let end_pos = self.previous_token()?.span.end;
-            left = AstNode::Expression(Expression::BinaryOp(BinaryOp {
-                left: Box::new(left),
-                op,
-                right: Box::new(right),
-                span: Span::new(start_pos, end_pos),
-            }));
+    /// Parses a type annotation.
+    fn parse_type(&mut self) -> Result<TypeExpr> {
+        let token = self.current_token()?;
+        let start_pos = token.span.start;
+        match token.token_type {
+            TokenType::LBracket => {
+                self.advance();
+                let element = Box::new(self.parse_type()?);
+                if self.match_token(TokenType::Semicolon) {
+                    let len_token = self.consume(TokenType::IntLiteral(0, None))?;
+                    let len = match len_token.token_type {
+                        TokenType::IntLiteral(len, _) => usize::try_from(len).map_err(|_| ParserError::Parser {
+                            message: "Array length must not be negative".to_string(),
+                            span: len_token.span,
+                        })?,
+                        _ => unreachable!("consume checked the token type"),
+                    };
+                    self.consume(TokenType::RBracket)?;
+                    let end_pos = self.previous_token()?.span.end;
+                    Ok(TypeExpr::Array(element, len, Span::new(start_pos, end_pos)))
+                } else {
+                    self.consume(TokenType::RBracket)?;
+                    let end_pos = self.previous_token()?.span.end;
+                    Ok(TypeExpr::Slice(element, Span::new(start_pos, end_pos)))
+                }
+            }
+            TokenType::LParen => {
+                self.advance();
+                let elements = self.parse_type_list(TokenType::RParen)?;
+                let end_pos = self.previous_token()?.span.end;
+                Ok(TypeExpr::Tuple(elements, Span::new(start_pos, end_pos)))
+            }
+            TokenType::Fnc => {
+                self.advance();
+                self.consume(TokenType::LParen)?;
+                let params = self.parse_type_list(TokenType::RParen)?;
+                let return_type = self.parse_return_type()?.map(Box::new);
+                let end_pos = self.previous_token()?.span.end;
+                Ok(TypeExpr::Function(FunctionTypeExpr {
+                    params,
+                    return_type,
+                    span: Span::new(start_pos, end_pos),
+                }))
+            }
+            TokenType::I8
+            | TokenType::I16
+            | TokenType::I32
+            | TokenType::I64
+            | TokenType::I128
+            | TokenType::Isz
+            | TokenType::U8
+            | TokenType::U16
+            | TokenType::U32
+            | TokenType::U64
+            | TokenType::U128
+            | TokenType::Usz
+            | TokenType::F32
+            | TokenType::F64
+            | TokenType::Strng
+            | TokenType::Stilo => {
+                self.advance();
+                Ok(TypeExpr::Primitive(token.lexeme, token.span))
+            }
+            TokenType::Identifier(ref name) if PRIMITIVE_TYPES.contains(&name.as_str()) => {
+                self.advance();
+                Ok(TypeExpr::Primitive(token.lexeme, token.span))
+            }
+            TokenType::Identifier(_) | TokenType::Optn | TokenType::Res => {
+                self.advance();
+                let path = self.parse_path_from(token)?;
+                let args = if self.match_token(TokenType::Lt) {
+                    self.parse_type_list(TokenType::Gt)?
+                } else {
+                    Vec::new()
+                };
+                let end_pos = self.previous_token()?.span.end;
+                Ok(TypeExpr::Named(Box::new(NamedType {
+                    path,
+                    args,
+                    span: Span::new(start_pos, end_pos),
+                })))
+            }
+            _ => Err(ParserError::UnexpectedToken {
+                expected: "type".to_string(),
+                found: token.lexeme,
+                span: token.span,
+            }),
         }
-        Ok(left)
     }
 
-    // Parses an 'and' expression.
-    fn parse_and_expression(&mut self) -> Result<AstNode> {
-        let mut left = self.parse_equality_expression()?;
-        while self.match_token(TokenType::And) {
+    /// Parses comma-separated types up to and including `close`.
+    fn parse_type_list(&mut self, close: TokenType) -> Result<Vec<TypeExpr>> {
+        let mut types = Vec::new();
+        while !self.check(close.clone()) && !self.is_at_end() {
+            types.push(self.parse_type()?);
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(close)?;
+        Ok(types)
+    }
+
+    /// Parses a parenthesized parameter list, which may start with a `soy`
+    /// receiver.
+    fn parse_params(&mut self) -> Result<(Option<Receiver>, Vec<Param>)> {
+        self.consume(TokenType::LParen)?; // Consume '('
+        let mut receiver = None;
+        let mut params = Vec::new();
+        while !self.check(TokenType::RParen) && !self.is_at_end() {
             let start_pos = self.current_token()?.span.start;
+            let mutability = self.parse_mutability();
+            if receiver.is_none() && params.is_empty() && self.check(TokenType::Soy) {
+                let end_pos = self.consume(TokenType::Soy)?.span.end;
+                receiver = Some(Receiver {
+                    mutability,
+                    span: Span::new(start_pos, end_pos),
+                });
+            } else {
+                let name = self.consume(TokenType::Identifier(String::new()))?.lexeme;
+                let ty = if self.match_token(TokenType::Colon) {
+                    Some(self.parse_type()?)
+                } else {
+                    None
+                };
+                let end_pos = self.previous_token()?.span.end;
+                params.push(Param {
+                    mutability,
+                    name,
+                    ty,
+                    span: Span::new(start_pos, end_pos),
+                });
+            }
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RParen)?; // Consume ')'
+        Ok((receiver, params))
+    }
+
+    /// Parses an optional `muta` or `nmut` marker.
+    fn parse_mutability(&mut self) -> Mutability {
+        if self.match_token(TokenType::Muta) {
+            Mutability::Mutable
+        } else if self.match_token(TokenType::Nmut) {
+            Mutability::Immutable
+        } else {
+            Mutability::Unspecified
+        }
+    }
+
+    /// Parses an optional `-> Type` return type.
+    fn parse_return_type(&mut self) -> Result<Option<TypeExpr>> {
+        if self.match_token(TokenType::Arrow) {
+            Ok(Some(self.parse_type()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses a function definition.
+    fn parse_function(&mut self, attributes: Vec<Attribute>) -> Result<Function> {
+        let start_pos = self.current_token()?.span.start;
+        let is_async = self.match_token(TokenType::Xnc);
+        self.consume(TokenType::Fnc)?; // Consume 'fnc'
+        let name_token = self.consume(TokenType::Identifier(String::new()))?;
+        let name = name_token.lexeme.clone();
+        let generics = self.parse_generic_params()?;
+        let (receiver, params) = self.parse_params()?;
+        let return_type = self.parse_return_type()?;
+
+        let body = self.parse_block_statement()?;
+        let end_pos = self.previous_token()?.span.end;
+
+        Ok(Function {
+            attributes,
+            name,
+            is_async,
+            generics,
+            receiver,
+            params,
+            return_type,
+            body,
+            span: Span::new(start_pos, end_pos),
+        })
+    }
+
+    /// Parses a `snc { }` block.
+    fn parse_sync_block(&mut self) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        self.consume(TokenType::Snc)?; // Consume 'snc'
+        let body = self.parse_block_statement()?;
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::SyncBlock(SyncBlock {
+            body,
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
+
+    /// Parses a return statement.
+    fn parse_return_statement(&mut self) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        self.consume(TokenType::Ret)?; // Consume 'ret'
+        let value = self.parse_expression()?;
+        self.consume(TokenType::Semicolon)?; // Consume ';'
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::ReturnStatement(ReturnStatement {
+            value: Box::new(value),
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
+
+    /// Returns the keywords spelling `if` and `else` in the current edition.
+    fn conditional_keywords(&self) -> (TokenType, TokenType) {
+        match self.edition {
+            Edition::Legacy => (TokenType::Ate, TokenType::Rev),
+            Edition::V2 => (TokenType::Si, TokenType::Sino),
+        }
+    }
+
+    /// Parses an if statement.
+    fn parse_if_statement(&mut self) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        let (if_keyword, else_keyword) = self.conditional_keywords();
+        self.consume(if_keyword.clone())?; // Consume 'ate' or 'si'
+        let condition = self.with_struct_literals(false, Self::parse_expression)?;
+        let body = self.parse_block_statement()?;
+        let else_body = if self.match_token(else_keyword) {
+            // `sino si` chains another conditional.
+            if self.edition == Edition::V2 && self.check(if_keyword) {
+                Some(vec![self.parse_if_statement()?])
+            } else {
+                Some(self.parse_block_statement()?)
+            }
+        } else {
+            None
+        };
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::IfStatement(IfStatement {
+            condition: Box::new(condition),
+            body,
+            else_body,
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
+
+    /// Parses an `ate { } rev err { }` error handling block.
+    fn parse_try_statement(&mut self) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        self.consume(TokenType::Ate)?; // Consume 'ate'
+        let body = self.parse_block_statement()?;
+        self.consume(TokenType::Rev)?; // Consume 'rev'
+        let binding = self.consume(TokenType::Identifier(String::new()))?;
+        let handler = self.parse_block_statement()?;
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::TryCatch(TryCatch {
+            body,
+            binding: binding.lexeme,
+            binding_span: binding.span,
+            handler,
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
+
+    /// Returns true if the current tokens are a loop label: `label: wyo` or
+    /// `label: spa`.
+    fn starts_labeled_loop(&self) -> bool {
+        matches!(
+            (self.tokens.get(self.current + 1), self.tokens.get(self.current + 2)),
+            (Some(colon), Some(keyword))
+                if colon.token_type == TokenType::Colon
+                    && (matches!(keyword.token_type, TokenType::Wyo | TokenType::Spa)
+                        || self.is_for_arrow(self.current + 2))
+        )
+    }
+
+    /// Returns true if the tokens at `index` are the `4>` spelling of `spa`:
+    /// a `4` written right against a `>`.
+    ///
+    /// The lexer always produces a number and `>`, because only the parser
+    /// knows which is meant. `4>` means `spa` after a loop label, between the
+    /// interface and type of an `imp`, and where a statement starts with the
+    /// shape of a loop (see [`Self::starts_for_arrow_loop`]); everywhere else,
+    /// as in `x = 4> y`, it is a comparison.
+    fn is_for_arrow(&self, index: usize) -> bool {
+        match (self.tokens.get(index), self.tokens.get(index + 1)) {
+            (Some(four), Some(gt)) => {
+                four.lexeme == "4" && gt.token_type == TokenType::Gt && four.span.end == gt.span.start
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the statement at the current token is a `4>` loop,
+    /// `4> pattern in iterable { ... }`, rather than an expression such as
+    /// `4>x;`. Since `in` cannot appear in an expression, it is enough to look
+    /// for it outside any brackets before the statement's `;`.
+    fn starts_for_arrow_loop(&self) -> bool {
+        if !self.is_for_arrow(self.current) {
+            return false;
+        }
+        let mut depth = 0usize;
+        for token in &self.tokens[self.current + 2..] {
+            match token.token_type {
+                TokenType::In if depth == 0 => return true,
+                TokenType::LParen | TokenType::LBracket | TokenType::LBrace => depth += 1,
+                TokenType::RParen | TokenType::RBracket | TokenType::RBrace if depth == 0 => return false,
+                TokenType::RParen | TokenType::RBracket | TokenType::RBrace => depth -= 1,
+                TokenType::Semicolon if depth == 0 => return false,
+                TokenType::Eof => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Consumes `spa` or its `4>` spelling, if present.
+    fn match_spa(&mut self) -> bool {
+        if self.is_for_arrow(self.current) {
+            self.advance();
+            self.advance();
+            true
+        } else {
+            self.match_token(TokenType::Spa)
+        }
+    }
+
+    /// Parses a loop preceded by a label.
+    fn parse_labeled_loop(&mut self) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        let label = self.consume(TokenType::Identifier(String::new()))?.lexeme;
+        self.consume(TokenType::Colon)?; // Consume ':'
+        let mut node = if self.check(TokenType::Spa) || self.is_for_arrow(self.current) {
+            self.parse_for_statement(Some(label))?
+        } else {
+            self.parse_loop_statement(Some(label))?
+        };
+        // The label is part of the loop.
+        if let AstNode::Statement(Statement::LoopStatement(LoopStatement { span, .. }))
+        | AstNode::Statement(Statement::ForStatement(ForStatement { span, .. })) = &mut node
+        {
+            span.start = start_pos;
+        }
+        Ok(node)
+    }
+
+    /// Parses a loop statement.
+    fn parse_loop_statement(&mut self, label: Option<String>) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        self.consume(TokenType::Wyo)?; // Consume 'wyo'
+        let condition = self.with_struct_literals(false, Self::parse_expression)?;
+        let body = self.parse_block_statement()?;
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::LoopStatement(LoopStatement {
+            label,
+            condition: Box::new(condition),
+            body,
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
+
+    /// Parses a for loop.
+    fn parse_for_statement(&mut self, label: Option<String>) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        if !self.match_spa() {
+            self.consume(TokenType::Spa)?; // Consume 'spa' or '4>'
+        }
+        let pattern = self.parse_pattern()?;
+        self.consume(TokenType::In)?; // Consume 'in'
+        let iterable = self.with_struct_literals(false, Self::parse_expression)?;
+        let body = self.parse_block_statement()?;
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::ForStatement(ForStatement {
+            label,
+            pattern,
+            iterable: Box::new(iterable),
+            body,
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
+
+    /// Parses a `break` or `continue` statement with an optional label.
+    fn parse_loop_control(&mut self) -> Result<AstNode> {
+        let keyword = self.current_token()?;
+        self.advance(); // Consume 'break' or 'continue'
+        let label = if self.check(TokenType::Identifier(String::new())) {
+            Some(self.consume(TokenType::Identifier(String::new()))?.lexeme)
+        } else {
+            None
+        };
+        self.consume(TokenType::Semicolon)?; // Consume ';'
+        let end_pos = self.previous_token()?.span.end;
+        let control = LoopControl {
+            label,
+            span: Span::new(keyword.span.start, end_pos),
+        };
+        Ok(AstNode::Statement(match keyword.token_type {
+            TokenType::Break => Statement::Break(control),
+            _ => Statement::Continue(control),
+        }))
+    }
+
+    /// Parses a block statement.
+    fn parse_block_statement(&mut self) -> Result<Vec<AstNode>> {
+        self.with_struct_literals(true, Self::parse_block_contents)
+    }
+
+    /// Parses the `{ }`-delimited statements of a block.
+    fn parse_block_contents(&mut self) -> Result<Vec<AstNode>> {
+        self.consume(TokenType::LBrace)?; // Consume '{'
+        self.block_depth += 1;
+        let mut statements = Vec::new();
+        while !self.check(TokenType::RBrace) && !self.is_at_end() {
+            let statement = if self.recovering {
+                self.parse_statement_recovering()
+            } else {
+                self.parse_statement()?
+            };
+            statements.push(statement);
+        }
+        self.block_depth -= 1;
+        self.consume(TokenType::RBrace)?; // Consume '}'
+        Ok(statements)
+    }
+
+    /// Parses a declaration statement.
+    fn parse_declaration(&mut self) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        self.consume(TokenType::Djq)?; // Consume 'djq'
+        let mutability = self.parse_mutability();
+        let pattern = self.parse_pattern()?;
+        let ty = if self.match_token(TokenType::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::Eq)?; // Consume '='
+        let value = self.parse_expression()?;
+        self.consume(TokenType::Semicolon)?; // Consume ';'
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::Declaration(Declaration {
+            mutability,
+            pattern,
+            ty,
+            value: Box::new(value),
+            span: Span::new(start_pos, end_pos),
+        })))
+    }
+
+    /// Parses the rest of an assignment statement to `target`, once the
+    /// assignment operator `op` has been consumed.
+    fn parse_assignment(&mut self, target: AstNode, op: String) -> Result<AstNode> {
+        let is_place = matches!(
+            target,
+            AstNode::Expression(
+                Expression::Identifier(..) | Expression::FieldAccess(_) | Expression::ArrayAccess(_)
+            )
+        );
+        if !is_place {
+            return Err(ParserError::Parser {
+                message: "Invalid assignment target".to_string(),
+                span: target.span(),
+            });
+        }
+
+        let value = self.parse_expression()?;
+        self.consume(TokenType::Semicolon)?; // Consume ';'
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Statement(Statement::Assignment(Assignment {
+            span: Span::new(target.span().start, end_pos),
+            target: Box::new(target),
+            op,
+            value: Box::new(value),
+        })))
+    }
+
+    /// Consumes an assignment operator and returns it, if one is next.
+    fn match_assignment_operator(&mut self) -> Option<String> {
+        if self.match_tokens(&[
+            TokenType::Eq,
+            TokenType::PlusEq,
+            TokenType::MinusEq,
+            TokenType::StarEq,
+            TokenType::SlashEq,
+            TokenType::PercentEq,
+            TokenType::AmpEq,
+            TokenType::PipeEq,
+            TokenType::CaretEq,
+            TokenType::ShlEq,
+        ]) {
+            return self.previous_token().ok().map(|token| token.lexeme);
+        }
+        if self.check_adjacent(TokenType::Gt, TokenType::Ge) {
+            self.advance();
+            self.advance();
+            return Some(">>=".to_string());
+        }
+        None
+    }
+
+    /// Returns true if the current token is `first` and is immediately
+    /// followed, with no space in between, by a `second` token.
+    ///
+    /// The lexer never joins `>` characters so that nested generics such as
+    /// `Vec<Vec<i32>>` close properly; `>>` and `>>=` are recognized here.
+    fn check_adjacent(&self, first: TokenType, second: TokenType) -> bool {
+        match (self.tokens.get(self.current), self.tokens.get(self.current + 1)) {
+            (Some(a), Some(b)) => {
+                a.token_type == first
+                    && b.token_type == second
+                    && a.span.end.offset == b.span.start.offset
+            }
+            _ => false,
+        }
+    }
+
+    /// Parses an expression.
+    fn parse_expression(&mut self) -> Result<AstNode> {
+        self.parse_range_expression()
+    }
+
+    /// Runs `parse` with struct literals allowed or not, restoring the
+    /// previous setting afterwards.
+    ///
+    /// Struct literals are disallowed in an expression directly followed by
+    /// a block, such as `wyo activo {`, and allowed again inside brackets
+    /// and blocks nested within it.
+    fn with_struct_literals<T>(&mut self, allowed: bool, parse: fn(&mut Self) -> Result<T>) -> Result<T> {
+        let outer = std::mem::replace(&mut self.no_struct_literal, !allowed);
+        let result = parse(self);
+        self.no_struct_literal = outer;
+        result
+    }
+
+    /// Builds a binary operation node spanning both operands.
+    fn binary(left: AstNode, op: String, right: AstNode) -> AstNode {
+        AstNode::Expression(Expression::BinaryOp(BinaryOp {
+            span: Span::new(left.span().start, right.span().end),
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        }))
+    }
+
+    /// Parses a left-associative chain of the given binary operators, with
+    /// `operand` parsing the next tighter precedence level.
+    fn parse_binary_level(
+        &mut self,
+        operators: &[TokenType],
+        operand: fn(&mut Self) -> Result<AstNode>,
+    ) -> Result<AstNode> {
+        let mut left = operand(self)?;
+        while self.match_tokens(operators) {
             let op = self.previous_token()?.lexeme.clone();
-            let right = self.parse_equality_expression()?;
-            let end_pos = self.previous_token()?.span.end;
-            left = AstNode::Expression(Expression::BinaryOp(BinaryOp {
-                left: Box::new(left),
-                op,
-                right: Box::new(right),
-                span: Span::new(start_pos, end_pos),
-            }));
+            let right = operand(self)?;
+            left = Self::binary(left, op, right);
         }
         Ok(left)
     }
 
+    // Operator precedence, from loosest to tightest:
+    //
+    //   ..  ..=             (non-associative)
+    //   ||
+    //   &&
+    //   ==  !=
+    //   <  >  <=  >=
+    //   |
+    //   ^
+    //   &
+    //   <<  >>
+    //   +  -
+    //   *  /  %
+    //   unary -  !  ~  spro
+
+    /// Parses a range expression such as `a..b` or `a..=b`.
+    fn parse_range_expression(&mut self) -> Result<AstNode> {
+        let left = self.parse_or_expression()?;
+        if self.match_tokens(&[TokenType::DotDot, TokenType::DotDotEq]) {
+            let op = self.previous_token()?.lexeme.clone();
+            let right = self.parse_or_expression()?;
+            Ok(Self::binary(left, op, right))
+        } else {
+            Ok(left)
+        }
+    }
+
+    // Parses an 'or' expression.
+    fn parse_or_expression(&mut self) -> Result<AstNode> {
+        self.parse_binary_level(&[TokenType::Or], Self::parse_and_expression)
+    }
+
+    // Parses an 'and' expression.
+    fn parse_and_expression(&mut self) -> Result<AstNode> {
+        self.parse_binary_level(&[TokenType::And], Self::parse_equality_expression)
+    }
+
     // Parses an equality expression.
     fn parse_equality_expression(&mut self) -> Result<AstNode> {
-        let mut left = self.parse_comparison_expression()?;
-        while self.match_tokens(&[TokenType::EqEq, TokenType::Ne]) {
-            let start_pos = self.current_token()?.span.start;
+        self.parse_binary_level(&[TokenType::EqEq, TokenType::Ne], Self::parse_comparison_expression)
+    }
+
+    // Parses a comparison expression.
+    fn parse_comparison_expression(&mut self) -> Result<AstNode> {
+        let mut left = self.parse_bit_or_expression()?;
+        // A `>` starting `>>=` belongs to an assignment, not a comparison.
+        while !self.check_adjacent(TokenType::Gt, TokenType::Ge)
+            && self.match_tokens(&[TokenType::Gt, TokenType::Lt, TokenType::Ge, TokenType::Le])
+        {
             let op = self.previous_token()?.lexeme.clone();
-            let right = self.parse_comparison_expression()?;
-            let end_pos = self.previous_token()?.span.end;
-            left = AstNode::Expression(Expression::BinaryOp(BinaryOp {
-                left: Box::new(left),
-                op,
-                right: Box::new(right),
-                span: Span::new(start_pos, end_pos),
-            }));
+            let right = self.parse_bit_or_expression()?;
+            left = Self::binary(left, op, right);
         }
         Ok(left)
     }
 
-    // Parses a comparison expression.
-    fn parse_comparison_expression(&mut self) -> Result<AstNode> {
+    /// Parses a bitwise or expression.
+    fn parse_bit_or_expression(&mut self) -> Result<AstNode> {
+        self.parse_binary_level(&[TokenType::Pipe], Self::parse_bit_xor_expression)
+    }
+
+    /// Parses a bitwise xor expression.
+    fn parse_bit_xor_expression(&mut self) -> Result<AstNode> {
+        self.parse_binary_level(&[TokenType::Caret], Self::parse_bit_and_expression)
+    }
+
+    /// Parses a bitwise and expression.
+    fn parse_bit_and_expression(&mut self) -> Result<AstNode> {
+        self.parse_binary_level(&[TokenType::Amp], Self::parse_shift_expression)
+    }
+
+    /// Parses a shift expression.
+    fn parse_shift_expression(&mut self) -> Result<AstNode> {
         let mut left = self.parse_term()?;
-        while self.match_tokens(&[TokenType::Gt, TokenType::Lt, TokenType::Ge, TokenType::Le]) {
-            let start_pos = self.current_token()?.span.start;
-            let op = self.previous_token()?.lexeme.clone();
+        loop {
+            let op = if self.match_token(TokenType::Shl) {
+                "<<"
+            } else if self.check_adjacent(TokenType::Gt, TokenType::Gt) {
+                self.advance();
+                self.advance();
+                ">>"
+            } else {
+                return Ok(left);
+            };
             let right = self.parse_term()?;
-            let end_pos = self.previous_token()?.span.end;
-            left = AstNode::Expression(Expression::BinaryOp(BinaryOp {
-                left: Box::new(left),
-                op,
-                right: Box::new(right),
-                span: Span::new(start_pos, end_pos),
-            }));
+            left = Self::binary(left, op.to_string(), right);
         }
-        Ok(left)
     }
 
     // Parses a term expression.
     fn parse_term(&mut self) -> Result<AstNode> {
-        let mut left = self.parse_factor()?;
-        while self.match_tokens(&[TokenType::Plus, TokenType::Minus]) {
-            let start_pos = self.current_token()?.span.start;
-            let op = self.previous_token()?.lexeme.clone();
-            let right = self.parse_factor()?;
-            let end_pos = self.previous_token()?.span.end;
-            left = AstNode::Expression(Expression::BinaryOp(BinaryOp {
-                left: Box::new(left),
-                op,
-                right: Box::new(right),
-                span: Span::new(start_pos, end_pos),
-            }));
-        }
-        Ok(left)
+        self.parse_binary_level(&[TokenType::Plus, TokenType::Minus], Self::parse_factor)
     }
 
     /// Parses a factor expression.
     fn parse_factor(&mut self) -> Result<AstNode> {
-        let mut left = self.parse_unary()?;
-        while self.match_tokens(&[TokenType::Star, TokenType::Slash, TokenType::Percent]) {
-            let start_pos = self.current_token()?.span.start;
-            let op = self.previous_token()?.lexeme.clone();
-            let right = self.parse_unary()?;
-            let end_pos = self.previous_token()?.span.end;
-            left = AstNode::Expression(Expression::BinaryOp(BinaryOp {
-                left: Box::new(left),
-                op,
-                right: Box::new(right),
-                span: Span::new(start_pos, end_pos),
-            }));
-        }
-        Ok(left)
+        self.parse_binary_level(&[TokenType::Star, TokenType::Slash, TokenType::Percent], Self::parse_unary)
     }
 
     /// Parses a unary expression.
     fn parse_unary(&mut self) -> Result<AstNode> {
-        if self.match_tokens(&[TokenType::Minus, TokenType::Not]) {
-            let start_pos = self.current_token()?.span.start;
+        if self.match_tokens(&[TokenType::Minus, TokenType::Not, TokenType::Tilde]) {
+            let start_pos = self.previous_token()?.span.start;
             let op = self.previous_token()?.lexeme.clone();
             let operand = self.parse_unary()?;
             let end_pos = self.previous_token()?.span.end;
@@ -454,8 +1336,71 @@ impl Parser {
                 operand: Box::new(operand),
                 span: Span::new(start_pos, end_pos),
             })))
+        } else if self.check(TokenType::Spro) {
+            let start_pos = self.current_token()?.span.start;
+            self.advance(); // Consume 'spro'
+            let value = self.parse_unary()?;
+            let end_pos = self.previous_token()?.span.end;
+            Ok(AstNode::Expression(Expression::Await(Await {
+                value: Box::new(value),
+                span: Span::new(start_pos, end_pos),
+            })))
         } else {
-            self.parse_primary()
+            self.parse_postfix()
+        }
+    }
+
+    /// Parses a primary expression followed by any number of calls, field
+    /// accesses, method calls, index operations and `?` propagations.
+    fn parse_postfix(&mut self) -> Result<AstNode> {
+        let start_pos = self.current_token()?.span.start;
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.match_token(TokenType::LParen) {
+                let args = self.parse_function_call_arguments()?;
+                let end_pos = self.previous_token()?.span.end;
+                expr = AstNode::Expression(Expression::FunctionCall(FunctionCall {
+                    callee: Box::new(expr),
+                    args,
+                    span: Span::new(start_pos, end_pos),
+                }));
+            } else if self.match_token(TokenType::Dot) {
+                let name = self.consume(TokenType::Identifier(String::new()))?.lexeme;
+                if self.match_token(TokenType::LParen) {
+                    let args = self.parse_function_call_arguments()?;
+                    let end_pos = self.previous_token()?.span.end;
+                    expr = AstNode::Expression(Expression::MethodCall(MethodCall {
+                        receiver: Box::new(expr),
+                        method: name,
+                        args,
+                        span: Span::new(start_pos, end_pos),
+                    }));
+                } else {
+                    let end_pos = self.previous_token()?.span.end;
+                    expr = AstNode::Expression(Expression::FieldAccess(FieldAccess {
+                        object: Box::new(expr),
+                        field: name,
+                        span: Span::new(start_pos, end_pos),
+                    }));
+                }
+            } else if self.match_token(TokenType::Question) {
+                let end_pos = self.previous_token()?.span.end;
+                expr = AstNode::Expression(Expression::Propagate(Propagate {
+                    value: Box::new(expr),
+                    span: Span::new(start_pos, end_pos),
+                }));
+            } else if self.match_token(TokenType::LBracket) {
+                let index = self.with_struct_literals(true, Self::parse_expression)?;
+                self.consume(TokenType::RBracket)?;
+                let end_pos = self.previous_token()?.span.end;
+                expr = AstNode::Expression(Expression::ArrayAccess(ArrayAccess {
+                    array: Box::new(expr),
+                    index: Box::new(index),
+                    span: Span::new(start_pos, end_pos),
+                }));
+            } else {
+                return Ok(expr);
+            }
         }
     }
 
@@ -463,72 +1408,392 @@ impl Parser {
     fn parse_primary(&mut self) -> Result<AstNode> {
         let token = self.current_token()?;
         match token.token_type {
-            TokenType::IntLiteral(val) => {
+            TokenType::IntLiteral(..)
+            | TokenType::FloatLiteral(..)
+            | TokenType::StringLiteral(_)
+            | TokenType::StiloLiteral(_)
+            | TokenType::ByteStringLiteral(_)
+            | TokenType::BoolLiteral(_)
+            | TokenType::Nil => Ok(AstNode::Expression(Expression::Literal(self.parse_literal()?))),
+            TokenType::Prnt => self.parse_builtin_call(),
+            TokenType::Identifier(_) if self.starts_builtin_call() => self.parse_builtin_call(),
+            TokenType::Identifier(_) => self.parse_identifier_expression(),
+            TokenType::Mth => Ok(AstNode::Expression(Expression::Match(self.parse_match()?))),
+            TokenType::Soy => {
                 self.advance();
-                Ok(AstNode::Expression(Expression::Literal(Literal::Int(
-                    val,
-                    token.span,
-                ))))
+                Ok(AstNode::Expression(Expression::SelfRef(token.span)))
             }
-            TokenType::FloatLiteral(val) => {
+            TokenType::LParen => {
                 self.advance();
-                Ok(AstNode::Expression(Expression::Literal(Literal::Float(
-                    val,
-                    token.span,
-                ))))
+                let (mut elements, trailing_comma) = self.parse_expression_list(TokenType::RParen)?;
+                let end_pos = self.previous_token()?.span.end;
+                // `(e)` is just a parenthesized expression; `(e,)` is a tuple.
+                if elements.len() == 1 && !trailing_comma {
+                    Ok(elements.remove(0))
+                } else {
+                    Ok(AstNode::Expression(Expression::Tuple(elements, Span::new(token.span.start, end_pos))))
+                }
             }
-            TokenType::StringLiteral(val) => {
+            TokenType::LBracket => {
                 self.advance();
-                Ok(AstNode::Expression(Expression::Literal(Literal::Strng(
-                    val,
-                    token.span,
-                ))))
+                let (elements, _) = self.parse_expression_list(TokenType::RBracket)?;
+                let end_pos = self.previous_token()?.span.end;
+                Ok(AstNode::Expression(Expression::Array(elements, Span::new(token.span.start, end_pos))))
             }
-            TokenType::BoolLiteral(val) => {
-                self.advance();
-                Ok(AstNode::Expression(Expression::Literal(Literal::Bool(
-                    val,
-                    token.span,
-                ))))
+            _ => Err(self.error("Expected literal, identifier, '(' or '['")),
+        }
+    }
+
+    /// Returns true if the current identifier begins a `name!(...)` call.
+    fn starts_builtin_call(&self) -> bool {
+        matches!(
+            (self.tokens.get(self.current + 1), self.tokens.get(self.current + 2)),
+            (Some(bang), Some(paren))
+                if bang.token_type == TokenType::Not && paren.token_type == TokenType::LParen
+        )
+    }
+
+    /// Parses a built-in call: `prnt(...)`, `prnt!(...)` or `name!(...)`.
+    ///
+    /// Built-ins taking a format string have their placeholders checked
+    /// against the number of arguments.
+    fn parse_builtin_call(&mut self) -> Result<AstNode> {
+        let name_token = self.current_token()?;
+        self.advance(); // Consume the name
+        if name_token.token_type == TokenType::Prnt {
+            self.match_token(TokenType::Not);
+        } else {
+            self.consume(TokenType::Not)?; // Consume '!'
+        }
+        self.consume(TokenType::LParen)?; // Consume '('
+        let args = self.parse_function_call_arguments()?;
+        let end_pos = self.previous_token()?.span.end;
+
+        let call = BuiltinCall {
+            name: name_token.lexeme,
+            args,
+            span: Span::new(name_token.span.start, end_pos),
+        };
+        if call.takes_format() {
+            Self::check_format_arguments(&call)?;
+        }
+        Ok(AstNode::Expression(Expression::BuiltinCall(call)))
+    }
+
+    /// Checks that a format built-in starts with a string literal whose
+    /// placeholders match the remaining arguments.
+    fn check_format_arguments(call: &BuiltinCall) -> Result<()> {
+        let (format, format_span) = match call.args.first() {
+            Some(AstNode::Expression(Expression::Literal(Literal::Strng(format, span) | Literal::Stilo(format, span)))) => {
+                (format, *span)
             }
-            TokenType::Nil => {
+            other => {
+                return Err(ParserError::Parser {
+                    message: format!("'{}' expects a string literal as its format argument", call.name),
+                    span: other.map_or(call.span, AstNode::span),
+                })
+            }
+        };
+
+        let expected = Self::format_arity(format).map_err(|message| ParserError::Parser {
+            message,
+            span: format_span,
+        })?;
+        let given = call.args.len() - 1;
+        if expected != given {
+            return Err(ParserError::Parser {
+                message: format!("Format string expects {} argument(s), but {} were given", expected, given),
+                span: call.span,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the number of arguments the placeholders of a format string
+    /// refer to. Placeholders are `{}`, `{N}` or `{name}`, optionally
+    /// followed by a `:spec`; `{{` and `}}` stand for literal braces. A named
+    /// placeholder formats the variable of that name rather than an
+    /// argument, so it does not count towards the arity.
+    fn format_arity(format: &str) -> std::result::Result<usize, String> {
+        let mut chars = format.chars().peekable();
+        let mut next_implicit = 0;
+        let mut arity = 0;
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => {}
+                '}' if chars.next_if_eq(&'}').is_some() => {}
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err("Unterminated '{' in format string".to_string()),
+                            Some(c) => placeholder.push(c),
+                        }
+                    }
+                    let position = placeholder.split(':').next().unwrap_or_default();
+                    let is_name = position.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                        && position.chars().all(|c| c.is_alphanumeric() || c == '_');
+                    if is_name {
+                        continue;
+                    }
+                    let index = if position.is_empty() {
+                        next_implicit += 1;
+                        next_implicit - 1
+                    } else {
+                        position
+                            .parse::<usize>()
+                            .map_err(|_| format!("Invalid placeholder '{{{}}}' in format string", placeholder))?
+                    };
+                    arity = arity.max(index + 1);
+                }
+                '}' => return Err("Unmatched '}' in format string; use '}}' for a literal brace".to_string()),
+                _ => {}
+            }
+        }
+        Ok(arity)
+    }
+
+    /// Parses an identifier-based expression: a variable, a path or a
+    /// struct literal.
+    fn parse_identifier_expression(&mut self) -> Result<AstNode> {
+        let mut path = self.parse_path()?;
+
+        if !self.no_struct_literal && self.check(TokenType::LBrace) {
+            self.parse_struct_literal(path)
+        } else if path.segments.len() == 1 {
+            Ok(AstNode::Expression(Expression::Identifier(
+                path.segments.remove(0),
+                path.span,
+            )))
+        } else {
+            Ok(AstNode::Expression(Expression::Path(path)))
+        }
+    }
+
+    /// Parses a match expression.
+    fn parse_match(&mut self) -> Result<Match> {
+        let start_pos = self.current_token()?.span.start;
+        self.consume(TokenType::Mth)?; // Consume 'mth'
+        let scrutinee = self.with_struct_literals(false, Self::parse_expression)?;
+        self.consume(TokenType::LBrace)?; // Consume '{'
+
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RBrace) && !self.is_at_end() {
+            let arm = self.with_struct_literals(true, Self::parse_match_arm)?;
+            // Arms with a block body don't need a separating comma.
+            let block_body = matches!(*arm.body, AstNode::Statement(Statement::Block(..)));
+            arms.push(arm);
+            if !self.match_token(TokenType::Comma) && !block_body {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RBrace)?; // Consume '}'
+        let end_pos = self.previous_token()?.span.end;
+        Ok(Match {
+            scrutinee: Box::new(scrutinee),
+            arms,
+            span: Span::new(start_pos, end_pos),
+        })
+    }
+
+    /// Parses a single `Pattern [ate guard] => body` match arm; the guard
+    /// is introduced by `si` in the V2 edition.
+    fn parse_match_arm(&mut self) -> Result<MatchArm> {
+        let pattern = self.parse_pattern()?;
+        let (guard_keyword, _) = self.conditional_keywords();
+        let guard = if self.match_token(guard_keyword) {
+            Some(Box::new(self.parse_expression()?))
+        } else {
+            None
+        };
+        self.consume(TokenType::FatArrow)?; // Consume '=>'
+
+        let body = if self.check(TokenType::LBrace) {
+            let block_start = self.current_token()?.span.start;
+            let statements = self.parse_block_statement()?;
+            let block_end = self.previous_token()?.span.end;
+            AstNode::Statement(Statement::Block(statements, Span::new(block_start, block_end)))
+        } else {
+            self.parse_expression()?
+        };
+
+        let end_pos = self.previous_token()?.span.end;
+        Ok(MatchArm {
+            span: Span::new(pattern.span().start, end_pos),
+            pattern,
+            guard,
+            body: Box::new(body),
+        })
+    }
+
+    /// Parses a pattern.
+    fn parse_pattern(&mut self) -> Result<Pattern> {
+        let token = self.current_token()?;
+        match &token.token_type {
+            TokenType::Identifier(name) if name == "_" => {
                 self.advance();
-                Ok(AstNode::Expression(Expression::Literal(Literal::Nil(
-                    token.span,
-                ))))
+                Ok(Pattern::Wildcard(token.span))
             }
-            TokenType::Identifier(_) => self.parse_identifier_expression(),
+            TokenType::Identifier(_) => self.parse_path_pattern(),
             TokenType::LParen => {
                 self.advance();
-                let expr = self.parse_expression()?;
-                self.consume(TokenType::RParen)?;
-                Ok(expr)
+                let (mut elements, trailing_comma) = self.parse_pattern_list(TokenType::RParen)?;
+                let end_pos = self.previous_token()?.span.end;
+                // `(p)` is just a parenthesized pattern; `(p,)` is a tuple.
+                if elements.len() == 1 && !trailing_comma {
+                    Ok(elements.remove(0))
+                } else {
+                    Ok(Pattern::Tuple(elements, Span::new(token.span.start, end_pos)))
+                }
+            }
+            TokenType::Minus => {
+                self.advance();
+                let span = Span::new(token.span.start, self.current_token()?.span.end);
+                match self.parse_literal()? {
+                    Literal::Int(val, suffix, _) => Ok(Pattern::Literal(Literal::Int(-val, suffix, span))),
+                    Literal::Float(val, suffix, _) => Ok(Pattern::Literal(Literal::Float(-val, suffix, span))),
+                    _ => Err(ParserError::Parser {
+                        message: "Only numeric literal patterns can be negated".to_string(),
+                        span,
+                    }),
+                }
             }
-            _ => Err(self.error("Expected literal, identifier, or '('")),
+            TokenType::IntLiteral(..)
+            | TokenType::FloatLiteral(..)
+            | TokenType::StringLiteral(_)
+            | TokenType::StiloLiteral(_)
+            | TokenType::ByteStringLiteral(_)
+            | TokenType::BoolLiteral(_)
+            | TokenType::Nil => Ok(Pattern::Literal(self.parse_literal()?)),
+            _ => Err(ParserError::UnexpectedToken {
+                expected: "pattern".to_string(),
+                found: token.lexeme.clone(),
+                span: token.span,
+            }),
+        }
+    }
+
+    /// Parses a pattern starting with a name: a binding, an enum variant or a
+    /// struct destructuring.
+    fn parse_path_pattern(&mut self) -> Result<Pattern> {
+        let start_pos = self.current_token()?.span.start;
+        let mut path = self.parse_path()?;
+
+        if self.match_token(TokenType::LParen) {
+            let (payload, _) = self.parse_pattern_list(TokenType::RParen)?;
+            let end_pos = self.previous_token()?.span.end;
+            return Ok(Pattern::Variant(VariantPattern {
+                path,
+                payload: Some(payload),
+                span: Span::new(start_pos, end_pos),
+            }));
+        }
+
+        if self.match_token(TokenType::LBrace) {
+            let mut fields = Vec::new();
+            let mut rest = false;
+            while !self.check(TokenType::RBrace) && !self.is_at_end() {
+                if self.match_token(TokenType::DotDot) {
+                    rest = true;
+                    break;
+                }
+                let name_token = self.consume(TokenType::Identifier(String::new()))?;
+                let pattern = if self.match_token(TokenType::Colon) {
+                    self.parse_pattern()?
+                } else {
+                    Pattern::Identifier(name_token.lexeme.clone(), name_token.span)
+                };
+                let end_pos = self.previous_token()?.span.end;
+                fields.push(FieldPattern {
+                    name: name_token.lexeme,
+                    pattern,
+                    span: Span::new(name_token.span.start, end_pos),
+                });
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RBrace)?; // Consume '}'
+            let end_pos = self.previous_token()?.span.end;
+            return Ok(Pattern::Struct(StructPattern {
+                path,
+                fields,
+                rest,
+                span: Span::new(start_pos, end_pos),
+            }));
+        }
+
+        let span = path.span;
+        if path.segments.len() == 1 {
+            Ok(Pattern::Identifier(path.segments.remove(0), span))
+        } else {
+            Ok(Pattern::Variant(VariantPattern { path, payload: None, span }))
         }
     }
 
-    /// Parses an identifier-based expression (variable, function call, etc.).
-    fn parse_identifier_expression(&mut self) -> Result<AstNode> {
-        let start_pos = self.current_token()?.span.start;
-        let name_token = self.consume(TokenType::Identifier(String::new()))?;
-        let name = name_token.lexeme.clone();
+    /// Parses comma-separated patterns up to and including `close`. Also
+    /// returns whether the list ended with a trailing comma.
+    fn parse_pattern_list(&mut self, close: TokenType) -> Result<(Vec<Pattern>, bool)> {
+        let mut patterns = Vec::new();
+        let mut trailing_comma = false;
+        while !self.check(close.clone()) && !self.is_at_end() {
+            patterns.push(self.parse_pattern()?);
+            trailing_comma = self.match_token(TokenType::Comma);
+            if !trailing_comma {
+                break;
+            }
+        }
+        self.consume(close)?;
+        Ok((patterns, trailing_comma))
+    }
 
-        if self.match_token(TokenType::LParen) {
-            let args = self.parse_function_call_arguments()?;
-            let end_pos = self.previous_token()?.span.end;
-            Ok(AstNode::Expression(Expression::FunctionCall(FunctionCall {
-                name,
-                args,
-                span: Span::new(start_pos, end_pos),
-            })))
-        } else {
+    /// Parses the `{ field: value, ... }` part of a struct literal.
+    fn parse_struct_literal(&mut self, path: Path) -> Result<AstNode> {
+        self.consume(TokenType::LBrace)?; // Consume '{'
+        let mut fields = Vec::new();
+        while !self.check(TokenType::RBrace) && !self.is_at_end() {
+            let name_token = self.consume(TokenType::Identifier(String::new()))?;
+            let value = if self.match_token(TokenType::Colon) {
+                self.with_struct_literals(true, Self::parse_expression)?
+            } else {
+                // `nombre` is shorthand for `nombre: nombre`.
+                AstNode::Expression(Expression::Identifier(name_token.lexeme.clone(), name_token.span))
+            };
             let end_pos = self.previous_token()?.span.end;
-            Ok(AstNode::Expression(Expression::Identifier(
-                name,
-                Span::new(start_pos, end_pos),
-            )))
+            fields.push(FieldInit {
+                name: name_token.lexeme,
+                value,
+                span: Span::new(name_token.span.start, end_pos),
+            });
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RBrace)?; // Consume '}'
+        let end_pos = self.previous_token()?.span.end;
+        Ok(AstNode::Expression(Expression::StructLiteral(StructLiteral {
+            span: Span::new(path.span.start, end_pos),
+            path,
+            fields,
+        })))
+    }
+
+    /// Parses a comma-separated list of expressions up to and including
+    /// `close`, and reports whether the list ended with a trailing comma.
+    fn parse_expression_list(&mut self, close: TokenType) -> Result<(Vec<AstNode>, bool)> {
+        let mut elements = Vec::new();
+        let mut trailing_comma = false;
+        while !self.check(close.clone()) && !self.is_at_end() {
+            elements.push(self.with_struct_literals(true, Self::parse_expression)?);
+            trailing_comma = self.match_token(TokenType::Comma);
+            if !trailing_comma {
+                break;
+            }
         }
+        self.consume(close)?;
+        Ok((elements, trailing_comma))
     }
 
     /// Parses the arguments of a function call.
@@ -536,7 +1801,7 @@ impl Parser {
         let mut args = Vec::new();
         if !self.check(TokenType::RParen) {
             loop {
-                let arg = self.parse_expression()?;
+                let arg = self.with_struct_literals(true, Self::parse_expression)?;
                 args.push(arg);
                 if !self.match_token(TokenType::Comma) {
                     break;
@@ -551,18 +1816,26 @@ impl Parser {
     fn parse_literal(&mut self) -> Result<Literal> {
         let token = self.current_token()?;
         match token.token_type {
-            TokenType::IntLiteral(val) => {
+            TokenType::IntLiteral(val, suffix) => {
                 self.advance();
-                Ok(Literal::Int(val, token.span))
+                Ok(Literal::Int(val, suffix, token.span))
             }
-            TokenType::FloatLiteral(val) => {
+            TokenType::FloatLiteral(val, suffix) => {
                 self.advance();
-                Ok(Literal::Float(val, token.span))
+                Ok(Literal::Float(val, suffix, token.span))
             }
             TokenType::StringLiteral(val) => {
                 self.advance();
                 Ok(Literal::Strng(val, token.span))
             }
+            TokenType::StiloLiteral(val) => {
+                self.advance();
+                Ok(Literal::Stilo(val, token.span))
+            }
+            TokenType::ByteStringLiteral(val) => {
+                self.advance();
+                Ok(Literal::Bytes(val, token.span))
+            }
             TokenType::BoolLiteral(val) => {
                 self.advance();
                 Ok(Literal::Bool(val, token.span))
@@ -582,6 +1855,9 @@ impl Parser {
     /// Parses an expression statement.
     fn parse_expression_statement(&mut self) -> Result<AstNode> {
         let expr = self.parse_expression()?;
+        if let Some(op) = self.match_assignment_operator() {
+            return self.parse_assignment(expr, op);
+        }
         self.consume(TokenType::Semicolon)?; // Consume ';'
         match expr {
             AstNode::Expression(e) => Ok(AstNode::Statement(Statement::Expression(e))),
@@ -590,9 +1866,13 @@ impl Parser {
     }
 
     /// Consumes the current token if it matches the expected type.
+    ///
+    /// Token types carrying a payload (identifiers, literals) match on the
+    /// variant alone, so `TokenType::Identifier(String::new())` accepts any
+    /// identifier.
     fn consume(&mut self, expected_type: TokenType) -> Result<Token> {
         let token = self.current_token()?;
-        if token.token_type == expected_type {
+        if Self::same_kind(&token.token_type, &expected_type) {
             self.advance();
             Ok(token)
         } else {
@@ -607,7 +1887,16 @@ impl Parser {
 
     /// Checks if the current token matches the given type without consuming it.
     fn check(&mut self, token_type: TokenType) -> bool {
-        !self.is_at_end() && self.current_token().map_or(false, |t| t.token_type == token_type)
+        !self.is_at_end() && self.current_token().is_ok_and(|t| Self::same_kind(&t.token_type, &token_type))
+    }
+
+    /// Returns true if both token types are the same variant, ignoring payloads.
+    ///
+    /// Comparing whole token types would make `consume` and `check` reject
+    /// every real identifier or literal, since callers pass an empty payload
+    /// as the expected type.
+    fn same_kind(a: &TokenType, b: &TokenType) -> bool {
+        std::mem::discriminant(a) == std::mem::discriminant(b)
     }
 
     /// Returns the current token without consuming it.
@@ -632,17 +1921,6 @@ impl Parser {
         }
     }
 
-    /// Returns the token after the next without consuming it.
-    fn peek_next(&self) -> Result<Token> {
-        if self.current + 1 >= self.tokens.len() {
-            Err(ParserError::UnexpectedEof {
-                span: Span::new(Position::new(0, 0, 0), Position::new(0, 0, 0)),
-            })
-        } else {
-            Ok(self.tokens[self.current + 1].clone())
-        }
-    }
-
     /// Returns the previously consumed token.
     fn previous_token(&self) -> Result<Token> {
         if self.current == 0 {
@@ -662,7 +1940,7 @@ impl Parser {
     }
 
     /// Checks if the current token is the end of input.
-    fn is_at_end(&self) -> bool {
+    pub(crate) fn is_at_end(&self) -> bool {
         self.current >= self.tokens.len() || self.tokens[self.current].token_type == TokenType::Eof
     }
 
@@ -696,4 +1974,817 @@ impl Parser {
             span,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn type_names(types: &[TypeExpr]) -> Vec<String> {
+        types.iter().map(ToString::to_string).collect()
+    }
+
+    fn parse(source: &str) -> Vec<AstNode> {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    fn parse_recovering(source: &str) -> (Vec<AstNode>, Vec<ParserError>) {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens).parse_with_recovery()
+    }
+
+    #[test]
+    fn test_recovery_missing_semicolon() {
+        let (nodes, errors) = parse_recovering("djq x = 1\ndjq y = 2;\nfnc f() { ret y; }");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(nodes.len(), 3);
+        assert!(matches!(nodes[0], AstNode::Error(_)));
+        assert!(matches!(nodes[1], AstNode::Statement(Statement::Declaration(_))));
+        assert!(matches!(nodes[2], AstNode::Statement(Statement::Function(_))));
+    }
+
+    #[test]
+    fn test_recovery_inside_block() {
+        let (nodes, errors) = parse_recovering("fnc f() { ret ; ret 1; }\nforma P { x: y }");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(nodes.len(), 2);
+        match &nodes[0] {
+            AstNode::Statement(Statement::Function(func)) => {
+                assert_eq!(func.body.len(), 2);
+                assert!(matches!(func.body[0], AstNode::Error(_)));
+            }
+            other => panic!("expected function, found {:?}", other),
+        }
+        assert!(matches!(nodes[1], AstNode::Statement(Statement::Struct(_))));
+    }
+
+    #[test]
+    fn test_recovery_stray_brace() {
+        let (nodes, errors) = parse_recovering("} enum Color { Rojo, Verde }");
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(nodes[0], AstNode::Error(_)));
+        assert!(matches!(nodes[1], AstNode::Statement(Statement::Enum(_))));
+    }
+
+    #[test]
+    fn test_tokens_match_by_kind() {
+        let tokens = Lexer::new("forma Punto { x: Num, y: Num }\ndjq p = 1;").tokenize().unwrap();
+        let nodes = Parser::new(tokens).parse().unwrap();
+        match &nodes[0] {
+            AstNode::Statement(Statement::Struct(def)) => {
+                assert_eq!(def.name, "Punto");
+                assert_eq!(def.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["x", "y"]);
+            }
+            other => panic!("expected struct, found {:?}", other),
+        }
+        assert!(matches!(nodes[1], AstNode::Statement(Statement::Declaration(_))));
+
+        let mut parser = Parser::new(Lexer::new("nombre 42").tokenize().unwrap());
+        assert!(parser.check(TokenType::Identifier(String::new())));
+        assert_eq!(parser.consume(TokenType::Identifier(String::new())).unwrap().lexeme, "nombre");
+        assert!(!parser.check(TokenType::Identifier(String::new())));
+        assert_eq!(parser.consume(TokenType::IntLiteral(0, None)).unwrap().lexeme, "42");
+    }
+
+    #[test]
+    fn test_strict_parse_stops_at_first_error() {
+        let tokens = Lexer::new("djq x = 1\ndjq y = 2;").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_implementation_block() {
+        let nodes = parse("imp Persona {\n    fnc saludo(soy) { ret soy.nombre; }\n    fnc crear(nombre) { ret nombre; }\n}");
+        let imp = match &nodes[0] {
+            AstNode::Statement(Statement::Implementation(imp)) => imp,
+            other => panic!("expected implementation, found {:?}", other),
+        };
+        assert_eq!(imp.target.to_string(), "Persona");
+        assert_eq!(imp.interface, None);
+        assert_eq!(imp.methods.len(), 2);
+
+        let saludo = &imp.methods[0];
+        assert!(saludo.receiver.is_some());
+        assert!(saludo.params.is_empty());
+        assert!(matches!(
+            &saludo.body[0],
+            AstNode::Statement(Statement::ReturnStatement(ret))
+                if matches!(&*ret.value, AstNode::Expression(Expression::FieldAccess(access))
+                    if matches!(*access.object, AstNode::Expression(Expression::SelfRef(_))) && access.field == "nombre")
+        ));
+        assert!(imp.methods[1].receiver.is_none());
+    }
+
+    #[test]
+    fn test_implementation_of_interface() {
+        let nodes = parse("imp Saludar spa Persona { fnc saludo(soy, otro) { ret soy; } }");
+        match &nodes[0] {
+            AstNode::Statement(Statement::Implementation(imp)) => {
+                assert_eq!(imp.target.to_string(), "Persona");
+                assert_eq!(imp.interface.as_ref().map(ToString::to_string).as_deref(), Some("Saludar"));
+                assert_eq!(imp.methods[0].params[0].name, "otro");
+            }
+            other => panic!("expected implementation, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_receiver_must_come_first() {
+        let tokens = Lexer::new("imp Persona { fnc f(x, soy) { ret x; } }").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    fn parse_match_expr(source: &str) -> Match {
+        match parse(source).remove(0) {
+            AstNode::Statement(Statement::Expression(Expression::Match(match_expr))) => match_expr,
+            other => panic!("expected match, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_match_patterns() {
+        let match_expr = parse_match_expr(
+            "mth valor {\n    0 => cero(),\n    -1 => menos(),\n    \"hola\" => saludo(),\n    Res:>Ok(x) => x,\n    Color:>Rojo => rojo(),\n    Persona { nombre, edad: 30, .. } => nombre,\n    (a, _) => a,\n    otro => otro,\n}",
+        );
+        assert_eq!(match_expr.arms.len(), 8);
+        let patterns: Vec<&Pattern> = match_expr.arms.iter().map(|arm| &arm.pattern).collect();
+        assert!(matches!(patterns[0], Pattern::Literal(Literal::Int(0, _, _))));
+        assert!(matches!(patterns[1], Pattern::Literal(Literal::Int(-1, _, _))));
+        assert!(matches!(patterns[2], Pattern::Literal(Literal::Strng(_, _))));
+        match patterns[3] {
+            Pattern::Variant(variant) => {
+                assert_eq!(variant.path.segments, vec!["Res".to_string(), "Ok".to_string()]);
+                assert!(matches!(variant.payload.as_deref(), Some([Pattern::Identifier(name, _)]) if name == "x"));
+            }
+            other => panic!("expected variant pattern, found {:?}", other),
+        }
+        assert!(matches!(patterns[4], Pattern::Variant(VariantPattern { payload: None, .. })));
+        match patterns[5] {
+            Pattern::Struct(struct_pattern) => {
+                assert!(struct_pattern.rest);
+                assert_eq!(struct_pattern.fields.len(), 2);
+                assert!(matches!(&struct_pattern.fields[0].pattern, Pattern::Identifier(name, _) if name == "nombre"));
+                assert!(matches!(struct_pattern.fields[1].pattern, Pattern::Literal(Literal::Int(30, _, _))));
+            }
+            other => panic!("expected struct pattern, found {:?}", other),
+        }
+        assert!(matches!(patterns[6], Pattern::Tuple(elements, _) if matches!(elements[1], Pattern::Wildcard(_))));
+        assert!(matches!(patterns[7], Pattern::Identifier(name, _) if name == "otro"));
+    }
+
+    #[test]
+    fn test_match_guards_and_block_arms() {
+        let match_expr = parse_match_expr("mth n { x ate x > 10 => { ret x; } _ => 0 }");
+        assert_eq!(match_expr.arms.len(), 2);
+        assert!(match_expr.arms[0].guard.is_some());
+        assert!(matches!(*match_expr.arms[0].body, AstNode::Statement(Statement::Block(..))));
+        assert!(match_expr.arms[1].guard.is_none());
+    }
+
+    #[test]
+    fn test_match_spans() {
+        let source = "mth v { Algo(x) => x }";
+        let match_expr = parse_match_expr(source);
+        let arm = &match_expr.arms[0];
+        assert_eq!(match_expr.span.end.offset, source.len());
+        assert_eq!(arm.span.start.offset, 8);
+        assert_eq!(arm.span.end.offset, 20);
+        assert_eq!(arm.pattern.span().end.offset, 15);
+    }
+
+    #[test]
+    fn test_match_as_expression() {
+        let nodes = parse("usar(mth v { _ => 1 });");
+        assert!(matches!(
+            &nodes[0],
+            AstNode::Statement(Statement::Expression(Expression::FunctionCall(call)))
+                if matches!(call.args[0], AstNode::Expression(Expression::Match(_)))
+        ));
+    }
+
+    #[test]
+    fn test_for_loops() {
+        let nodes = parse("spa x in xs { usar(x); }\n4> (a, b) in pares { continue; }");
+        match &nodes[0] {
+            AstNode::Statement(Statement::ForStatement(for_stmt)) => {
+                assert_eq!(for_stmt.label, None);
+                assert!(matches!(&for_stmt.pattern, Pattern::Identifier(name, _) if name == "x"));
+                assert!(matches!(&*for_stmt.iterable, AstNode::Expression(Expression::Identifier(name, _)) if name == "xs"));
+                assert_eq!(for_stmt.body.len(), 1);
+            }
+            other => panic!("expected for loop, found {:?}", other),
+        }
+        match &nodes[1] {
+            AstNode::Statement(Statement::ForStatement(for_stmt)) => {
+                assert!(matches!(for_stmt.pattern, Pattern::Tuple(..)));
+                assert!(matches!(for_stmt.body[0], AstNode::Statement(Statement::Continue(LoopControl { label: None, .. }))));
+            }
+            other => panic!("expected for loop, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_for_arrow_or_comparison() {
+        let nodes = parse("djq x = 4> y;\nx = 4> y;\nate 4> y { usar(y); }\n4> z in zs { usar(z); }\nfuera: 4> z in zs { break fuera; }");
+        let is_comparison = |node: &AstNode| {
+            matches!(node, AstNode::Expression(Expression::BinaryOp(binary))
+                if binary.op == ">"
+                    && matches!(&*binary.left, AstNode::Expression(Expression::Literal(Literal::Int(4, ..))))
+                    && matches!(&*binary.right, AstNode::Expression(Expression::Identifier(name, _)) if name == "y"))
+        };
+        match &nodes[0] {
+            AstNode::Statement(Statement::Declaration(decl)) => assert!(is_comparison(&decl.value)),
+            other => panic!("expected declaration, found {:?}", other),
+        }
+        match &nodes[1] {
+            AstNode::Statement(Statement::Assignment(assign)) => assert!(is_comparison(&assign.value)),
+            other => panic!("expected assignment, found {:?}", other),
+        }
+        match &nodes[2] {
+            AstNode::Statement(Statement::IfStatement(if_stmt)) => assert!(is_comparison(&if_stmt.condition)),
+            other => panic!("expected if statement, found {:?}", other),
+        }
+        assert!(matches!(&nodes[3], AstNode::Statement(Statement::ForStatement(ForStatement { label: None, .. }))));
+        assert!(matches!(&nodes[4], AstNode::Statement(Statement::ForStatement(ForStatement { label: Some(label), .. })) if label == "fuera"));
+
+        // At the start of a statement only the loop shape makes `4>` a loop.
+        let nodes = parse("4>y;\n4> Punto { x, y } in puntos { usar(x); }");
+        match &nodes[0] {
+            AstNode::Statement(Statement::Expression(expr)) => assert!(is_comparison(&AstNode::Expression(expr.clone()))),
+            other => panic!("expected comparison, found {:?}", other),
+        }
+        assert!(matches!(&nodes[1], AstNode::Statement(Statement::ForStatement(_))));
+
+        let nodes = parse("imp Mostrar 4> Punto { fnc f() {} }");
+        assert!(matches!(&nodes[0], AstNode::Statement(Statement::Implementation(imp)) if imp.interface.is_some()));
+    }
+
+    #[test]
+    fn test_labeled_loop_control() {
+        let source = "fuera: wyo true { spa x in xs { ate x { break fuera; } continue; } }";
+        let nodes = parse(source);
+        let outer = match &nodes[0] {
+            AstNode::Statement(Statement::LoopStatement(outer)) => outer,
+            other => panic!("expected loop, found {:?}", other),
+        };
+        assert_eq!(outer.label.as_deref(), Some("fuera"));
+        assert_eq!(outer.span.start.offset, 0);
+        assert_eq!(outer.span.end.offset, source.len());
+
+        let inner = match &outer.body[0] {
+            AstNode::Statement(Statement::ForStatement(inner)) => inner,
+            other => panic!("expected for loop, found {:?}", other),
+        };
+        assert!(matches!(
+            &inner.body[0],
+            AstNode::Statement(Statement::IfStatement(if_stmt))
+                if matches!(&if_stmt.body[0], AstNode::Statement(Statement::Break(LoopControl { label: Some(label), .. })) if label == "fuera")
+        ));
+        assert!(matches!(inner.body[1], AstNode::Statement(Statement::Continue(_))));
+    }
+
+    #[test]
+    fn test_interface() {
+        let nodes = parse(
+            "ifz Nombre<T: Clone + Otro, U> : Base + core:>Mostrar {\n    fnc metodo(soy, x: i32) -> Strng;\n    fnc par(soy, v: Res<T, [u8]>) -> (i32, U);\n    fnc con_default(soy) { ret 1; }\n}",
+        );
+        let interface = match &nodes[0] {
+            AstNode::Statement(Statement::Interface(interface)) => interface,
+            other => panic!("expected interface, found {:?}", other),
+        };
+        assert_eq!(interface.name, "Nombre");
+        assert_eq!(interface.generics.len(), 2);
+        assert_eq!(type_names(&interface.generics[0].bounds), vec!["Clone", "Otro"]);
+        assert!(interface.generics[1].bounds.is_empty());
+        assert_eq!(type_names(&interface.super_interfaces), vec!["Base", "core:>Mostrar"]);
+
+        let metodo = &interface.methods[0];
+        assert!(metodo.receiver.is_some());
+        assert_eq!(metodo.params[0].name, "x");
+        assert!(matches!(&metodo.params[0].ty, Some(TypeExpr::Primitive(name, _)) if name == "i32"));
+        assert!(matches!(&metodo.return_type, Some(TypeExpr::Primitive(name, _)) if name == "Strng"));
+        assert!(metodo.body.is_none());
+
+        let par = &interface.methods[1];
+        assert_eq!(par.params[0].ty.as_ref().unwrap().to_string(), "Res<T, [u8]>");
+        assert_eq!(par.return_type.as_ref().unwrap().to_string(), "(i32, U)");
+
+        let con_default = &interface.methods[2];
+        assert_eq!(con_default.return_type, None);
+        assert_eq!(con_default.body.as_ref().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn test_typed_function_signature() {
+        let source = "fnc mapear<T: Clone, U>(muta soy, nmut xs: [T], f, muta n: i32) -> Res<U, Strng> { ret n; }";
+        let function = match parse(source).remove(0) {
+            AstNode::Statement(Statement::Function(function)) => function,
+            other => panic!("expected function, found {:?}", other),
+        };
+        assert_eq!(function.generics.len(), 2);
+        assert_eq!(function.generics[0].name, "T");
+        assert_eq!(function.return_type.unwrap().to_string(), "Res<U, Strng>");
+
+        let receiver = function.receiver.unwrap();
+        assert_eq!(receiver.mutability, Mutability::Mutable);
+        assert_eq!(receiver.span.start.offset, source.find("muta soy").unwrap());
+
+        let params: Vec<_> = function
+            .params
+            .iter()
+            .map(|param| (param.mutability, param.name.as_str(), param.ty.as_ref().map(ToString::to_string)))
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                (Mutability::Immutable, "xs", Some("[T]".to_string())),
+                (Mutability::Unspecified, "f", None),
+                (Mutability::Mutable, "n", Some("i32".to_string())),
+            ]
+        );
+        assert_eq!(function.params[0].span.start.offset, source.find("nmut xs").unwrap());
+    }
+
+    fn parse_type_of(source: &str) -> TypeExpr {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::new(tokens).parse_type().unwrap()
+    }
+
+    #[test]
+    fn test_type_expressions() {
+        assert!(matches!(parse_type_of("i32"), TypeExpr::Primitive(name, _) if name == "i32"));
+        assert!(matches!(parse_type_of("bool"), TypeExpr::Primitive(..)));
+        match parse_type_of("core:>io:>Handle") {
+            TypeExpr::Named(named) => {
+                assert_eq!(named.path.segments, vec!["core", "io", "Handle"]);
+                assert!(named.args.is_empty());
+            }
+            other => panic!("expected named type, found {:?}", other),
+        }
+        match parse_type_of("Res<i32, Strng>") {
+            TypeExpr::Named(named) => assert_eq!(named.args.len(), 2),
+            other => panic!("expected named type, found {:?}", other),
+        }
+        assert!(matches!(parse_type_of("[u8]"), TypeExpr::Slice(..)));
+        assert!(matches!(parse_type_of("[u8; 4]"), TypeExpr::Array(_, 4, _)));
+        assert!(matches!(parse_type_of("()"), TypeExpr::Tuple(elements, _) if elements.is_empty()));
+        assert!(matches!(parse_type_of("fnc(i32, f64) -> bool"), TypeExpr::Function(_)));
+
+        for source in ["Optn<[Persona]>", "(i32, Strng)", "fnc(i32) -> Res<(), Strng>", "[f64; 3]"] {
+            assert_eq!(parse_type_of(source).to_string(), source);
+        }
+    }
+
+    #[test]
+    fn test_types_in_declarations() {
+        let source = "forma Buffer { datos: [u8], manejador: core:>io:>Handle, res: Res<i32, Strng> }\ndjq n: i64 = 3;";
+        let nodes = parse(source);
+        match &nodes[0] {
+            AstNode::Statement(Statement::Struct(struct_def)) => {
+                assert_eq!(type_names(&struct_def.fields.iter().map(|f| f.ty.clone()).collect::<Vec<_>>()), vec!["[u8]", "core:>io:>Handle", "Res<i32, Strng>"]);
+                let field = &struct_def.fields[1];
+                assert_eq!(field.span.start.offset, source.find("manejador").unwrap());
+                assert_eq!(field.ty.span().end.offset, source.find(", res").unwrap());
+            }
+            other => panic!("expected struct, found {:?}", other),
+        }
+        assert!(matches!(
+            &nodes[1],
+            AstNode::Statement(Statement::Declaration(Declaration { ty: Some(TypeExpr::Primitive(name, _)), .. })) if name == "i64"
+        ));
+    }
+
+    fn parse_import_tree(source: &str) -> UseTree {
+        match parse(source).remove(0) {
+            AstNode::Statement(Statement::Import(import)) => import.tree,
+            other => panic!("expected import, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_grouped_imports() {
+        let tree = parse_import_tree("des core:>{math, io:>File as F, fs:>*};");
+        assert_eq!(tree.prefix.segments, vec!["core"]);
+        let nested = match tree.kind {
+            UseTreeKind::Nested(nested) => nested,
+            other => panic!("expected nested tree, found {:?}", other),
+        };
+        assert_eq!(nested.len(), 3);
+        assert_eq!(nested[0].prefix.to_string(), "math");
+        assert_eq!(nested[0].kind, UseTreeKind::Simple(None));
+        assert_eq!(nested[1].prefix.to_string(), "io:>File");
+        assert_eq!(nested[1].kind, UseTreeKind::Simple(Some("F".to_string())));
+        assert_eq!(nested[2].prefix.to_string(), "fs");
+        assert_eq!(nested[2].kind, UseTreeKind::Glob);
+
+        let tree = parse_import_tree("des {a, b:>{c}};");
+        assert!(tree.prefix.segments.is_empty());
+        assert!(matches!(&tree.kind, UseTreeKind::Nested(nested) if matches!(nested[1].kind, UseTreeKind::Nested(_))));
+
+        let tree = parse_import_tree("pydes numpy as np;");
+        assert_eq!(tree.prefix.to_string(), "numpy");
+        assert_eq!(tree.kind, UseTreeKind::Simple(Some("np".to_string())));
+    }
+
+    #[test]
+    fn test_path_expressions() {
+        let source = "core:>math:>add(5, 3);\nusar(Color:>Rojo);";
+        let nodes = parse(source);
+        match &nodes[0] {
+            AstNode::Statement(Statement::Expression(Expression::FunctionCall(call))) => {
+                match &*call.callee {
+                    AstNode::Expression(Expression::Path(path)) => {
+                        assert_eq!(path.segments, vec!["core", "math", "add"]);
+                        assert_eq!(path.span.end.offset, source.find('(').unwrap());
+                    }
+                    other => panic!("expected path callee, found {:?}", other),
+                }
+                assert_eq!(call.args.len(), 2);
+            }
+            other => panic!("expected call, found {:?}", other),
+        }
+        assert!(matches!(
+            &nodes[1],
+            AstNode::Statement(Statement::Expression(Expression::FunctionCall(call)))
+                if matches!(&call.args[0], AstNode::Expression(Expression::Path(path)) if path.to_string() == "Color:>Rojo")
+        ));
+    }
+
+    fn parse_expr(source: &str) -> Expression {
+        match parse(source).remove(0) {
+            AstNode::Statement(Statement::Expression(expr)) => expr,
+            other => panic!("expected expression statement, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_postfix_chains() {
+        // a.b.c
+        match parse_expr("a.b.c;") {
+            Expression::FieldAccess(outer) => {
+                assert_eq!(outer.field, "c");
+                assert!(matches!(&*outer.object, AstNode::Expression(Expression::FieldAccess(inner))
+                    if inner.field == "b" && matches!(&*inner.object, AstNode::Expression(Expression::Identifier(name, _)) if name == "a")));
+            }
+            other => panic!("expected field access, found {:?}", other),
+        }
+
+        // persona.saludo(1).len()
+        let source = "persona.saludo(1).len();";
+        match parse_expr(source) {
+            Expression::MethodCall(outer) => {
+                assert_eq!(outer.method, "len");
+                assert!(outer.args.is_empty());
+                assert_eq!(outer.span.end.offset, source.len() - 1);
+                match &*outer.receiver {
+                    AstNode::Expression(Expression::MethodCall(inner)) => {
+                        assert_eq!(inner.method, "saludo");
+                        assert_eq!(inner.args.len(), 1);
+                        assert_eq!(inner.span.end.offset, source.find(".len").unwrap());
+                    }
+                    other => panic!("expected method call, found {:?}", other),
+                }
+            }
+            other => panic!("expected method call, found {:?}", other),
+        }
+
+        // tabla[i][j]
+        match parse_expr("tabla[i][j];") {
+            Expression::ArrayAccess(outer) => {
+                assert!(matches!(&*outer.index, AstNode::Expression(Expression::Identifier(name, _)) if name == "j"));
+                assert!(matches!(&*outer.array, AstNode::Expression(Expression::ArrayAccess(inner))
+                    if matches!(&*inner.array, AstNode::Expression(Expression::Identifier(name, _)) if name == "tabla")));
+            }
+            other => panic!("expected array access, found {:?}", other),
+        }
+
+        // make()(x)
+        match parse_expr("make()(x);") {
+            Expression::FunctionCall(outer) => {
+                assert_eq!(outer.args.len(), 1);
+                assert!(matches!(&*outer.callee, AstNode::Expression(Expression::FunctionCall(inner)) if inner.args.is_empty()));
+            }
+            other => panic!("expected call, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_async_syntax() {
+        let source = "xnc fnc cargar(url) { djq x = 1; snc { usar(x); } ret spro pedir(url).texto(); }";
+        let nodes = parse(source);
+        let func = match &nodes[0] {
+            AstNode::Statement(Statement::Function(func)) => func,
+            other => panic!("expected function, found {:?}", other),
+        };
+        assert!(func.is_async);
+        assert_eq!(func.span.start.offset, 0);
+        assert!(matches!(&func.body[1], AstNode::Statement(Statement::SyncBlock(block))
+            if block.body.len() == 1 && &source[block.span.start.offset..block.span.start.offset + 3] == "snc"));
+        match &func.body[2] {
+            AstNode::Statement(Statement::ReturnStatement(ret)) => match &*ret.value {
+                AstNode::Expression(Expression::Await(await_expr)) => {
+                    assert!(matches!(&*await_expr.value, AstNode::Expression(Expression::MethodCall(call)) if call.method == "texto"));
+                    assert_eq!(await_expr.span.start.offset, source.find("spro").unwrap());
+                    assert_eq!(await_expr.span.end.offset, source.rfind("; }").unwrap());
+                }
+                other => panic!("expected await, found {:?}", other),
+            },
+            other => panic!("expected return, found {:?}", other),
+        }
+
+        assert!(!matches!(&parse("fnc f() {}")[0], AstNode::Statement(Statement::Function(func)) if func.is_async));
+        assert!(matches!(&parse("ifz Fuente { xnc fnc leer(soy); }")[0],
+            AstNode::Statement(Statement::Interface(iface)) if iface.methods[0].is_async));
+    }
+
+    fn parse_v2(source: &str) -> Vec<AstNode> {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        Parser::with_edition(tokens, Edition::V2).parse().unwrap()
+    }
+
+    #[test]
+    fn test_editions() {
+        // Legacy files keep `ate`/`rev` as if/else.
+        assert!(matches!(&parse("ate x { usar(1); } rev { usar(2); }")[0],
+            AstNode::Statement(Statement::IfStatement(stmt)) if stmt.else_body.is_some()));
+        // ...and may use `si`/`sino` as names.
+        let nodes = parse("djq si = 1; djq sino = si; ate si > sino { usar(si); }");
+        assert!(matches!(&nodes[1], AstNode::Statement(Statement::Declaration(decl))
+            if matches!(&*decl.value, AstNode::Expression(Expression::Identifier(name, _)) if name == "si")));
+        assert!(matches!(&nodes[2], AstNode::Statement(Statement::IfStatement(_))));
+
+        let nodes = parse_v2("si x { usar(1); } sino si y { usar(2); } sino { usar(3); }");
+        match &nodes[0] {
+            AstNode::Statement(Statement::IfStatement(stmt)) => {
+                let else_body = stmt.else_body.as_ref().unwrap();
+                assert!(matches!(&else_body[0], AstNode::Statement(Statement::IfStatement(inner)) if inner.else_body.is_some()));
+            }
+            other => panic!("expected if statement, found {:?}", other),
+        }
+
+        let source = "ate { usar(leer(ruta)?); } rev err { usar(err); }";
+        match &parse_v2(source)[0] {
+            AstNode::Statement(Statement::TryCatch(try_catch)) => {
+                assert_eq!(try_catch.binding, "err");
+                assert_eq!(try_catch.binding_span.start.offset, source.find("err").unwrap());
+                assert_eq!(try_catch.handler.len(), 1);
+                assert_eq!(try_catch.span.end.offset, source.len());
+            }
+            other => panic!("expected try/catch, found {:?}", other),
+        }
+
+        let match_expr = match &parse_v2("mth x { n si n > 0 => 1, _ => 0 }")[0] {
+            AstNode::Statement(Statement::Expression(Expression::Match(match_expr))) => match_expr.clone(),
+            other => panic!("expected match, found {:?}", other),
+        };
+        assert!(match_expr.arms[0].guard.is_some());
+    }
+
+    #[test]
+    fn test_error_propagation() {
+        let source = "ret leer(ruta)?.len();";
+        match &parse(source)[0] {
+            AstNode::Statement(Statement::ReturnStatement(ret)) => match &*ret.value {
+                AstNode::Expression(Expression::MethodCall(call)) => {
+                    assert!(matches!(&*call.receiver, AstNode::Expression(Expression::Propagate(propagate))
+                        if propagate.span.end.offset == source.find('.').unwrap()
+                            && matches!(*propagate.value, AstNode::Expression(Expression::FunctionCall(_)))));
+                }
+                other => panic!("expected method call, found {:?}", other),
+            },
+            other => panic!("expected return, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_declarations() {
+        let source = "djq muta x: i32 = f() + 1;";
+        match &parse(source)[0] {
+            AstNode::Statement(Statement::Declaration(decl)) => {
+                assert_eq!(decl.mutability, Mutability::Mutable);
+                assert!(matches!(&decl.pattern, Pattern::Identifier(name, _) if name == "x"));
+                assert!(matches!(&decl.ty, Some(TypeExpr::Primitive(name, _)) if name == "i32"));
+                assert!(matches!(&*decl.value, AstNode::Expression(Expression::BinaryOp(_))));
+                assert_eq!(decl.span.end.offset, source.len());
+            }
+            other => panic!("expected declaration, found {:?}", other),
+        }
+
+        match &parse("djq nmut (a, Punto { x, y }) = par();")[0] {
+            AstNode::Statement(Statement::Declaration(decl)) => {
+                assert_eq!(decl.mutability, Mutability::Immutable);
+                assert!(matches!(&decl.pattern, Pattern::Tuple(elements, _)
+                    if matches!(elements[1], Pattern::Struct(_))));
+                assert!(matches!(&*decl.value, AstNode::Expression(Expression::FunctionCall(_))));
+            }
+            other => panic!("expected declaration, found {:?}", other),
+        }
+
+        assert!(matches!(&parse("djq p = crear();")[0],
+            AstNode::Statement(Statement::Declaration(decl)) if decl.mutability == Mutability::Unspecified));
+    }
+
+    fn binary_parts(node: &AstNode) -> (&AstNode, &str, &AstNode) {
+        match node {
+            AstNode::Expression(Expression::BinaryOp(op)) => (&op.left, op.op.as_str(), &op.right),
+            other => panic!("expected binary operation, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        // a | b ^ c & d << 1 + 2 groups as a | (b ^ (c & (d << (1 + 2))))
+        let source = "ret a | b ^ c & d << 1 + 2;";
+        let value = match &parse(source)[0] {
+            AstNode::Statement(Statement::ReturnStatement(ret)) => ret.value.clone(),
+            other => panic!("expected return, found {:?}", other),
+        };
+        let mut ops = Vec::new();
+        let mut node = &*value;
+        while let AstNode::Expression(Expression::BinaryOp(_)) = node {
+            let (left, op, right) = binary_parts(node);
+            assert!(!matches!(left, AstNode::Expression(Expression::BinaryOp(_))));
+            ops.push(op.to_string());
+            node = right;
+        }
+        assert_eq!(ops, vec!["|", "^", "&", "<<", "+"]);
+        assert_eq!(value.span().start.offset, source.find('a').unwrap());
+
+        // `>>` is two `>` tokens joined back together
+        let value = match &parse("ret x >> 2 > y;")[0] {
+            AstNode::Statement(Statement::ReturnStatement(ret)) => ret.value.clone(),
+            other => panic!("expected return, found {:?}", other),
+        };
+        let (left, op, _) = binary_parts(&value);
+        assert_eq!(op, ">");
+        assert_eq!(binary_parts(left).1, ">>");
+
+        let value = match &parse("spa i in 0..n + 1 { }")[0] {
+            AstNode::Statement(Statement::ForStatement(stmt)) => stmt.iterable.clone(),
+            other => panic!("expected for loop, found {:?}", other),
+        };
+        let (_, op, right) = binary_parts(&value);
+        assert_eq!(op, "..");
+        assert_eq!(binary_parts(right).1, "+");
+    }
+
+    #[test]
+    fn test_assignments() {
+        let cases = [
+            ("x += 1;", "+="),
+            ("p.edad = 3;", "="),
+            ("v[i] *= 2;", "*="),
+            ("m[0].bits >>= 1;", ">>="),
+            ("flags |= 4;", "|="),
+        ];
+        for (source, expected_op) in cases {
+            match &parse(source)[0] {
+                AstNode::Statement(Statement::Assignment(assign)) => {
+                    assert_eq!(assign.op, expected_op, "{}", source);
+                    assert_eq!(assign.span.end.offset, source.len());
+                }
+                other => panic!("expected assignment for {}, found {:?}", source, other),
+            }
+        }
+
+        match &parse("v[i] *= 2;")[0] {
+            AstNode::Statement(Statement::Assignment(assign)) => {
+                assert!(matches!(&*assign.target, AstNode::Expression(Expression::ArrayAccess(_))));
+            }
+            other => panic!("expected assignment, found {:?}", other),
+        }
+
+        let (_, errors) = parse_recovering("f() = 1;");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_collection_and_struct_literals() {
+        match parse_expr("[1, 2, 3];") {
+            Expression::Array(elements, _) => assert_eq!(elements.len(), 3),
+            other => panic!("expected array, found {:?}", other),
+        }
+        match parse_expr("(a, b);") {
+            Expression::Tuple(elements, _) => assert_eq!(elements.len(), 2),
+            other => panic!("expected tuple, found {:?}", other),
+        }
+        assert!(matches!(parse_expr("(a,);"), Expression::Tuple(elements, _) if elements.len() == 1));
+        assert!(matches!(parse_expr("(a);"), Expression::Identifier(..)));
+        assert!(matches!(parse_expr("();"), Expression::Tuple(elements, _) if elements.is_empty()));
+
+        let source = "Persona { nombre: \"x\", edad };";
+        match parse_expr(source) {
+            Expression::StructLiteral(literal) => {
+                assert_eq!(literal.path.to_string(), "Persona");
+                assert_eq!(literal.fields.len(), 2);
+                assert!(matches!(&literal.fields[1].value, AstNode::Expression(Expression::Identifier(name, _)) if name == "edad"));
+                assert_eq!(literal.span.end.offset, source.len() - 1);
+            }
+            other => panic!("expected struct literal, found {:?}", other),
+        }
+        assert!(matches!(parse_expr("Forma:>Rect { ancho: 1.0, alto: 2.0 };"),
+            Expression::StructLiteral(literal) if literal.path.segments.len() == 2));
+
+        // A name before a block is not a struct literal...
+        assert!(matches!(&parse("wyo activo { usar(1); }")[0], AstNode::Statement(Statement::LoopStatement(_))));
+        assert!(matches!(&parse("spa x in xs { usar(x); }")[0], AstNode::Statement(Statement::ForStatement(_))));
+        assert!(matches!(&parse("mth x { _ => 0 }")[0], AstNode::Statement(Statement::Expression(Expression::Match(_)))));
+        // ...unless it is parenthesized or inside the block.
+        match &parse("wyo igual(p, P { x: 1 }) { djq q = P { x: 2 }; }")[0] {
+            AstNode::Statement(Statement::LoopStatement(stmt)) => {
+                assert!(matches!(&stmt.body[0], AstNode::Statement(Statement::Declaration(decl))
+                    if matches!(&*decl.value, AstNode::Expression(Expression::StructLiteral(_)))));
+            }
+            other => panic!("expected loop, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_enum_variant_payloads() {
+        let source = "enum Forma { Punto, Circulo(f64), Rect { ancho: f64, alto: f64 } }";
+        match &parse(source)[0] {
+            AstNode::Statement(Statement::Enum(enum_def)) => {
+                assert_eq!(enum_def.variants[0].payload, VariantPayload::Unit);
+                assert!(matches!(&enum_def.variants[1].payload, VariantPayload::Tuple(types) if types.len() == 1));
+                assert!(matches!(&enum_def.variants[2].payload, VariantPayload::Struct(fields) if fields.len() == 2));
+                let rect = &enum_def.variants[2].span;
+                assert_eq!(&source[rect.start.offset..rect.end.offset], "Rect { ancho: f64, alto: f64 }");
+            }
+            other => panic!("expected enum, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_attributes() {
+        let source = "#[obsoleto(\"usa otra\")] #[inline]\nfnc vieja() { }\n\
+                      #[derive(Debug)] forma Punto { #[ffi(nombre = \"px\", 4)] x: i32 }\n\
+                      #[prueba] enum Color { Rojo }\n\
+                      #[seguro] imp Punto { #[inline] fnc x(soy) -> i32 { ret soy.x; } }";
+        let nodes = parse(source);
+
+        match &nodes[0] {
+            AstNode::Statement(Statement::Function(func)) => {
+                assert_eq!(func.attributes.len(), 2);
+                assert_eq!(func.attributes[0].name, "obsoleto");
+                assert_eq!(func.attributes[0].message(), Some("usa otra"));
+                assert!(func.attributes[1].args.is_empty());
+                let span = func.attributes[0].span;
+                assert_eq!(&source[span.start.offset..span.end.offset], "#[obsoleto(\"usa otra\")]");
+                assert!(source[func.span.start.offset..].starts_with("fnc vieja"));
+            }
+            other => panic!("expected function, found {:?}", other),
+        }
+        match &nodes[1] {
+            AstNode::Statement(Statement::Struct(struct_def)) => {
+                assert!(matches!(&struct_def.attributes[0].args[0], AttributeArg::Identifier(name, _) if name == "Debug"));
+                let field = &struct_def.fields[0].attributes[0];
+                assert!(matches!(&field.args[0], AttributeArg::NameValue(name, Literal::Strng(value, _), _)
+                    if name == "nombre" && value == "px"));
+                assert!(matches!(&field.args[1], AttributeArg::Literal(Literal::Int(4, None, _))));
+            }
+            other => panic!("expected struct, found {:?}", other),
+        }
+        assert!(matches!(&nodes[2], AstNode::Statement(Statement::Enum(e)) if e.attributes[0].name == "prueba"));
+        match &nodes[3] {
+            AstNode::Statement(Statement::Implementation(imp)) => {
+                assert_eq!(imp.attributes[0].name, "seguro");
+                assert_eq!(imp.methods[0].attributes[0].name, "inline");
+            }
+            other => panic!("expected imp block, found {:?}", other),
+        }
+
+        let (_, errors) = parse_recovering("#[inline] djq x = 1;");
+        assert!(errors[0].message().contains("Attributes can only be applied"));
+    }
+
+    #[test]
+    fn test_builtin_calls() {
+        match &parse("prnt(\"hola {} y {1:>4} {{}}\", x, y);")[0] {
+            AstNode::Statement(Statement::Expression(Expression::BuiltinCall(call))) => {
+                assert_eq!(call.name, "prnt");
+                assert_eq!(call.args.len(), 3);
+            }
+            other => panic!("expected prnt call, found {:?}", other),
+        }
+        match parse_expr("verx!(\"estado {}\", x);") {
+            Expression::BuiltinCall(call) => assert!(call.takes_format()),
+            other => panic!("expected verx call, found {:?}", other),
+        }
+        // Other built-ins accept arbitrary arguments.
+        assert!(matches!(parse_expr("afirma!(x > 1);"), Expression::BuiltinCall(call) if call.name == "afirma"));
+
+        let (_, errors) = parse_recovering("prnt(\"{} {}\", x);");
+        assert_eq!(errors[0].message(), "Format string expects 2 argument(s), but 1 were given");
+        // Named placeholders refer to variables, not arguments.
+        assert!(matches!(parse_expr("prnt!(\"{nombre} {:?} {edad:>3}\", x);"), Expression::BuiltinCall(call) if call.args.len() == 2));
+        let (_, errors) = parse_recovering("prnt(\"{nombre}\", x);");
+        assert_eq!(errors[0].message(), "Format string expects 0 argument(s), but 1 were given");
+
+        let (_, errors) = parse_recovering("prnt(\"{x-1}\", x); prnt(\"a }\"); prnt(x);");
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].message().contains("Invalid placeholder '{x-1}'"));
+        assert!(errors[1].message().contains("Unmatched '}'"));
+        assert!(errors[2].message().contains("expects a string literal"));
+    }
+}
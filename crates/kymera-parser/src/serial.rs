@@ -0,0 +1,178 @@
+//! Versioned JSON and binary encodings of tokens and ASTs.
+//!
+//! Every [`Token`](crate::lexer::Token), [`TokenType`](crate::lexer::TokenType)
+//! and AST node implements `Serialize` and `Deserialize`. The functions here
+//! wrap a value in an envelope that records [`SCHEMA_VERSION`], so a reader
+//! can reject data written for a different layout instead of misreading it.
+//!
+//! # JSON schema
+//!
+//! [`to_json`] writes `{"version": 2, "data": ...}`. Within `data`:
+//!
+//! - structs are objects whose keys are the Rust field names;
+//! - enum variants are externally tagged: unit variants are strings
+//!   (`"Semicolon"`), other variants are single-key objects
+//!   (`{"Identifier": "x"}`, `{"IntLiteral": [42, null]}`);
+//! - a [`Span`](crate::position::Span) is
+//!   `{"start": {"line", "column", "offset"}, "end": {...}}`, with an
+//!   exclusive end;
+//! - `Option` is `null` or the value, `Box` is transparent, and byte strings
+//!   are arrays of numbers.
+//!
+//! Any change to the token or AST types that alters this layout bumps
+//! [`SCHEMA_VERSION`].
+//!
+//! # Binary encoding
+//!
+//! [`to_bytes`] writes the magic bytes `KYMA`, the schema version as a
+//! little-endian `u32`, and then the value in bincode. It is meant for
+//! caches, not for exchange: unlike the JSON, it cannot be read without the
+//! exact types that wrote it.
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+
+use crate::err::{ParserError, Result};
+
+/// The version of the token and AST layout written by this crate.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Marks the start of the binary encoding.
+const MAGIC: &[u8; 4] = b"KYMA";
+
+#[derive(Serialize)]
+struct Envelope<'a, T: ?Sized> {
+    version: u32,
+    data: &'a T,
+}
+
+/// An envelope read back from JSON. It is read twice: first with
+/// [`IgnoredAny`] data to check the version, so that a mismatch is reported
+/// as such rather than as a shape error, then with the real data type.
+/// Decoding straight from the text, rather than through `serde_json::Value`,
+/// keeps integers beyond the range of `u64` exact.
+#[derive(Deserialize)]
+struct DecodedEnvelope<T> {
+    version: u32,
+    data: T,
+}
+
+fn check_version(version: u32) -> Result<()> {
+    if version == SCHEMA_VERSION {
+        Ok(())
+    } else {
+        Err(ParserError::serialization(format!(
+            "Unsupported schema version {} (expected {})",
+            version, SCHEMA_VERSION
+        )))
+    }
+}
+
+/// Encodes a value as compact JSON.
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let envelope = Envelope {
+        version: SCHEMA_VERSION,
+        data: value,
+    };
+    serde_json::to_string(&envelope).map_err(|e| ParserError::serialization(e.to_string()))
+}
+
+/// Encodes a value as indented JSON, for reading by people.
+pub fn to_json_pretty<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let envelope = Envelope {
+        version: SCHEMA_VERSION,
+        data: value,
+    };
+    serde_json::to_string_pretty(&envelope).map_err(|e| ParserError::serialization(e.to_string()))
+}
+
+/// Decodes a value written by [`to_json`] or [`to_json_pretty`].
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T> {
+    let header: DecodedEnvelope<IgnoredAny> =
+        serde_json::from_str(json).map_err(|e| ParserError::serialization(e.to_string()))?;
+    check_version(header.version)?;
+    let envelope: DecodedEnvelope<T> =
+        serde_json::from_str(json).map_err(|e| ParserError::serialization(e.to_string()))?;
+    Ok(envelope.data)
+}
+
+/// Encodes a value in the binary format.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&SCHEMA_VERSION.to_le_bytes());
+    bincode::serialize_into(&mut bytes, value).map_err(|e| ParserError::serialization(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Decodes a value written by [`to_bytes`].
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    let header = bytes
+        .strip_prefix(MAGIC)
+        .filter(|rest| rest.len() >= 4)
+        .ok_or_else(|| ParserError::serialization("Not a Kymera binary encoding"))?;
+    let (version, data) = header.split_at(4);
+    check_version(u32::from_le_bytes([version[0], version[1], version[2], version[3]]))?;
+    bincode::deserialize(data).map_err(|e| ParserError::serialization(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::AstNode;
+    use crate::lexer::{Lexer, Token};
+    use crate::parser::Parser;
+
+    const SOURCE: &str = "#[inline] fnc f(a: i32) -> Optn<i32> { djq b = b\"\\x01\"; ret a + 0x10u8 * 2.5; }\n\
+                          enum E { A, B(i32), C { x: f64 } }\n\
+                          des core:>{io, math};";
+
+    #[test]
+    fn test_round_trips() {
+        let tokens = Lexer::new(SOURCE).tokenize().unwrap();
+        let nodes = Parser::new(tokens.clone()).parse().unwrap();
+
+        assert_eq!(from_json::<Vec<Token>>(&to_json(&tokens).unwrap()).unwrap(), tokens);
+        assert_eq!(from_json::<Vec<AstNode>>(&to_json(&nodes).unwrap()).unwrap(), nodes);
+        assert_eq!(from_json::<Vec<AstNode>>(&to_json_pretty(&nodes).unwrap()).unwrap(), nodes);
+        assert_eq!(from_bytes::<Vec<Token>>(&to_bytes(&tokens).unwrap()).unwrap(), tokens);
+        assert_eq!(from_bytes::<Vec<AstNode>>(&to_bytes(&nodes).unwrap()).unwrap(), nodes);
+    }
+
+    #[test]
+    fn test_round_trips_wide_integers() {
+        let source = "djq x = 100000000000000000000000i128; djq y = 170141183460469231731687303715884105727i128; djq z = 18446744073709551616i128;";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let nodes = Parser::new(tokens.clone()).parse().unwrap();
+
+        assert_eq!(from_json::<Vec<Token>>(&to_json(&tokens).unwrap()).unwrap(), tokens);
+        assert_eq!(from_json::<Vec<AstNode>>(&to_json(&nodes).unwrap()).unwrap(), nodes);
+        assert_eq!(from_bytes::<Vec<AstNode>>(&to_bytes(&nodes).unwrap()).unwrap(), nodes);
+    }
+
+    #[test]
+    fn test_json_layout() {
+        let tokens = Lexer::new("x 42;").tokenize().unwrap();
+        let json = to_json(&tokens[..2]).unwrap();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"version":2,"data":["#,
+                r#"{"token_type":{"Identifier":"x"},"lexeme":"x","span":{"start":{"line":1,"column":1,"offset":0},"end":{"line":1,"column":2,"offset":1}}},"#,
+                r#"{"token_type":{"IntLiteral":[42,null]},"lexeme":"42","span":{"start":{"line":1,"column":3,"offset":2},"end":{"line":1,"column":5,"offset":4}}}"#,
+                "]}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        let json = to_json(&vec![1u8]).unwrap().replace("\"version\":2", "\"version\":99");
+        let error = from_json::<Vec<u8>>(&json).unwrap_err();
+        assert_eq!(error.message(), "Unsupported schema version 99 (expected 2)");
+
+        let mut bytes = to_bytes(&vec![1u8]).unwrap();
+        bytes[4] = 99;
+        assert!(from_bytes::<Vec<u8>>(&bytes).is_err());
+        assert!(from_bytes::<Vec<u8>>(b"KYM").is_err());
+    }
+}
@@ -0,0 +1,546 @@
+//! Read-only traversal of the Kymera AST.
+//!
+//! [`Visitor`] has one method per kind of node, each defaulting to the
+//! matching `walk_*` function, which visits the node's children in source
+//! order. An implementation overrides the methods it cares about and calls
+//! the `walk_*` function from them to keep descending. Every span in the tree
+//! reaches [`Visitor::visit_span`].
+//!
+//! The walk functions match every AST variant exhaustively, so a new variant
+//! does not compile until it is walked here, in [`crate::visit_mut`] and in
+//! [`crate::fold`].
+
+use crate::ast::{
+    Attribute, AttributeArg, AstNode, EnumVariant, Expression, Function, GenericParam, InterfaceMethod, Literal,
+    MatchArm, Param, Path, Pattern, Statement, StructField, TypeExpr, UseTree, UseTreeKind, VariantPayload,
+};
+use crate::position::Span;
+
+/// Visits the nodes of an AST by reference.
+pub trait Visitor {
+    /// Visits any AST node.
+    fn visit_node(&mut self, node: &AstNode) {
+        walk_node(self, node);
+    }
+
+    /// Visits a statement.
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    /// Visits an expression.
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+
+    /// Visits the statements of a body, such as a function or loop body.
+    fn visit_block(&mut self, body: &[AstNode]) {
+        walk_block(self, body);
+    }
+
+    /// Visits a function or method definition.
+    fn visit_function(&mut self, function: &Function) {
+        walk_function(self, function);
+    }
+
+    /// Visits a method declared by an interface.
+    fn visit_interface_method(&mut self, method: &InterfaceMethod) {
+        walk_interface_method(self, method);
+    }
+
+    /// Visits a function parameter.
+    fn visit_param(&mut self, param: &Param) {
+        walk_param(self, param);
+    }
+
+    /// Visits a generic parameter.
+    fn visit_generic_param(&mut self, param: &GenericParam) {
+        walk_generic_param(self, param);
+    }
+
+    /// Visits an item attribute.
+    fn visit_attribute(&mut self, attribute: &Attribute) {
+        walk_attribute(self, attribute);
+    }
+
+    /// Visits a field of a struct or struct-style variant.
+    fn visit_struct_field(&mut self, field: &StructField) {
+        walk_struct_field(self, field);
+    }
+
+    /// Visits an enum variant.
+    fn visit_enum_variant(&mut self, variant: &EnumVariant) {
+        walk_enum_variant(self, variant);
+    }
+
+    /// Visits a match arm.
+    fn visit_match_arm(&mut self, arm: &MatchArm) {
+        walk_match_arm(self, arm);
+    }
+
+    /// Visits a pattern.
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern);
+    }
+
+    /// Visits a type annotation.
+    fn visit_type(&mut self, ty: &TypeExpr) {
+        walk_type(self, ty);
+    }
+
+    /// Visits a `:>`-separated path.
+    fn visit_path(&mut self, path: &Path) {
+        walk_path(self, path);
+    }
+
+    /// Visits the use tree of an import.
+    fn visit_use_tree(&mut self, tree: &UseTree) {
+        walk_use_tree(self, tree);
+    }
+
+    /// Visits a literal.
+    fn visit_literal(&mut self, literal: &Literal) {
+        walk_literal(self, literal);
+    }
+
+    /// Visits a span. Does nothing by default.
+    fn visit_span(&mut self, _span: &Span) {}
+}
+
+/// Walks the children of an AST node.
+pub fn walk_node<V: Visitor + ?Sized>(visitor: &mut V, node: &AstNode) {
+    match node {
+        AstNode::Expression(expression) => visitor.visit_expression(expression),
+        AstNode::Statement(statement) => visitor.visit_statement(statement),
+        AstNode::Error(span) => visitor.visit_span(span),
+    }
+}
+
+/// Walks the children of a statement.
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Declaration(decl) => {
+            visitor.visit_pattern(&decl.pattern);
+            if let Some(ty) = &decl.ty {
+                visitor.visit_type(ty);
+            }
+            visitor.visit_node(&decl.value);
+            visitor.visit_span(&decl.span);
+        }
+        Statement::Assignment(assign) => {
+            visitor.visit_node(&assign.target);
+            visitor.visit_node(&assign.value);
+            visitor.visit_span(&assign.span);
+        }
+        Statement::IfStatement(stmt) => {
+            visitor.visit_node(&stmt.condition);
+            visitor.visit_block(&stmt.body);
+            if let Some(else_body) = &stmt.else_body {
+                visitor.visit_block(else_body);
+            }
+            visitor.visit_span(&stmt.span);
+        }
+        Statement::LoopStatement(stmt) => {
+            visitor.visit_node(&stmt.condition);
+            visitor.visit_block(&stmt.body);
+            visitor.visit_span(&stmt.span);
+        }
+        Statement::ForStatement(stmt) => {
+            visitor.visit_pattern(&stmt.pattern);
+            visitor.visit_node(&stmt.iterable);
+            visitor.visit_block(&stmt.body);
+            visitor.visit_span(&stmt.span);
+        }
+        Statement::Break(control) | Statement::Continue(control) => visitor.visit_span(&control.span),
+        Statement::ReturnStatement(stmt) => {
+            visitor.visit_node(&stmt.value);
+            visitor.visit_span(&stmt.span);
+        }
+        Statement::Function(function) => visitor.visit_function(function),
+        Statement::Struct(struct_def) => {
+            for attribute in &struct_def.attributes {
+                visitor.visit_attribute(attribute);
+            }
+            for field in &struct_def.fields {
+                visitor.visit_struct_field(field);
+            }
+            visitor.visit_span(&struct_def.span);
+        }
+        Statement::Enum(enum_def) => {
+            for attribute in &enum_def.attributes {
+                visitor.visit_attribute(attribute);
+            }
+            for variant in &enum_def.variants {
+                visitor.visit_enum_variant(variant);
+            }
+            visitor.visit_span(&enum_def.span);
+        }
+        Statement::Interface(interface) => {
+            for generic in &interface.generics {
+                visitor.visit_generic_param(generic);
+            }
+            for super_interface in &interface.super_interfaces {
+                visitor.visit_type(super_interface);
+            }
+            for method in &interface.methods {
+                visitor.visit_interface_method(method);
+            }
+            visitor.visit_span(&interface.span);
+        }
+        Statement::Implementation(imp) => {
+            for attribute in &imp.attributes {
+                visitor.visit_attribute(attribute);
+            }
+            if let Some(interface) = &imp.interface {
+                visitor.visit_type(interface);
+            }
+            visitor.visit_type(&imp.target);
+            for method in &imp.methods {
+                visitor.visit_function(method);
+            }
+            visitor.visit_span(&imp.span);
+        }
+        Statement::Import(import) => {
+            visitor.visit_use_tree(&import.tree);
+            visitor.visit_span(&import.span);
+        }
+        Statement::Block(body, span) => {
+            visitor.visit_block(body);
+            visitor.visit_span(span);
+        }
+        Statement::SyncBlock(block) => {
+            visitor.visit_block(&block.body);
+            visitor.visit_span(&block.span);
+        }
+        Statement::TryCatch(try_catch) => {
+            visitor.visit_block(&try_catch.body);
+            visitor.visit_span(&try_catch.binding_span);
+            visitor.visit_block(&try_catch.handler);
+            visitor.visit_span(&try_catch.span);
+        }
+        Statement::Expression(expression) => visitor.visit_expression(expression),
+    }
+}
+
+/// Walks the children of an expression.
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Literal(literal) => visitor.visit_literal(literal),
+        Expression::BinaryOp(op) => {
+            visitor.visit_node(&op.left);
+            visitor.visit_node(&op.right);
+            visitor.visit_span(&op.span);
+        }
+        Expression::UnaryOp(op) => {
+            visitor.visit_node(&op.operand);
+            visitor.visit_span(&op.span);
+        }
+        Expression::Identifier(_, span) | Expression::SelfRef(span) => visitor.visit_span(span),
+        Expression::Path(path) => visitor.visit_path(path),
+        Expression::Array(elements, span) | Expression::Tuple(elements, span) => {
+            for element in elements {
+                visitor.visit_node(element);
+            }
+            visitor.visit_span(span);
+        }
+        Expression::StructLiteral(literal) => {
+            visitor.visit_path(&literal.path);
+            for field in &literal.fields {
+                visitor.visit_node(&field.value);
+                visitor.visit_span(&field.span);
+            }
+            visitor.visit_span(&literal.span);
+        }
+        Expression::FunctionCall(call) => {
+            visitor.visit_node(&call.callee);
+            for arg in &call.args {
+                visitor.visit_node(arg);
+            }
+            visitor.visit_span(&call.span);
+        }
+        Expression::BuiltinCall(call) => {
+            for arg in &call.args {
+                visitor.visit_node(arg);
+            }
+            visitor.visit_span(&call.span);
+        }
+        Expression::MethodCall(call) => {
+            visitor.visit_node(&call.receiver);
+            for arg in &call.args {
+                visitor.visit_node(arg);
+            }
+            visitor.visit_span(&call.span);
+        }
+        Expression::Await(await_expr) => {
+            visitor.visit_node(&await_expr.value);
+            visitor.visit_span(&await_expr.span);
+        }
+        Expression::Propagate(propagate) => {
+            visitor.visit_node(&propagate.value);
+            visitor.visit_span(&propagate.span);
+        }
+        Expression::Match(match_expr) => {
+            visitor.visit_node(&match_expr.scrutinee);
+            for arm in &match_expr.arms {
+                visitor.visit_match_arm(arm);
+            }
+            visitor.visit_span(&match_expr.span);
+        }
+        Expression::FieldAccess(access) => {
+            visitor.visit_node(&access.object);
+            visitor.visit_span(&access.span);
+        }
+        Expression::ArrayAccess(access) => {
+            visitor.visit_node(&access.array);
+            visitor.visit_node(&access.index);
+            visitor.visit_span(&access.span);
+        }
+    }
+}
+
+/// Walks the statements of a body.
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, body: &[AstNode]) {
+    for node in body {
+        visitor.visit_node(node);
+    }
+}
+
+/// Walks the children of a function definition.
+pub fn walk_function<V: Visitor + ?Sized>(visitor: &mut V, function: &Function) {
+    for attribute in &function.attributes {
+        visitor.visit_attribute(attribute);
+    }
+    for generic in &function.generics {
+        visitor.visit_generic_param(generic);
+    }
+    if let Some(receiver) = &function.receiver {
+        visitor.visit_span(&receiver.span);
+    }
+    for param in &function.params {
+        visitor.visit_param(param);
+    }
+    if let Some(return_type) = &function.return_type {
+        visitor.visit_type(return_type);
+    }
+    visitor.visit_block(&function.body);
+    visitor.visit_span(&function.span);
+}
+
+/// Walks the children of an interface method.
+pub fn walk_interface_method<V: Visitor + ?Sized>(visitor: &mut V, method: &InterfaceMethod) {
+    for generic in &method.generics {
+        visitor.visit_generic_param(generic);
+    }
+    if let Some(receiver) = &method.receiver {
+        visitor.visit_span(&receiver.span);
+    }
+    for param in &method.params {
+        visitor.visit_param(param);
+    }
+    if let Some(return_type) = &method.return_type {
+        visitor.visit_type(return_type);
+    }
+    if let Some(body) = &method.body {
+        visitor.visit_block(body);
+    }
+    visitor.visit_span(&method.span);
+}
+
+/// Walks the children of a function parameter.
+pub fn walk_param<V: Visitor + ?Sized>(visitor: &mut V, param: &Param) {
+    if let Some(ty) = &param.ty {
+        visitor.visit_type(ty);
+    }
+    visitor.visit_span(&param.span);
+}
+
+/// Walks the children of a generic parameter.
+pub fn walk_generic_param<V: Visitor + ?Sized>(visitor: &mut V, param: &GenericParam) {
+    for bound in &param.bounds {
+        visitor.visit_type(bound);
+    }
+    visitor.visit_span(&param.span);
+}
+
+/// Walks the children of an attribute.
+pub fn walk_attribute<V: Visitor + ?Sized>(visitor: &mut V, attribute: &Attribute) {
+    for arg in &attribute.args {
+        match arg {
+            AttributeArg::Literal(literal) => visitor.visit_literal(literal),
+            AttributeArg::Identifier(_, span) => visitor.visit_span(span),
+            AttributeArg::NameValue(_, literal, span) => {
+                visitor.visit_literal(literal);
+                visitor.visit_span(span);
+            }
+        }
+    }
+    visitor.visit_span(&attribute.span);
+}
+
+/// Walks the children of a struct field.
+pub fn walk_struct_field<V: Visitor + ?Sized>(visitor: &mut V, field: &StructField) {
+    for attribute in &field.attributes {
+        visitor.visit_attribute(attribute);
+    }
+    visitor.visit_type(&field.ty);
+    visitor.visit_span(&field.span);
+}
+
+/// Walks the children of an enum variant.
+pub fn walk_enum_variant<V: Visitor + ?Sized>(visitor: &mut V, variant: &EnumVariant) {
+    match &variant.payload {
+        VariantPayload::Unit => {}
+        VariantPayload::Tuple(types) => {
+            for ty in types {
+                visitor.visit_type(ty);
+            }
+        }
+        VariantPayload::Struct(fields) => {
+            for field in fields {
+                visitor.visit_struct_field(field);
+            }
+        }
+    }
+    visitor.visit_span(&variant.span);
+}
+
+/// Walks the children of a match arm.
+pub fn walk_match_arm<V: Visitor + ?Sized>(visitor: &mut V, arm: &MatchArm) {
+    visitor.visit_pattern(&arm.pattern);
+    if let Some(guard) = &arm.guard {
+        visitor.visit_node(guard);
+    }
+    visitor.visit_node(&arm.body);
+    visitor.visit_span(&arm.span);
+}
+
+/// Walks the children of a pattern.
+pub fn walk_pattern<V: Visitor + ?Sized>(visitor: &mut V, pattern: &Pattern) {
+    match pattern {
+        Pattern::Wildcard(span) | Pattern::Identifier(_, span) => visitor.visit_span(span),
+        Pattern::Literal(literal) => visitor.visit_literal(literal),
+        Pattern::Variant(variant) => {
+            visitor.visit_path(&variant.path);
+            for pattern in variant.payload.iter().flatten() {
+                visitor.visit_pattern(pattern);
+            }
+            visitor.visit_span(&variant.span);
+        }
+        Pattern::Struct(struct_pattern) => {
+            visitor.visit_path(&struct_pattern.path);
+            for field in &struct_pattern.fields {
+                visitor.visit_pattern(&field.pattern);
+                visitor.visit_span(&field.span);
+            }
+            visitor.visit_span(&struct_pattern.span);
+        }
+        Pattern::Tuple(patterns, span) => {
+            for pattern in patterns {
+                visitor.visit_pattern(pattern);
+            }
+            visitor.visit_span(span);
+        }
+    }
+}
+
+/// Walks the children of a type annotation.
+pub fn walk_type<V: Visitor + ?Sized>(visitor: &mut V, ty: &TypeExpr) {
+    match ty {
+        TypeExpr::Primitive(_, span) => visitor.visit_span(span),
+        TypeExpr::Named(named) => {
+            visitor.visit_path(&named.path);
+            for arg in &named.args {
+                visitor.visit_type(arg);
+            }
+            visitor.visit_span(&named.span);
+        }
+        TypeExpr::Slice(element, span) | TypeExpr::Array(element, _, span) => {
+            visitor.visit_type(element);
+            visitor.visit_span(span);
+        }
+        TypeExpr::Tuple(elements, span) => {
+            for element in elements {
+                visitor.visit_type(element);
+            }
+            visitor.visit_span(span);
+        }
+        TypeExpr::Function(function) => {
+            for param in &function.params {
+                visitor.visit_type(param);
+            }
+            if let Some(return_type) = &function.return_type {
+                visitor.visit_type(return_type);
+            }
+            visitor.visit_span(&function.span);
+        }
+    }
+}
+
+/// Walks the children of a path.
+pub fn walk_path<V: Visitor + ?Sized>(visitor: &mut V, path: &Path) {
+    visitor.visit_span(&path.span);
+}
+
+/// Walks the children of a use tree.
+pub fn walk_use_tree<V: Visitor + ?Sized>(visitor: &mut V, tree: &UseTree) {
+    visitor.visit_path(&tree.prefix);
+    if let UseTreeKind::Nested(trees) = &tree.kind {
+        for tree in trees {
+            visitor.visit_use_tree(tree);
+        }
+    }
+    visitor.visit_span(&tree.span);
+}
+
+/// Walks the children of a literal.
+pub fn walk_literal<V: Visitor + ?Sized>(visitor: &mut V, literal: &Literal) {
+    match literal {
+        Literal::Int(_, _, span)
+        | Literal::Float(_, _, span)
+        | Literal::Bool(_, span)
+        | Literal::Strng(_, span)
+        | Literal::Stilo(_, span)
+        | Literal::Bytes(_, span)
+        | Literal::Nil(span) => visitor.visit_span(span),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Counts identifiers and collects every span.
+    #[derive(Default)]
+    struct Collector {
+        identifiers: Vec<String>,
+        spans: usize,
+    }
+
+    impl Visitor for Collector {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::Identifier(name, _) = expression {
+                self.identifiers.push(name.clone());
+            }
+            walk_expression(self, expression);
+        }
+
+        fn visit_span(&mut self, _span: &Span) {
+            self.spans += 1;
+        }
+    }
+
+    #[test]
+    fn test_visitor_reaches_nested_expressions() {
+        let source = "fnc f(a: i32) -> i32 { djq b = [a, c.d]; mth b { x ate x > e => usar(x), _ => f }; ret b; }";
+        let nodes = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+
+        let mut collector = Collector::default();
+        for node in &nodes {
+            collector.visit_node(node);
+        }
+        assert_eq!(collector.identifiers, ["a", "c", "b", "x", "e", "usar", "x", "f", "b"]);
+        assert!(collector.spans > collector.identifiers.len());
+    }
+}
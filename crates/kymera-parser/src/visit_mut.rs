@@ -0,0 +1,576 @@
+//! In-place traversal of the Kymera AST.
+//!
+//! [`VisitorMut`] mirrors [`crate::visit::Visitor`] with mutable references:
+//! each `visit_*_mut` method defaults to the matching `walk_*_mut` function,
+//! and every span in the tree reaches [`VisitorMut::visit_span_mut`], which
+//! makes rewriting positions a one-method implementation.
+
+use crate::ast::{
+    Attribute, AttributeArg, AstNode, EnumVariant, Expression, Function, GenericParam, InterfaceMethod, Literal,
+    MatchArm, Param, Path, Pattern, Statement, StructField, TypeExpr, UseTree, UseTreeKind, VariantPayload,
+};
+use crate::position::Span;
+
+/// Visits the nodes of an AST by mutable reference.
+pub trait VisitorMut {
+    /// Visits any AST node.
+    fn visit_node_mut(&mut self, node: &mut AstNode) {
+        walk_node_mut(self, node);
+    }
+
+    /// Visits a statement.
+    fn visit_statement_mut(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+    }
+
+    /// Visits an expression.
+    fn visit_expression_mut(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+    }
+
+    /// Visits the statements of a body, such as a function or loop body.
+    fn visit_block_mut(&mut self, body: &mut [AstNode]) {
+        walk_block_mut(self, body);
+    }
+
+    /// Visits a function or method definition.
+    fn visit_function_mut(&mut self, function: &mut Function) {
+        walk_function_mut(self, function);
+    }
+
+    /// Visits a method declared by an interface.
+    fn visit_interface_method_mut(&mut self, method: &mut InterfaceMethod) {
+        walk_interface_method_mut(self, method);
+    }
+
+    /// Visits a function parameter.
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        walk_param_mut(self, param);
+    }
+
+    /// Visits a generic parameter.
+    fn visit_generic_param_mut(&mut self, param: &mut GenericParam) {
+        walk_generic_param_mut(self, param);
+    }
+
+    /// Visits an item attribute.
+    fn visit_attribute_mut(&mut self, attribute: &mut Attribute) {
+        walk_attribute_mut(self, attribute);
+    }
+
+    /// Visits a field of a struct or struct-style variant.
+    fn visit_struct_field_mut(&mut self, field: &mut StructField) {
+        walk_struct_field_mut(self, field);
+    }
+
+    /// Visits an enum variant.
+    fn visit_enum_variant_mut(&mut self, variant: &mut EnumVariant) {
+        walk_enum_variant_mut(self, variant);
+    }
+
+    /// Visits a match arm.
+    fn visit_match_arm_mut(&mut self, arm: &mut MatchArm) {
+        walk_match_arm_mut(self, arm);
+    }
+
+    /// Visits a pattern.
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        walk_pattern_mut(self, pattern);
+    }
+
+    /// Visits a type annotation.
+    fn visit_type_mut(&mut self, ty: &mut TypeExpr) {
+        walk_type_mut(self, ty);
+    }
+
+    /// Visits a `:>`-separated path.
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        walk_path_mut(self, path);
+    }
+
+    /// Visits the use tree of an import.
+    fn visit_use_tree_mut(&mut self, tree: &mut UseTree) {
+        walk_use_tree_mut(self, tree);
+    }
+
+    /// Visits a literal.
+    fn visit_literal_mut(&mut self, literal: &mut Literal) {
+        walk_literal_mut(self, literal);
+    }
+
+    /// Visits a span. Does nothing by default.
+    fn visit_span_mut(&mut self, _span: &mut Span) {}
+}
+
+/// Walks the children of an AST node.
+pub fn walk_node_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut AstNode) {
+    match node {
+        AstNode::Expression(expression) => visitor.visit_expression_mut(expression),
+        AstNode::Statement(statement) => visitor.visit_statement_mut(statement),
+        AstNode::Error(span) => visitor.visit_span_mut(span),
+    }
+}
+
+/// Walks the children of a statement.
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match statement {
+        Statement::Declaration(decl) => {
+            visitor.visit_pattern_mut(&mut decl.pattern);
+            if let Some(ty) = &mut decl.ty {
+                visitor.visit_type_mut(ty);
+            }
+            visitor.visit_node_mut(&mut decl.value);
+            visitor.visit_span_mut(&mut decl.span);
+        }
+        Statement::Assignment(assign) => {
+            visitor.visit_node_mut(&mut assign.target);
+            visitor.visit_node_mut(&mut assign.value);
+            visitor.visit_span_mut(&mut assign.span);
+        }
+        Statement::IfStatement(stmt) => {
+            visitor.visit_node_mut(&mut stmt.condition);
+            visitor.visit_block_mut(&mut stmt.body);
+            if let Some(else_body) = &mut stmt.else_body {
+                visitor.visit_block_mut(else_body);
+            }
+            visitor.visit_span_mut(&mut stmt.span);
+        }
+        Statement::LoopStatement(stmt) => {
+            visitor.visit_node_mut(&mut stmt.condition);
+            visitor.visit_block_mut(&mut stmt.body);
+            visitor.visit_span_mut(&mut stmt.span);
+        }
+        Statement::ForStatement(stmt) => {
+            visitor.visit_pattern_mut(&mut stmt.pattern);
+            visitor.visit_node_mut(&mut stmt.iterable);
+            visitor.visit_block_mut(&mut stmt.body);
+            visitor.visit_span_mut(&mut stmt.span);
+        }
+        Statement::Break(control) | Statement::Continue(control) => visitor.visit_span_mut(&mut control.span),
+        Statement::ReturnStatement(stmt) => {
+            visitor.visit_node_mut(&mut stmt.value);
+            visitor.visit_span_mut(&mut stmt.span);
+        }
+        Statement::Function(function) => visitor.visit_function_mut(function),
+        Statement::Struct(struct_def) => {
+            for attribute in &mut struct_def.attributes {
+                visitor.visit_attribute_mut(attribute);
+            }
+            for field in &mut struct_def.fields {
+                visitor.visit_struct_field_mut(field);
+            }
+            visitor.visit_span_mut(&mut struct_def.span);
+        }
+        Statement::Enum(enum_def) => {
+            for attribute in &mut enum_def.attributes {
+                visitor.visit_attribute_mut(attribute);
+            }
+            for variant in &mut enum_def.variants {
+                visitor.visit_enum_variant_mut(variant);
+            }
+            visitor.visit_span_mut(&mut enum_def.span);
+        }
+        Statement::Interface(interface) => {
+            for generic in &mut interface.generics {
+                visitor.visit_generic_param_mut(generic);
+            }
+            for super_interface in &mut interface.super_interfaces {
+                visitor.visit_type_mut(super_interface);
+            }
+            for method in &mut interface.methods {
+                visitor.visit_interface_method_mut(method);
+            }
+            visitor.visit_span_mut(&mut interface.span);
+        }
+        Statement::Implementation(imp) => {
+            for attribute in &mut imp.attributes {
+                visitor.visit_attribute_mut(attribute);
+            }
+            if let Some(interface) = &mut imp.interface {
+                visitor.visit_type_mut(interface);
+            }
+            visitor.visit_type_mut(&mut imp.target);
+            for method in &mut imp.methods {
+                visitor.visit_function_mut(method);
+            }
+            visitor.visit_span_mut(&mut imp.span);
+        }
+        Statement::Import(import) => {
+            visitor.visit_use_tree_mut(&mut import.tree);
+            visitor.visit_span_mut(&mut import.span);
+        }
+        Statement::Block(body, span) => {
+            visitor.visit_block_mut(body);
+            visitor.visit_span_mut(span);
+        }
+        Statement::SyncBlock(block) => {
+            visitor.visit_block_mut(&mut block.body);
+            visitor.visit_span_mut(&mut block.span);
+        }
+        Statement::TryCatch(try_catch) => {
+            visitor.visit_block_mut(&mut try_catch.body);
+            visitor.visit_span_mut(&mut try_catch.binding_span);
+            visitor.visit_block_mut(&mut try_catch.handler);
+            visitor.visit_span_mut(&mut try_catch.span);
+        }
+        Statement::Expression(expression) => visitor.visit_expression_mut(expression),
+    }
+}
+
+/// Walks the children of an expression.
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match expression {
+        Expression::Literal(literal) => visitor.visit_literal_mut(literal),
+        Expression::BinaryOp(op) => {
+            visitor.visit_node_mut(&mut op.left);
+            visitor.visit_node_mut(&mut op.right);
+            visitor.visit_span_mut(&mut op.span);
+        }
+        Expression::UnaryOp(op) => {
+            visitor.visit_node_mut(&mut op.operand);
+            visitor.visit_span_mut(&mut op.span);
+        }
+        Expression::Identifier(_, span) | Expression::SelfRef(span) => visitor.visit_span_mut(span),
+        Expression::Path(path) => visitor.visit_path_mut(path),
+        Expression::Array(elements, span) | Expression::Tuple(elements, span) => {
+            for element in elements {
+                visitor.visit_node_mut(element);
+            }
+            visitor.visit_span_mut(span);
+        }
+        Expression::StructLiteral(literal) => {
+            visitor.visit_path_mut(&mut literal.path);
+            for field in &mut literal.fields {
+                visitor.visit_node_mut(&mut field.value);
+                visitor.visit_span_mut(&mut field.span);
+            }
+            visitor.visit_span_mut(&mut literal.span);
+        }
+        Expression::FunctionCall(call) => {
+            visitor.visit_node_mut(&mut call.callee);
+            for arg in &mut call.args {
+                visitor.visit_node_mut(arg);
+            }
+            visitor.visit_span_mut(&mut call.span);
+        }
+        Expression::BuiltinCall(call) => {
+            for arg in &mut call.args {
+                visitor.visit_node_mut(arg);
+            }
+            visitor.visit_span_mut(&mut call.span);
+        }
+        Expression::MethodCall(call) => {
+            visitor.visit_node_mut(&mut call.receiver);
+            for arg in &mut call.args {
+                visitor.visit_node_mut(arg);
+            }
+            visitor.visit_span_mut(&mut call.span);
+        }
+        Expression::Await(await_expr) => {
+            visitor.visit_node_mut(&mut await_expr.value);
+            visitor.visit_span_mut(&mut await_expr.span);
+        }
+        Expression::Propagate(propagate) => {
+            visitor.visit_node_mut(&mut propagate.value);
+            visitor.visit_span_mut(&mut propagate.span);
+        }
+        Expression::Match(match_expr) => {
+            visitor.visit_node_mut(&mut match_expr.scrutinee);
+            for arm in &mut match_expr.arms {
+                visitor.visit_match_arm_mut(arm);
+            }
+            visitor.visit_span_mut(&mut match_expr.span);
+        }
+        Expression::FieldAccess(access) => {
+            visitor.visit_node_mut(&mut access.object);
+            visitor.visit_span_mut(&mut access.span);
+        }
+        Expression::ArrayAccess(access) => {
+            visitor.visit_node_mut(&mut access.array);
+            visitor.visit_node_mut(&mut access.index);
+            visitor.visit_span_mut(&mut access.span);
+        }
+    }
+}
+
+/// Walks the statements of a body.
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, body: &mut [AstNode]) {
+    for node in body {
+        visitor.visit_node_mut(node);
+    }
+}
+
+/// Walks the children of a function definition.
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(visitor: &mut V, function: &mut Function) {
+    for attribute in &mut function.attributes {
+        visitor.visit_attribute_mut(attribute);
+    }
+    for generic in &mut function.generics {
+        visitor.visit_generic_param_mut(generic);
+    }
+    if let Some(receiver) = &mut function.receiver {
+        visitor.visit_span_mut(&mut receiver.span);
+    }
+    for param in &mut function.params {
+        visitor.visit_param_mut(param);
+    }
+    if let Some(return_type) = &mut function.return_type {
+        visitor.visit_type_mut(return_type);
+    }
+    visitor.visit_block_mut(&mut function.body);
+    visitor.visit_span_mut(&mut function.span);
+}
+
+/// Walks the children of an interface method.
+pub fn walk_interface_method_mut<V: VisitorMut + ?Sized>(visitor: &mut V, method: &mut InterfaceMethod) {
+    for generic in &mut method.generics {
+        visitor.visit_generic_param_mut(generic);
+    }
+    if let Some(receiver) = &mut method.receiver {
+        visitor.visit_span_mut(&mut receiver.span);
+    }
+    for param in &mut method.params {
+        visitor.visit_param_mut(param);
+    }
+    if let Some(return_type) = &mut method.return_type {
+        visitor.visit_type_mut(return_type);
+    }
+    if let Some(body) = &mut method.body {
+        visitor.visit_block_mut(body);
+    }
+    visitor.visit_span_mut(&mut method.span);
+}
+
+/// Walks the children of a function parameter.
+pub fn walk_param_mut<V: VisitorMut + ?Sized>(visitor: &mut V, param: &mut Param) {
+    if let Some(ty) = &mut param.ty {
+        visitor.visit_type_mut(ty);
+    }
+    visitor.visit_span_mut(&mut param.span);
+}
+
+/// Walks the children of a generic parameter.
+pub fn walk_generic_param_mut<V: VisitorMut + ?Sized>(visitor: &mut V, param: &mut GenericParam) {
+    for bound in &mut param.bounds {
+        visitor.visit_type_mut(bound);
+    }
+    visitor.visit_span_mut(&mut param.span);
+}
+
+/// Walks the children of an attribute.
+pub fn walk_attribute_mut<V: VisitorMut + ?Sized>(visitor: &mut V, attribute: &mut Attribute) {
+    for arg in &mut attribute.args {
+        match arg {
+            AttributeArg::Literal(literal) => visitor.visit_literal_mut(literal),
+            AttributeArg::Identifier(_, span) => visitor.visit_span_mut(span),
+            AttributeArg::NameValue(_, literal, span) => {
+                visitor.visit_literal_mut(literal);
+                visitor.visit_span_mut(span);
+            }
+        }
+    }
+    visitor.visit_span_mut(&mut attribute.span);
+}
+
+/// Walks the children of a struct field.
+pub fn walk_struct_field_mut<V: VisitorMut + ?Sized>(visitor: &mut V, field: &mut StructField) {
+    for attribute in &mut field.attributes {
+        visitor.visit_attribute_mut(attribute);
+    }
+    visitor.visit_type_mut(&mut field.ty);
+    visitor.visit_span_mut(&mut field.span);
+}
+
+/// Walks the children of an enum variant.
+pub fn walk_enum_variant_mut<V: VisitorMut + ?Sized>(visitor: &mut V, variant: &mut EnumVariant) {
+    match &mut variant.payload {
+        VariantPayload::Unit => {}
+        VariantPayload::Tuple(types) => {
+            for ty in types {
+                visitor.visit_type_mut(ty);
+            }
+        }
+        VariantPayload::Struct(fields) => {
+            for field in fields {
+                visitor.visit_struct_field_mut(field);
+            }
+        }
+    }
+    visitor.visit_span_mut(&mut variant.span);
+}
+
+/// Walks the children of a match arm.
+pub fn walk_match_arm_mut<V: VisitorMut + ?Sized>(visitor: &mut V, arm: &mut MatchArm) {
+    visitor.visit_pattern_mut(&mut arm.pattern);
+    if let Some(guard) = &mut arm.guard {
+        visitor.visit_node_mut(guard);
+    }
+    visitor.visit_node_mut(&mut arm.body);
+    visitor.visit_span_mut(&mut arm.span);
+}
+
+/// Walks the children of a pattern.
+pub fn walk_pattern_mut<V: VisitorMut + ?Sized>(visitor: &mut V, pattern: &mut Pattern) {
+    match pattern {
+        Pattern::Wildcard(span) | Pattern::Identifier(_, span) => visitor.visit_span_mut(span),
+        Pattern::Literal(literal) => visitor.visit_literal_mut(literal),
+        Pattern::Variant(variant) => {
+            visitor.visit_path_mut(&mut variant.path);
+            for pattern in variant.payload.iter_mut().flatten() {
+                visitor.visit_pattern_mut(pattern);
+            }
+            visitor.visit_span_mut(&mut variant.span);
+        }
+        Pattern::Struct(struct_pattern) => {
+            visitor.visit_path_mut(&mut struct_pattern.path);
+            for field in &mut struct_pattern.fields {
+                visitor.visit_pattern_mut(&mut field.pattern);
+                visitor.visit_span_mut(&mut field.span);
+            }
+            visitor.visit_span_mut(&mut struct_pattern.span);
+        }
+        Pattern::Tuple(patterns, span) => {
+            for pattern in patterns {
+                visitor.visit_pattern_mut(pattern);
+            }
+            visitor.visit_span_mut(span);
+        }
+    }
+}
+
+/// Walks the children of a type annotation.
+pub fn walk_type_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ty: &mut TypeExpr) {
+    match ty {
+        TypeExpr::Primitive(_, span) => visitor.visit_span_mut(span),
+        TypeExpr::Named(named) => {
+            visitor.visit_path_mut(&mut named.path);
+            for arg in &mut named.args {
+                visitor.visit_type_mut(arg);
+            }
+            visitor.visit_span_mut(&mut named.span);
+        }
+        TypeExpr::Slice(element, span) | TypeExpr::Array(element, _, span) => {
+            visitor.visit_type_mut(element);
+            visitor.visit_span_mut(span);
+        }
+        TypeExpr::Tuple(elements, span) => {
+            for element in elements {
+                visitor.visit_type_mut(element);
+            }
+            visitor.visit_span_mut(span);
+        }
+        TypeExpr::Function(function) => {
+            for param in &mut function.params {
+                visitor.visit_type_mut(param);
+            }
+            if let Some(return_type) = &mut function.return_type {
+                visitor.visit_type_mut(return_type);
+            }
+            visitor.visit_span_mut(&mut function.span);
+        }
+    }
+}
+
+/// Walks the children of a path.
+pub fn walk_path_mut<V: VisitorMut + ?Sized>(visitor: &mut V, path: &mut Path) {
+    visitor.visit_span_mut(&mut path.span);
+}
+
+/// Walks the children of a use tree.
+pub fn walk_use_tree_mut<V: VisitorMut + ?Sized>(visitor: &mut V, tree: &mut UseTree) {
+    visitor.visit_path_mut(&mut tree.prefix);
+    if let UseTreeKind::Nested(trees) = &mut tree.kind {
+        for tree in trees {
+            visitor.visit_use_tree_mut(tree);
+        }
+    }
+    visitor.visit_span_mut(&mut tree.span);
+}
+
+/// Walks the children of a literal.
+pub fn walk_literal_mut<V: VisitorMut + ?Sized>(visitor: &mut V, literal: &mut Literal) {
+    match literal {
+        Literal::Int(_, _, span)
+        | Literal::Float(_, _, span)
+        | Literal::Bool(_, span)
+        | Literal::Strng(_, span)
+        | Literal::Stilo(_, span)
+        | Literal::Bytes(_, span)
+        | Literal::Nil(span) => visitor.visit_span_mut(span),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Renames one variable, increments integer literals and moves every
+    /// span right by a fixed number of columns.
+    struct Rewrite<'a> {
+        from: &'a str,
+        to: &'a str,
+        shift: usize,
+    }
+
+    impl VisitorMut for Rewrite<'_> {
+        fn visit_expression_mut(&mut self, expression: &mut Expression) {
+            if let Expression::Identifier(name, _) = expression {
+                if name == self.from {
+                    *name = self.to.to_string();
+                }
+            }
+            walk_expression_mut(self, expression);
+        }
+
+        fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+            if let Pattern::Identifier(name, _) = pattern {
+                if name == self.from {
+                    *name = self.to.to_string();
+                }
+            }
+            walk_pattern_mut(self, pattern);
+        }
+
+        fn visit_literal_mut(&mut self, literal: &mut Literal) {
+            if let Literal::Int(value, _, _) = literal {
+                *value += 1;
+            }
+            walk_literal_mut(self, literal);
+        }
+
+        fn visit_span_mut(&mut self, span: &mut Span) {
+            for position in [&mut span.start, &mut span.end] {
+                position.column += self.shift;
+                position.offset += self.shift;
+            }
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut_rewrites_nested_nodes_and_spans() {
+        let parse = |source: &str| Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        // Kept on one line so that shifting columns matches indenting the source.
+        let source = "#[inline] fnc f<T>(a: i32, xs: Vec<T>) -> Optn<i32> { \
+                      djq muta v = [a, 1]; v = v + 2; \
+                      spa (p, q) in xs { ate p > 3 { break; } } \
+                      mth v { 4 => usar(v), Punto { x, .. } => x, _ => 5 }; \
+                      ret Punto { x: v.y, y: 6 }; } \
+                      forma Punto { x: i32 } enum E { A(i32), B { x: i32 } } des core:>{io, math};";
+        let expected = "    #[inline] fnc f<T>(a: i32, xs: Vec<T>) -> Optn<i32> { \
+                        djq muta w = [a, 2]; w = w + 3; \
+                        spa (p, q) in xs { ate p > 4 { break; } } \
+                        mth w { 5 => usar(w), Punto { x, .. } => x, _ => 6 }; \
+                        ret Punto { x: w.y, y: 7 }; } \
+                        forma Punto { x: i32 } enum E { A(i32), B { x: i32 } } des core:>{io, math};";
+
+        let mut nodes = parse(source);
+        let mut rewrite = Rewrite { from: "v", to: "w", shift: 4 };
+        for node in &mut nodes {
+            rewrite.visit_node_mut(node);
+        }
+        assert_eq!(nodes, parse(expected));
+    }
+}
@@ -101,8 +101,98 @@ impl LanguageServer for KymeraLanguageServer {
     }
 }
 
+/// Formats the given files in place, or standard input to standard output
+/// when no files are given. With `--check`, lists the files that would
+/// change instead of writing them; `--edition legacy|v2` selects the
+/// grammar edition, which defaults to legacy. Syntax errors are reported
+/// with labeled snippets of the offending file.
+fn run_fmt(args: &[String]) -> std::process::ExitCode {
+    use std::io::{IsTerminal, Read, Write};
+    use std::process::ExitCode;
+
+    let mut options = kymera_parser::FormatOptions::default();
+    let mut check = false;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--edition" => {
+                options.edition = match args.next().map(String::as_str) {
+                    Some("legacy") => kymera_parser::Edition::Legacy,
+                    Some("v2") => kymera_parser::Edition::V2,
+                    other => {
+                        eprintln!("error: unknown edition {:?}; expected `legacy` or `v2`", other.unwrap_or(""));
+                        return ExitCode::FAILURE;
+                    }
+                };
+            }
+            _ => files.push(arg),
+        }
+    }
+    let renderer = if std::io::stderr().is_terminal() {
+        kymera_parser::Renderer::colored()
+    } else {
+        kymera_parser::Renderer::new()
+    };
+
+    if files.is_empty() {
+        let mut source = String::new();
+        if let Err(err) = std::io::stdin().read_to_string(&mut source) {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+        return match kymera_parser::format(&source, &options) {
+            Ok(formatted) if check && formatted != source => ExitCode::FAILURE,
+            Ok(_) if check => ExitCode::SUCCESS,
+            Ok(formatted) => {
+                let _ = std::io::stdout().write_all(formatted.as_bytes());
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprint!("{}", renderer.render("<stdin>", &source, &err));
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let mut status = ExitCode::SUCCESS;
+    for file in files {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("error: {}: {}", file, err);
+                status = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        match kymera_parser::format(&source, &options) {
+            Ok(formatted) if formatted != source => {
+                if check {
+                    println!("{}", file);
+                    status = ExitCode::FAILURE;
+                } else if let Err(err) = std::fs::write(file, formatted) {
+                    eprintln!("error: {}: {}", file, err);
+                    status = ExitCode::FAILURE;
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                eprint!("{}", renderer.render(file, &source, &err));
+                status = ExitCode::FAILURE;
+            }
+        }
+    }
+    status
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> std::process::ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("fmt") {
+        return run_fmt(&args[1..]);
+    }
+
     tracing_subscriber::fmt::init();
 
     let stdin = tokio::io::stdin();
@@ -114,4 +204,5 @@ async fn main() {
     });
 
     Server::new(stdin, stdout, socket).serve(service).await;
+    std::process::ExitCode::SUCCESS
 }